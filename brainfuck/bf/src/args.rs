@@ -1,5 +1,10 @@
-use brainlib::Settings;
-use clap::{value_parser, Parser, ValueEnum};
+use std::path::PathBuf;
+
+use brainlib::{interpreter::TraceFormat, CellWidth, EofBehavior, Settings, TapeMode};
+use clap::{
+    builder::{PossibleValuesParser, TypedValueParser},
+    value_parser, Parser, ValueEnum,
+};
 
 use super::input::Input;
 
@@ -8,6 +13,46 @@ pub(crate) struct Arguments {
     #[arg(short, long, default_value = "run")]
     pub(crate) target: Target,
 
+    /// Engine used to interpret the code with the run target
+    #[arg(long, default_value = "standard")]
+    pub(crate) engine: EngineKind,
+
+    /// Stop with an error after executing this many (optimized) instructions
+    #[arg(long)]
+    pub(crate) max_steps: Option<u64>,
+
+    /// Memory cap of the undo log used by the debugger, in megabytes
+    #[arg(long, value_name = "MB", default_value_t = 64)]
+    pub(crate) history_cap: usize,
+
+    /// Enter the debugger before executing the first instruction
+    #[arg(long)]
+    pub(crate) pause: bool,
+
+    /// Write the profile to a file as folded stacks, used by flamegraph tools
+    #[arg(long, value_name = "FILE")]
+    pub(crate) folded_stacks: Option<PathBuf>,
+
+    /// Write the coverage to a file in the LCOV format
+    #[arg(long, value_name = "FILE")]
+    pub(crate) lcov: Option<PathBuf>,
+
+    /// Write a record of every executed instruction to a file
+    #[arg(long, value_name = "FILE")]
+    pub(crate) trace: Option<PathBuf>,
+
+    /// Format of the records written with --trace
+    #[arg(long, value_name = "FORMAT", default_value = "json-lines")]
+    pub(crate) trace_format: Trace,
+
+    /// Save the engine state to a file once the execution stops
+    #[arg(long, value_name = "FILE")]
+    pub(crate) save_state: Option<PathBuf>,
+
+    /// Resume the execution from a saved engine state (consumed input is skipped)
+    #[arg(long, value_name = "FILE")]
+    pub(crate) load_state: Option<PathBuf>,
+
     #[command(flatten)]
     pub(crate) input: Input,
 
@@ -20,9 +65,13 @@ impl From<&Arguments> for Settings {
         Self::try_new(
             args.conventions.tape_length,
             args.conventions.strict,
-            args.target == Target::Debug,
+            // Coverage is collected without optimizations, so that every loop is kept.
+            matches!(args.target, Target::Debug | Target::Coverage),
         )
         .unwrap()
+        .with_tape_mode(args.conventions.tape.into())
+        .with_cell_width(CellWidth::from_bits(args.conventions.cell_bits).unwrap())
+        .with_eof_behavior(args.conventions.eof.into())
     }
 }
 
@@ -34,21 +83,148 @@ pub(crate) enum Target {
     /// Run the code in debug mode (use # to set a breakpoint)
     Debug,
 
+    /// Run the code and report the loops which executed the most instructions
+    Profile,
+
+    /// Run the code and show which parts of it were executed
+    Coverage,
+
+    /// Compile the code to native machine code and run it (x86-64 Linux only, interpreted elsewhere)
+    #[cfg(feature = "jit")]
+    Jit,
+
     /// Compile the code to plain WASM text format
     WasmText,
 
     /// Compile the code to WASM text format, using WASI
     WasmWasiText,
+
+    /// Compile the code to plain WASM binary format
+    Wasm,
+
+    /// Compile the code to WASM binary format, using WASI
+    WasmWasi,
+
+    /// Emit a JavaScript loader for the plain WASM binary format
+    WasmLoader,
+
+    /// Emit a JavaScript loader for the WASM binary format, using WASI
+    WasmWasiLoader,
+
+    /// Compile the code to standalone C99 source code
+    C,
+
+    /// Compile the code to a Rust module exposing a run function
+    Rust,
+
+    /// Compile the code to the main.rs of a standalone Rust binary
+    RustMain,
+
+    /// Compile the code to a JavaScript module exposing a run function
+    Js,
+
+    /// Compile the code to textual LLVM IR
+    LlvmIr,
+
+    /// Compile the code to x86-64 Linux assembly for the GNU assembler
+    #[value(name = "x86-64-asm")]
+    X86_64Asm,
+
+    /// Compile the code to a static x86-64 Linux ELF executable
+    Elf,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EngineKind {
+    /// Execute the instructions one by one, supports --trace
+    Standard,
+
+    /// Translate the instructions before running them, which is several times faster
+    Fast,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Tape {
+    /// Allocate all cells up front
+    Fixed,
+
+    /// Grow to the right when needed
+    GrowRight,
+
+    /// Grow in both directions when needed, allowing negative cell numbers
+    GrowBoth,
+}
+
+impl From<Tape> for TapeMode {
+    fn from(tape: Tape) -> Self {
+        match tape {
+            Tape::Fixed => Self::Fixed,
+            Tape::GrowRight => Self::GrowRight,
+            Tape::GrowBoth => Self::GrowBoth,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Trace {
+    /// One JSON object per line
+    JsonLines,
+
+    /// Compact binary records, with numbers stored as LEB128
+    Binary,
+}
+
+impl From<Trace> for TraceFormat {
+    fn from(trace: Trace) -> Self {
+        match trace {
+            Trace::JsonLines => Self::JsonLines,
+            Trace::Binary => Self::Binary,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Eof {
+    /// Store zero in the cell
+    Zero,
+
+    /// Leave the cell unchanged
+    Unchanged,
+
+    /// Store the maximum cell value (255 for 8-bit cells)
+    Max,
+}
+
+impl From<Eof> for EofBehavior {
+    fn from(eof: Eof) -> Self {
+        match eof {
+            Eof::Zero => Self::Zero,
+            Eof::Unchanged => Self::Unchanged,
+            Eof::Max => Self::Max,
+        }
+    }
 }
 
 #[derive(Parser)]
 #[command(next_help_heading = "Conventions")]
 #[group()]
 struct Conventions {
-    /// Count of available memory cells
-    #[arg(long = "length", default_value_t = Settings::DEFAULT_LENGTH, value_parser = value_parser!(u32).range(3..=1_000_000_000))]
+    /// Count of available memory cells (initial count for a growable tape)
+    #[arg(long = "length", default_value_t = Settings::DEFAULT_LENGTH, value_parser = value_parser!(u32).range(3..=Settings::MAX_LENGTH as i64))]
     tape_length: u32,
 
+    /// Whether the tape has a fixed length or grows when needed
+    #[arg(long, default_value = "fixed")]
+    tape: Tape,
+
+    /// Number of bits in a single memory cell
+    #[arg(long = "cell-bits", default_value_t = 8, value_parser = PossibleValuesParser::new(["8", "16", "32"]).map(|bits| bits.parse::<u32>().unwrap()))]
+    cell_bits: u32,
+
+    /// Value stored in a cell when reading past the end of input
+    #[arg(long, default_value = "zero")]
+    eof: Eof,
+
     /// If enabled, stop execution when overflowing a cell or tape index
     #[arg(long)]
     strict: bool,
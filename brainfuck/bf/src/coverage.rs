@@ -0,0 +1,166 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use brainlib::interpreter::{Coverage, Engine, StdIo};
+
+use crate::errors::runtime_error_message;
+
+/// Coverage of the source code, collected from the instructions produced from it.
+struct SourceCoverage<'a> {
+    lines: Vec<&'a str>,
+    /// Highest execution count of the instructions on each line, `None` for lines without code.
+    counts: Vec<Option<u64>>,
+    loops: Vec<LoopBranches>,
+}
+
+/// How many times a loop was entered and skipped, `None` if it was never reached.
+struct LoopBranches {
+    line: usize,
+    col: usize,
+    entered: Option<u64>,
+    skipped: Option<u64>,
+}
+
+pub(crate) fn run_coverage(
+    mut eng: Engine<StdIo, Coverage>,
+    source: &str,
+    source_name: &str,
+    lcov: Option<&Path>,
+) -> Result<(), String> {
+    let result = eng.run();
+    let coverage = SourceCoverage::collect(&eng, source);
+    eprint!("\n{}", coverage.listing());
+
+    if let Some(path) = lcov {
+        File::create(path)
+            .and_then(|file| coverage.write_lcov(BufWriter::new(file), source_name))
+            .map_err(|_| {
+                format!(
+                    "InterpreterError: Could not write LCOV file at path: {}",
+                    path.display()
+                )
+            })?;
+    }
+
+    result.map_err(|e| runtime_error_message(e, &eng, source))
+}
+
+impl<'a> SourceCoverage<'a> {
+    fn collect(eng: &Engine<StdIo, Coverage>, source: &'a str) -> Self {
+        let coverage = eng.observer();
+        let program = eng.program();
+
+        // Lines are split the same way as in `source::line_col`, positions are character indices.
+        let lines = source.split('\n').collect::<Vec<_>>();
+        let line_starts = (source.chars().enumerate())
+            .filter(|(_, c)| *c == '\n')
+            .map(|(pos, _)| pos + 1)
+            .collect::<Vec<_>>();
+        let line_of = |pos: usize| line_starts.partition_point(|&start| start <= pos);
+
+        let mut counts = vec![None; lines.len()];
+        for (pc, &count) in coverage.counts().iter().enumerate() {
+            let Some(span) = program.span(pc) else {
+                continue;
+            };
+            for line in &mut counts[line_of(span.start)..=line_of(span.end - 1)] {
+                *line = Some(line.unwrap_or(0).max(count));
+            }
+        }
+
+        let loops = (coverage.loops().iter())
+            .filter_map(|l| {
+                let pos = program.span(l.start())?.start;
+                let line = line_of(pos);
+                let reached = coverage.is_covered(l.start());
+                Some(LoopBranches {
+                    line,
+                    col: pos - line.checked_sub(1).map_or(0, |i| line_starts[i]),
+                    entered: reached.then_some(l.entered()),
+                    skipped: reached.then_some(l.skipped()),
+                })
+            })
+            .collect();
+
+        Self {
+            lines,
+            counts,
+            loops,
+        }
+    }
+
+    /// Returns the source code, with the execution count of each line and the loops which were never entered.
+    fn listing(&self) -> String {
+        let code_lines = self.counts.iter().flatten().count();
+        let covered_lines = self.counts.iter().flatten().filter(|&&c| c > 0).count();
+        let entered_loops = (self.loops.iter())
+            .filter(|l| l.entered.is_some_and(|n| n > 0))
+            .count();
+
+        let mut listing = format!(
+            "Coverage: Executed {covered_lines} of {code_lines} lines with code, entered {entered_loops} of {} loops.\n",
+            self.loops.len()
+        );
+        for (index, (line, count)) in self.lines.iter().zip(&self.counts).enumerate() {
+            let count = match count {
+                None => "-".to_owned(),
+                Some(0) => "#####".to_owned(),
+                Some(count) => count.to_string(),
+            };
+            listing += &format!("{count:>9} | {line}\n");
+            for l in self.loops.iter().filter(|l| l.line == index) {
+                if l.entered == Some(0) {
+                    listing += &format!(
+                        "{:>9} | {}^ loop was never entered\n",
+                        "",
+                        " ".repeat(l.col)
+                    );
+                }
+            }
+        }
+        listing
+    }
+
+    /// Writes the coverage in the LCOV tracefile format[^1].
+    ///
+    /// Each loop is a block with two branches, entering and skipping the loop.
+    ///
+    /// [^1]: [geninfo(1)](https://github.com/linux-test-project/lcov/blob/master/man/geninfo.1)
+    fn write_lcov(&self, mut write: impl Write, source_name: &str) -> io::Result<()> {
+        writeln!(write, "TN:")?;
+        writeln!(write, "SF:{source_name}")?;
+
+        for (block, l) in self.loops.iter().enumerate() {
+            for (branch, taken) in [l.entered, l.skipped].into_iter().enumerate() {
+                let taken = taken.map_or("-".to_owned(), |n| n.to_string());
+                writeln!(write, "BRDA:{},{block},{branch},{taken}", l.line + 1)?;
+            }
+        }
+        let branches = (self.loops.iter()).flat_map(|l| [l.entered, l.skipped]);
+        writeln!(write, "BRF:{}", 2 * self.loops.len())?;
+        writeln!(
+            write,
+            "BRH:{}",
+            branches.filter(|b| b.is_some_and(|n| n > 0)).count()
+        )?;
+
+        let lines = (self.counts.iter().enumerate())
+            .filter_map(|(index, count)| Some((index + 1, (*count)?)))
+            .collect::<Vec<_>>();
+        for (line, count) in &lines {
+            writeln!(write, "DA:{line},{count}")?;
+        }
+        writeln!(write, "LF:{}", lines.len())?;
+        writeln!(
+            write,
+            "LH:{}",
+            lines.iter().filter(|(_, count)| *count > 0).count()
+        )?;
+
+        writeln!(write, "end_of_record")?;
+        write.flush()
+    }
+}
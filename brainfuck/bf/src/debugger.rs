@@ -1,13 +1,18 @@
-use std::num::NonZeroUsize;
+use std::{num::NonZeroUsize, path::Path};
 
-use brainlib::{interpreter::StdEngine, Instruction};
+use brainlib::{
+    interpreter::{Engine, RuntimeError, StdEngine, StdIo},
+    Instruction, TapeMode,
+};
 use colored::Colorize;
 use indoc::indoc;
 use rustyline::{error::ReadlineError, DefaultEditor};
 
 use crate::{
-    errors::{show_error, CliError},
-    source::{highlight_code, highlight_source},
+    errors::{runtime_error_message, show_error},
+    source::{highlight_code, highlight_source, parse_position, underline_source},
+    state::{load_state, save_state},
+    stops::{Stop, Stops},
 };
 
 enum ReplAction {
@@ -16,11 +21,21 @@ enum ReplAction {
     EndSilently,
 }
 
-pub(crate) fn run_debugger(mut eng: StdEngine, source: &str) -> Result<(), String> {
+/// The engine run by the debugger, which pauses once one of the [`Stops`] is hit.
+type DebugEngine = Engine<StdIo, Stops>;
+
+pub(crate) fn run_debugger(eng: StdEngine, source: &str, pause: bool) -> Result<(), String> {
+    let stops = Stops::new(eng.program(), eng.settings());
+    let mut eng = eng.with_observer(stops);
+
     macro_rules! enter_repl {
         () => {
             match repl(&mut eng, source) {
-                Ok(ReplAction::Resume) => show("Resuming execution..."),
+                Ok(ReplAction::Resume) => {
+                    show("Resuming execution...");
+                    let pc = eng.pc();
+                    eng.observer_mut().resume(pc);
+                },
                 Ok(ReplAction::Quit) => {
                     show("Aborting due to a quit from REPL.");
                     return Ok(());
@@ -31,29 +46,67 @@ pub(crate) fn run_debugger(mut eng: StdEngine, source: &str) -> Result<(), Strin
         };
     }
 
-    while let Some(result) = eng.step() {
-        if let Ok(Instruction::Breakpoint(pos)) = result {
+    if pause {
+        show("Debugger: Paused before the first instruction.");
+        enter_repl!();
+    }
+
+    loop {
+        match eng.run() {
+            Ok(()) => break,
+            Err(RuntimeError::Paused) =>
+                if let Some(stop) = eng.observer_mut().take_stop() {
+                    show_stop(&eng, source, stop);
+                },
+            Err(err) => {
+                show_error(&runtime_error_message(err, &eng, source));
+                show("Debugger: Entering debugger due to a runtime error.");
+            },
+        }
+        enter_repl!();
+    }
+
+    Ok(())
+}
+
+fn show_stop(eng: &DebugEngine, source: &str, stop: Stop) {
+    let (header, pc) = match stop {
+        Stop::Breakpoint { position, defined } => {
+            let label = if defined {
+                "breakpoint defined here"
+            } else {
+                "breakpoint set here"
+            };
             show(
                 highlight_source(
                     "Debugger: Entering debugger due to a breakpoint hit.",
                     source,
-                    pos as usize,
-                    "breakpoint defined here",
+                    position,
+                    label,
                 )
                 .as_str(),
             );
-            enter_repl!();
-        } else if let Err(err) = result {
-            show_error(&err.message(source));
-            show("Debugger: Entering debugger due to a runtime error.");
-            enter_repl!();
-        }
+            return;
+        },
+        Stop::Watch { pc, cell, from, to } => (
+            format!(
+                "Debugger: Entering debugger due to a change of cell #{cell} ({from} -> {to})."
+            ),
+            pc,
+        ),
+        Stop::Condition { pc, condition } => (
+            format!("Debugger: Entering debugger since {condition} became true."),
+            pc,
+        ),
+    };
+    match eng.program().span(pc) {
+        Some(span) =>
+            show(underline_source(&header, source, span, "after this instruction").as_str()),
+        None => show(header.as_str()),
     }
-
-    Ok(())
 }
 
-fn repl(eng: &mut StdEngine, source: &str) -> Result<ReplAction, ReadlineError> {
+fn repl(eng: &mut DebugEngine, source: &str) -> Result<ReplAction, ReadlineError> {
     macro_rules! unwrap_action {
         ($action:expr) => {
             if let Some(action) = $action {
@@ -75,20 +128,99 @@ fn repl(eng: &mut StdEngine, source: &str) -> Result<ReplAction, ReadlineError>
         rl.add_history_entry(&command)?;
         let parts = command.split_whitespace().collect::<Vec<_>>();
         match parts.as_slice() {
+            [":back"] => exec_back(eng, None),
+            [":back", n] => exec_back(eng, Some(n)),
+            [":break", pos] => exec_break(eng, source, pos),
+            [":break-if", condition @ ..] if !condition.is_empty() =>
+                exec_break_if(eng, &condition.concat()),
             [":c" | ":code"] => exec_code(eng),
+            [":clear"] => exec_clear(eng),
             [":h" | ":help"] => exec_help(),
+            [":load", path] => exec_load(eng, source, path),
             [":m" | ":memory"] => exec_memory(eng, None),
             [":m" | ":memory", c] => exec_memory(eng, Some(c)),
             [":r" | ":resume"] => return Ok(ReplAction::Resume),
+            [":rc" | ":reverse-continue"] => exec_reverse_continue(eng, source),
+            [":save", path] => exec_save(eng, path),
             [":s" | ":step"] => unwrap_action!(exec_step(eng, source, None)),
             [":s" | ":step", n] => unwrap_action!(exec_step(eng, source, Some(n))),
+            [":watch", cell] => exec_watch(eng, cell),
             [":q" | ":quit"] => return Ok(ReplAction::Quit),
             _ => show("Invalid command or arguments! Use :h to see all commands."),
         }
     }
 }
 
-fn exec_code(eng: &StdEngine) {
+fn exec_back(eng: &mut DebugEngine, n: Option<&str>) {
+    let Some(n) = n.unwrap_or("1").parse::<NonZeroUsize>().ok() else {
+        show("Invalid number of steps!");
+        return;
+    };
+
+    show(format!("Undoing up to {n} last instructions...").as_str());
+    for _ in 0..n.into() {
+        match eng.step_back() {
+            Some(instr) => show(format!("  {instr}").as_str()),
+            None => {
+                show("No more instructions to undo.");
+                break;
+            },
+        }
+    }
+}
+
+fn exec_reverse_continue(eng: &mut DebugEngine, source: &str) {
+    // The breakpoint which was just hit is skipped, to reach the previous one.
+    let mut first = true;
+    loop {
+        match eng.step_back() {
+            Some(Instruction::Breakpoint(pos)) if !first => {
+                show(
+                    highlight_source(
+                        "Debugger: Stopped at the previous breakpoint.",
+                        source,
+                        pos as usize,
+                        "breakpoint defined here",
+                    )
+                    .as_str(),
+                );
+                return;
+            },
+            Some(_) => first = false,
+            None => {
+                show("Debugger: Reached the start of the recorded history.");
+                return;
+            },
+        }
+    }
+}
+
+fn exec_break(eng: &mut DebugEngine, source: &str, pos: &str) {
+    let Some(pos) = parse_position(source, pos) else {
+        show("Invalid source position!");
+        return;
+    };
+    if eng.observer_mut().add_breakpoint(pos) {
+        show(highlight_source("Breakpoint set.", source, pos, "breakpoint set here").as_str());
+    } else {
+        show("No instruction at this source position!");
+    }
+}
+
+fn exec_break_if(eng: &mut DebugEngine, condition: &str) {
+    if eng.observer_mut().add_condition(condition) {
+        show(format!("Pausing once {condition} becomes true.").as_str());
+    } else {
+        show("Invalid condition! Use a condition such as cell[3]==10.");
+    }
+}
+
+fn exec_clear(eng: &mut DebugEngine) {
+    eng.observer_mut().clear();
+    show("Removed all breakpoints, watches and conditions.");
+}
+
+fn exec_code(eng: &DebugEngine) {
     let source = eng
         .program()
         .code()
@@ -102,45 +234,105 @@ fn exec_code(eng: &StdEngine) {
 fn exec_help() {
     show(indoc! {"
         Available commands:
+          :back <N>          Undo up to N last instructions [default: 1]
+          :break <P>         Pause before the instruction at source position P (line:col or index)
+          :break-if <E>      Pause once condition E becomes true (e.g. cell[3]==10)
           :c, :code          Display the surrounding instructions
+          :clear             Remove all breakpoints, watches and conditions added above
+          :load <F>          Restore the engine state from file F (input is not rewound)
           :m, :memory <C>    Display memory around cell C [default: pointer location]
           :r, :resume        Resume the execution
+          :rc, :reverse-continue
+                             Undo instructions until the previous breakpoint
+          :save <F>          Save the engine state to file F
           :s, :step <N>      Execute up to N next instructions [default: 1]
+          :watch <C>         Pause once the value of cell C changes
           :q, :quit          Abort the execution
           :h, :help          Display the list of available commands"});
 }
 
-fn exec_memory(eng: &StdEngine, c: Option<&str>) {
-    let c = c.unwrap_or(&eng.pointer().to_string()).parse::<u32>().ok();
+fn exec_memory(eng: &DebugEngine, c: Option<&str>) {
+    let c = c.unwrap_or(&eng.position().to_string()).parse::<i32>().ok();
     let Some(c) = c else {
         show("Invalid cell number!");
         return;
     };
-    if c >= eng.settings().tape_length() {
+    let settings = eng.settings();
+    let in_range = match settings.tape_mode() {
+        TapeMode::Fixed => (0..settings.tape_length() as i32).contains(&c),
+        TapeMode::GrowRight => c >= 0,
+        TapeMode::GrowBoth => true,
+    };
+    if !in_range {
         show("Cell number out of range!");
         return;
     }
 
+    let width = settings.cell_width().max().to_string().len();
     let cell = |offset: i32| -> String {
-        let i = (c as i32 + offset).rem_euclid(eng.settings().tape_length() as i32) as usize;
-        format!("[{:0>3}]", eng.memory()[i])
+        let position = i64::from(c) + i64::from(offset);
+        let value = match settings.tape_mode() {
+            TapeMode::Fixed => {
+                let i = position.rem_euclid(settings.tape_length().into()) as usize;
+                Some(eng.memory()[i])
+            },
+            TapeMode::GrowRight if position < 0 => None,
+            // Cells which were not allocated yet are zero.
+            TapeMode::GrowRight | TapeMode::GrowBoth => Some(
+                usize::try_from(position + i64::from(eng.origin()))
+                    .ok()
+                    .and_then(|i| eng.memory().get(i).copied())
+                    .unwrap_or(0),
+            ),
+        };
+        match value {
+            Some(value) => format!("[{value:0>width$}]"),
+            None => format!("[{}]", "-".repeat(width)),
+        }
     };
 
     show(
         format!(
-            "  {} {} {} {} {}\n                ^ #{}",
+            "  {} {} {} {} {}\n{}^ #{}",
             cell(-2),
             cell(-1),
             cell(0),
             cell(1),
             cell(2),
+            " ".repeat(2 + 2 * (width + 3) + (width + 2) / 2),
             c,
         )
         .as_str(),
     );
 }
 
-fn exec_step(eng: &mut StdEngine, source: &str, n: Option<&str>) -> Option<ReplAction> {
+fn exec_save(eng: &DebugEngine, path: &str) {
+    match save_state(eng, Path::new(path)) {
+        Ok(()) => show(format!("Saved the engine state to {path}.").as_str()),
+        Err(err) => show_error(&err),
+    }
+}
+
+fn exec_load(eng: &mut DebugEngine, source: &str, path: &str) {
+    match load_state(eng, Path::new(path), source) {
+        Ok(()) => show(format!("Restored the engine state from {path}.").as_str()),
+        Err(err) => show_error(&err),
+    }
+}
+
+fn exec_watch(eng: &mut DebugEngine, cell: &str) {
+    let Some(cell) = cell.parse::<i32>().ok() else {
+        show("Invalid cell number!");
+        return;
+    };
+    if eng.observer_mut().add_watch(cell) {
+        show(format!("Watching cell #{cell}.").as_str());
+    } else {
+        show("Cell number out of range!");
+    }
+}
+
+fn exec_step(eng: &mut DebugEngine, source: &str, n: Option<&str>) -> Option<ReplAction> {
     let Some(n) = n.unwrap_or("1").parse::<NonZeroUsize>().ok() else {
         show("Invalid number of steps!");
         return None;
@@ -151,16 +343,27 @@ fn exec_step(eng: &mut StdEngine, source: &str, n: Option<&str>) -> Option<ReplA
         match eng.step() {
             Some(Ok(instr)) => show(format!("  {instr}").as_str()),
             Some(Err(err)) => {
-                show_error(&err.message(source));
+                show_error(&runtime_error_message(err, eng, source));
                 break;
             },
-            None => return Some(ReplAction::EndSilently),
+            None => {
+                flush_output(eng, source);
+                return Some(ReplAction::EndSilently);
+            },
         }
     }
 
+    flush_output(eng, source);
     None
 }
 
+/// Shows the buffered output of the program, before the REPL prints anything else.
+fn flush_output(eng: &mut DebugEngine, source: &str) {
+    if let Err(err) = eng.flush() {
+        show_error(&runtime_error_message(err, eng, source));
+    }
+}
+
 fn show(message: impl Colorize) {
     eprintln!("{}", message.yellow());
 }
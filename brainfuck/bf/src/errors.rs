@@ -1,7 +1,10 @@
-use brainlib::{interpreter::RuntimeError, ParseError};
+use brainlib::{
+    interpreter::{Engine, EngineObserver, RuntimeError, StateError, StdIo},
+    ParseError,
+};
 use colored::Colorize;
 
-use crate::source::highlight_source;
+use crate::source::{highlight_source, underline_source};
 
 pub(crate) trait CliError {
     fn message(&self, source: &str) -> String;
@@ -29,11 +32,19 @@ impl CliError for ParseError {
 impl CliError for RuntimeError {
     fn message(&self, _: &str) -> String {
         match self {
-            RuntimeError::InputError => "RuntimeError: Could not read from input.".to_owned(),
-            RuntimeError::OutputError => "RuntimeError: Could not write to output.".to_owned(),
+            RuntimeError::InputError(err) =>
+                format!("RuntimeError: Could not read from input.\nThe underlying error was: {err}."),
+            RuntimeError::OutputError(err) =>
+                format!("RuntimeError: Could not write to output.\nThe underlying error was: {err}."),
             RuntimeError::TapeOverflow { from, by } => format!(
                 "RuntimeError: Tape address overflowed.\nAttempted to change pointer equal to {from} by {by}."
             ),
+            RuntimeError::OutOfFuel =>
+                "RuntimeError: Step limit exceeded.\nThe program did not halt within the allowed number of steps.".to_owned(),
+            RuntimeError::DeadlineExceeded =>
+                "RuntimeError: Deadline exceeded.\nThe program did not halt within the allowed time.".to_owned(),
+            RuntimeError::Paused =>
+                "RuntimeError: Execution paused.\nThe program was paused before its next instruction.".to_owned(),
             RuntimeError::CellOverflow { at, from, by } => format!(
                 "RuntimeError: Cell overflowed.\nAttempted to change a cell equal to {from} by {by} at address {at}."
             ),
@@ -41,6 +52,39 @@ impl CliError for RuntimeError {
     }
 }
 
+impl CliError for StateError {
+    fn message(&self, _: &str) -> String {
+        match self {
+            StateError::ProgramMismatch =>
+                "StateError: The saved state does not match the program.".to_owned(),
+            StateError::SettingsMismatch =>
+                "StateError: The saved state does not match the conventions.".to_owned(),
+        }
+    }
+}
+
+/// Returns the message of a [`RuntimeError`] returned by the engine,
+/// underlining the source code of the instruction which caused it.
+pub(crate) fn runtime_error_message(
+    err: RuntimeError,
+    eng: &Engine<StdIo, impl EngineObserver>,
+    source: &str,
+) -> String {
+    let message = err.message(source);
+    let span = match err {
+        // The engine stopped before executing the next instruction, none of them is at fault.
+        RuntimeError::OutOfFuel | RuntimeError::DeadlineExceeded | RuntimeError::Paused => None,
+        _ => eng
+            .pc()
+            .checked_sub(1)
+            .and_then(|pc| eng.program().span(pc)),
+    };
+    match span {
+        Some(span) => underline_source(&message, source, span, "caused by this code"),
+        None => message,
+    }
+}
+
 pub(crate) fn show_error(message: &str) {
     eprintln!("\n{}", message.red());
 }
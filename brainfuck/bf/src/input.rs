@@ -21,6 +21,15 @@ pub(crate) struct Input {
 }
 
 impl Input {
+    /// Returns the name of the source, used in reports.
+    pub(crate) fn name(&self) -> String {
+        match &self.file {
+            Some(path) => path.display().to_string(),
+            None if self.stdin => "<stdin>".to_owned(),
+            None => "<code>".to_owned(),
+        }
+    }
+
     pub(crate) fn get_source(self) -> Result<String, String> {
         match (self.file, self.code, self.stdin) {
             (Some(path), None, false) => fs::read_to_string(&path).map_err(|_| {
@@ -1,20 +1,36 @@
-use std::{io::stdout, process::ExitCode};
+use std::{io::stdout, path::Path, process::ExitCode};
 
-use args::{Arguments, Target};
+use args::{Arguments, EngineKind, Target};
+#[cfg(feature = "jit")]
+use brainlib::jit::JitEngine;
 use brainlib::{
-    interpreter::Engine,
+    c::CSource,
+    interpreter::{Engine, EngineObserver, FastEngine, RuntimeError, StdEngine, StdIo},
+    js::JsSource,
+    llvm::LlvmModule,
+    native::NativeModule,
+    rust::{RustSource, RustTarget},
     wasm::{WasmModule, WasmTarget},
     Program, Settings,
 };
 use clap::Parser;
+use coverage::run_coverage;
 use debugger::run_debugger;
-use errors::{show_error, CliError};
+use errors::{runtime_error_message, show_error, CliError};
+use profiler::run_profiler;
+use state::{load_state, save_state, skip_consumed_input};
+use trace::{finish_trace, trace_to_file};
 
 mod args;
+mod coverage;
 mod debugger;
 mod errors;
 mod input;
+mod profiler;
 mod source;
+mod state;
+mod stops;
+mod trace;
 
 fn main() -> ExitCode {
     match run() {
@@ -29,19 +45,165 @@ fn main() -> ExitCode {
 fn run() -> Result<(), String> {
     let args = Arguments::parse();
     let settings = Settings::from(&args);
+    let source_name = args.input.name();
     let source = args.input.get_source()?;
     let program = Program::compile(&source, &settings).map_err(|e| e.message(&source))?;
+    if args.engine == EngineKind::Fast && args.target != Target::Run {
+        return Err(
+            "InterpreterError: The fast engine can only be used with the run target.".into(),
+        );
+    }
+    if args.engine == EngineKind::Fast && args.trace.is_some() {
+        return Err("InterpreterError: The fast engine does not support --trace.".into());
+    }
+    #[cfg(feature = "jit")]
+    if args.target == Target::Jit && args.max_steps.is_some() {
+        return Err("InterpreterError: The jit target does not support --max-steps.".into());
+    }
 
     match args.target {
-        Target::Debug => run_debugger(Engine::new_std(program, settings), &source),
-        Target::Run => Engine::new_std(program, settings)
-            .run()
-            .map_err(|e| e.message(&source)),
+        Target::Debug => run_debugger(
+            new_engine(program, settings, args.load_state.as_deref(), &source)?
+                .with_history(args.history_cap.saturating_mul(1024 * 1024)),
+            &source,
+            args.pause,
+        ),
+        Target::Profile => run_profiler(
+            new_engine(program, settings, args.load_state.as_deref(), &source)?.with_profiling(),
+            &source,
+            args.folded_stacks.as_deref(),
+        ),
+        Target::Coverage => run_coverage(
+            new_engine(program, settings, args.load_state.as_deref(), &source)?.with_coverage(),
+            &source,
+            &source_name,
+            args.lcov.as_deref(),
+        ),
+        Target::Run => {
+            let mut engine = new_engine(program, settings, args.load_state.as_deref(), &source)?;
+            let save_state_to = args.save_state.as_deref();
+            match &args.trace {
+                None if args.engine == EngineKind::Fast =>
+                    run_fast_engine(engine, args.max_steps, save_state_to, &source),
+                Some(path) => {
+                    let mut engine = trace_to_file(engine, path, args.trace_format.into())?;
+                    let result = run_engine(&mut engine, args.max_steps, save_state_to, &source);
+                    result.and(finish_trace(&mut engine, path))
+                },
+                None => run_engine(&mut engine, args.max_steps, save_state_to, &source),
+            }
+        },
+        #[cfg(feature = "jit")]
+        Target::Jit => run_jit_engine(
+            new_engine(program, settings, args.load_state.as_deref(), &source)?,
+            args.save_state.as_deref(),
+            &source,
+        ),
         Target::WasmText => WasmModule::compile_from(&program, WasmTarget::Normal, &settings)
             .emit_wat(stdout())
             .map_err(|_| "Error: Could not write to stdout.".into()),
         Target::WasmWasiText => WasmModule::compile_from(&program, WasmTarget::Wasi, &settings)
             .emit_wat(stdout())
             .map_err(|_| "Error: Could not write to stdout.".into()),
+        Target::Wasm => WasmModule::compile_from(&program, WasmTarget::Normal, &settings)
+            .emit_wasm(stdout())
+            .map_err(|_| "Error: Could not write to stdout.".into()),
+        Target::WasmWasi => WasmModule::compile_from(&program, WasmTarget::Wasi, &settings)
+            .emit_wasm(stdout())
+            .map_err(|_| "Error: Could not write to stdout.".into()),
+        Target::WasmLoader => WasmModule::compile_from(&program, WasmTarget::Normal, &settings)
+            .emit_js_loader(stdout())
+            .map_err(|_| "Error: Could not write to stdout.".into()),
+        Target::WasmWasiLoader => WasmModule::compile_from(&program, WasmTarget::Wasi, &settings)
+            .emit_js_loader(stdout())
+            .map_err(|_| "Error: Could not write to stdout.".into()),
+        Target::C => CSource::compile_from(&program, &settings)
+            .emit(stdout())
+            .map_err(|_| "Error: Could not write to stdout.".into()),
+        Target::Rust => RustSource::compile_from(&program, RustTarget::Module, &settings)
+            .emit(stdout())
+            .map_err(|_| "Error: Could not write to stdout.".into()),
+        Target::RustMain => RustSource::compile_from(&program, RustTarget::Binary, &settings)
+            .emit(stdout())
+            .map_err(|_| "Error: Could not write to stdout.".into()),
+        Target::Js => JsSource::compile_from(&program, &settings)
+            .emit(stdout())
+            .map_err(|_| "Error: Could not write to stdout.".into()),
+        Target::LlvmIr => LlvmModule::compile_from(&program, &settings)
+            .emit_ll(stdout())
+            .map_err(|_| "Error: Could not write to stdout.".into()),
+        Target::X86_64Asm => NativeModule::compile_from(&program, &settings)
+            .emit_asm(stdout())
+            .map_err(|_| "Error: Could not write to stdout.".into()),
+        Target::Elf => NativeModule::compile_from(&program, &settings)
+            .emit_elf(stdout())
+            .map_err(|_| "Error: Could not write to stdout.".into()),
+    }
+}
+
+fn run_engine(
+    engine: &mut Engine<StdIo, impl EngineObserver>,
+    max_steps: Option<u64>,
+    save_state_to: Option<&Path>,
+    source: &str,
+) -> Result<(), String> {
+    let result = match max_steps {
+        Some(max_steps) => engine.run_with_fuel(max_steps),
+        None => engine.run(),
+    };
+    finish_run(result, engine, save_state_to, source)
+}
+
+/// Same as [`run_engine`], but uses a [`FastEngine`].
+fn run_fast_engine(
+    engine: StdEngine,
+    max_steps: Option<u64>,
+    save_state_to: Option<&Path>,
+    source: &str,
+) -> Result<(), String> {
+    let mut engine = FastEngine::from(engine);
+    let result = match max_steps {
+        Some(max_steps) => engine.run_with_fuel(max_steps),
+        None => engine.run(),
+    };
+    finish_run(result, &Engine::from(engine), save_state_to, source)
+}
+
+/// Same as [`run_engine`], but compiles the program to native code with a [`JitEngine`].
+#[cfg(feature = "jit")]
+fn run_jit_engine(
+    engine: StdEngine,
+    save_state_to: Option<&Path>,
+    source: &str,
+) -> Result<(), String> {
+    let mut engine = JitEngine::from(engine);
+    let result = engine.run();
+    finish_run(result, &Engine::from(engine), save_state_to, source)
+}
+
+/// Saves the state of the stopped engine if requested, and reports the error which stopped it.
+fn finish_run(
+    result: Result<(), RuntimeError>,
+    engine: &Engine<StdIo, impl EngineObserver>,
+    save_state_to: Option<&Path>,
+    source: &str,
+) -> Result<(), String> {
+    if let Some(path) = save_state_to {
+        save_state(engine, path)?;
+    }
+    result.map_err(|e| runtime_error_message(e, engine, source))
+}
+
+fn new_engine(
+    program: Program,
+    settings: Settings,
+    load_state_from: Option<&Path>,
+    source: &str,
+) -> Result<StdEngine, String> {
+    let mut engine = Engine::new_std(program, settings);
+    if let Some(path) = load_state_from {
+        load_state(&mut engine, path, source)?;
+        skip_consumed_input(&engine)?;
     }
+    Ok(engine)
 }
@@ -0,0 +1,60 @@
+use std::{fs::File, io::BufWriter, path::Path};
+
+use brainlib::interpreter::{Engine, LoopProfile, Profile, StdIo};
+
+use crate::{
+    errors::runtime_error_message,
+    source::{highlight_code, line_col},
+};
+
+/// Number of the hottest loops which are included in the report.
+const REPORT_LENGTH: usize = 10;
+
+pub(crate) fn run_profiler(
+    mut eng: Engine<StdIo, Profile>,
+    source: &str,
+    folded_stacks: Option<&Path>,
+) -> Result<(), String> {
+    let result = eng.run();
+    let profile = eng.observer();
+    let position = |l: &LoopProfile| eng.program().span(l.start()).map_or(0, |span| span.start);
+
+    let total = profile.total();
+    let mut loops = profile.loops();
+    loops.retain(|l| l.iterations() > 0);
+    loops.sort_by_key(|l| u64::MAX - l.steps());
+
+    if loops.is_empty() {
+        eprintln!("\nProfile: Executed {total} instructions, no loop was entered.");
+    } else {
+        eprintln!("\nProfile: Executed {total} instructions, the hottest loops were:");
+    }
+    for (rank, l) in loops.iter().take(REPORT_LENGTH).enumerate() {
+        let (line, col) = line_col(source, position(l));
+        eprintln!(
+            "#{} at {line}:{col}: {:.2}% of instructions ({}), {} iterations\n{}",
+            rank + 1,
+            l.steps() as f64 * 100.0 / total as f64,
+            l.steps(),
+            l.iterations(),
+            highlight_code(source, position(l), &format!("loop #{}", rank + 1)),
+        );
+    }
+
+    if let Some(path) = folded_stacks {
+        let frame = |l: &LoopProfile| {
+            let (line, col) = line_col(source, position(l));
+            format!("loop@{line}:{col}")
+        };
+        File::create(path)
+            .and_then(|file| profile.write_folded(BufWriter::new(file), frame))
+            .map_err(|_| {
+                format!(
+                    "InterpreterError: Could not write folded stacks file at path: {}",
+                    path.display()
+                )
+            })?;
+    }
+
+    result.map_err(|e| runtime_error_message(e, &eng, source))
+}
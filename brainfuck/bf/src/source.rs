@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 pub(crate) fn highlight_source(header: &str, source: &str, pos: usize, message: &str) -> String {
     let (line, col) = line_col(source, pos);
     format!(
@@ -11,7 +13,28 @@ pub(crate) fn highlight_code(source: &str, pos: usize, message: &str) -> String
     format!("  | {padded}\n  |      ^ {message}")
 }
 
-fn line_col(source: &str, pos: usize) -> (usize, usize) {
+/// Same as [`highlight_source`], but underlines a range of characters.
+pub(crate) fn underline_source(
+    header: &str,
+    source: &str,
+    span: Range<usize>,
+    message: &str,
+) -> String {
+    // Long spans are cut, so that the surrounding code still fits on the line.
+    let end = span.end.clamp(span.start + 1, span.start + 40);
+    let padded = format!("     {source}      ").chars().collect::<Vec<_>>();
+    let code = padded[span.start..end + 10]
+        .iter()
+        .collect::<String>()
+        .replace('\n', "␤");
+    let (line, col) = line_col(source, span.start);
+    format!(
+        "{header}\n  | {code}\n  |      {} {message}\n  |        at {line}:{col}",
+        "^".repeat(end - span.start)
+    )
+}
+
+pub(crate) fn line_col(source: &str, pos: usize) -> (usize, usize) {
     let mut line = 1;
     let mut col = 1;
     for c in source[..pos].chars() {
@@ -25,6 +48,30 @@ fn line_col(source: &str, pos: usize) -> (usize, usize) {
     (line, col)
 }
 
+/// Parses a source position, given either as `line:col` or as a character index.
+pub(crate) fn parse_position(source: &str, text: &str) -> Option<usize> {
+    let Some((line, col)) = text.split_once(':') else {
+        return text
+            .parse()
+            .ok()
+            .filter(|&pos| pos < source.chars().count());
+    };
+    let target = (line.parse::<usize>().ok()?, col.parse::<usize>().ok()?);
+    let mut current = (1, 1);
+    for (pos, c) in source.chars().enumerate() {
+        if current == target {
+            return Some(pos);
+        }
+        // Same counting as in `line_col`.
+        if c == '\n' {
+            current = (current.0 + 1, 1);
+        } else if !c.is_control() {
+            current.1 += 1;
+        }
+    }
+    None
+}
+
 #[cfg(test)]
 mod tests {
     mod highlight_source {
@@ -50,6 +97,24 @@ mod tests {
         }
     }
 
+    mod underline_source {
+        use indoc::indoc;
+
+        use super::super::underline_source;
+
+        #[test]
+        fn underlines_whole_span() {
+            assert_eq!(
+                underline_source("Error: My error message.", "+++\n>[-]<", 5..8, "message"),
+                indoc! {"
+                    Error: My error message.
+                      | +++␤>[-]<    
+                      |      ^^^ message
+                      |        at 2:2"}
+            )
+        }
+    }
+
     mod line_col {
         use super::super::line_col;
 
@@ -78,4 +143,24 @@ mod tests {
             assert_eq!(line_col("a\n\rb", 3), (2, 1));
         }
     }
+
+    mod parse_position {
+        use super::super::parse_position;
+
+        #[test]
+        fn accepts_character_index() {
+            assert_eq!(parse_position("+[-]", "2"), Some(2));
+        }
+
+        #[test]
+        fn accepts_line_and_column() {
+            assert_eq!(parse_position("a\nbcd\n+-", "3:2"), Some(7));
+        }
+
+        #[test]
+        fn rejects_position_outside_of_source() {
+            assert_eq!(parse_position("+-", "2"), None);
+            assert_eq!(parse_position("+-", "2:1"), None);
+        }
+    }
 }
@@ -0,0 +1,54 @@
+use std::{
+    fs::File,
+    io::{self, stdin, Read},
+    path::Path,
+};
+
+use brainlib::interpreter::{Engine, EngineObserver, EngineState, StdEngine, StdIo};
+
+use crate::errors::CliError;
+
+/// Restores the engine state from the file, without skipping any input.
+pub(crate) fn load_state(
+    eng: &mut Engine<StdIo, impl EngineObserver>,
+    path: &Path,
+    source: &str,
+) -> Result<(), String> {
+    let state = File::open(path)
+        .and_then(EngineState::read_from)
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::InvalidData => format!(
+                "InterpreterError: Invalid state file at path: {} ({err})",
+                path.display()
+            ),
+            _ => format!(
+                "InterpreterError: Could not read state file at path: {}",
+                path.display()
+            ),
+        })?;
+    eng.restore(state).map_err(|e| e.message(source))
+}
+
+/// Skips the input consumed before the engine state was saved.
+pub(crate) fn skip_consumed_input(eng: &StdEngine) -> Result<(), String> {
+    io::copy(
+        &mut stdin().lock().take(eng.input_position()),
+        &mut io::sink(),
+    )
+    .map(|_| ())
+    .map_err(|_| "InterpreterError: Unexpected error while reading stdin.".to_owned())
+}
+
+pub(crate) fn save_state(
+    eng: &Engine<StdIo, impl EngineObserver>,
+    path: &Path,
+) -> Result<(), String> {
+    File::create(path)
+        .and_then(|file| eng.snapshot().write_to(file))
+        .map_err(|_| {
+            format!(
+                "InterpreterError: Could not write state file at path: {}",
+                path.display()
+            )
+        })
+}
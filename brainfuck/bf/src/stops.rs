@@ -0,0 +1,251 @@
+use std::{
+    collections::BTreeSet,
+    fmt::{self, Display},
+};
+
+use brainlib::{
+    interpreter::{EngineObserver, EngineView},
+    Instruction, Program, Settings, TapeMode,
+};
+
+/// Breakpoints, watched cells and conditions added from the debugger REPL.
+///
+/// They are checked by the engine as its observer, which pauses the execution once one of them is hit.
+pub(crate) struct Stops {
+    /// The source position of each instruction, which breakpoints are set on.
+    positions: Vec<Option<usize>>,
+    settings: Settings,
+    breakpoints: BTreeSet<usize>,
+    watches: BTreeSet<i32>,
+    conditions: Vec<Condition>,
+    /// The instruction being executed, which caused the pending stop (if any).
+    pc: usize,
+    stop: Option<Stop>,
+    /// The instruction at which the execution was resumed, so that it is not stopped at again.
+    resumed_at: Option<usize>,
+}
+
+/// The reason for pausing the execution.
+pub(crate) enum Stop {
+    /// A breakpoint before an instruction, or a breakpoint instruction of the code.
+    Breakpoint {
+        position: usize,
+        defined: bool,
+    },
+    Watch {
+        pc: usize,
+        cell: i32,
+        from: u32,
+        to: u32,
+    },
+    Condition {
+        pc: usize,
+        condition: String,
+    },
+}
+
+struct Condition {
+    cell: i32,
+    comparison: Comparison,
+    value: u32,
+    /// Whether the condition held after the last change, so that it only stops once it becomes true.
+    held: bool,
+}
+
+#[derive(Clone, Copy)]
+enum Comparison {
+    Eq,
+    Ne,
+    Le,
+    Ge,
+    Lt,
+    Gt,
+}
+
+impl Stops {
+    pub(crate) fn new(program: &Program, settings: &Settings) -> Self {
+        Self {
+            positions: (0..program.len())
+                .map(|pc| program.span(pc).map(|span| span.start))
+                .collect(),
+            settings: *settings,
+            breakpoints: BTreeSet::new(),
+            watches: BTreeSet::new(),
+            conditions: Vec::new(),
+            pc: 0,
+            stop: None,
+            resumed_at: None,
+        }
+    }
+
+    /// Pauses before the instruction at the given source position.
+    ///
+    /// Returns `false` if there is no instruction at that position.
+    pub(crate) fn add_breakpoint(&mut self, pos: usize) -> bool {
+        let exists = self.positions.contains(&Some(pos));
+        if exists {
+            self.breakpoints.insert(pos);
+        }
+        exists
+    }
+
+    /// Pauses after the value of the given cell changes.
+    ///
+    /// Returns `false` if the tape can never contain such a cell.
+    pub(crate) fn add_watch(&mut self, cell: i32) -> bool {
+        let exists = self.exists(cell);
+        if exists {
+            self.watches.insert(cell);
+        }
+        exists
+    }
+
+    /// Pauses after a condition such as `cell[3]==10` becomes true.
+    ///
+    /// Returns `false` if the condition is malformed or refers to a nonexistent cell.
+    pub(crate) fn add_condition(&mut self, text: &str) -> bool {
+        let Some(condition) = Condition::parse(text).filter(|c| self.exists(c.cell)) else {
+            return false;
+        };
+        // Whether it holds is checked once the execution is resumed.
+        self.conditions.push(condition);
+        true
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.breakpoints.clear();
+        self.watches.clear();
+        self.conditions.clear();
+    }
+
+    /// Returns the reason for the last pause, if it was requested by a stop.
+    pub(crate) fn take_stop(&mut self) -> Option<Stop> {
+        self.stop.take()
+    }
+
+    /// Prepares for resuming the execution before the instruction at `pc`.
+    pub(crate) fn resume(&mut self, pc: usize) {
+        // Anything which happened in the REPL, such as stepping, is not a reason to pause.
+        self.stop = None;
+        self.resumed_at = Some(pc);
+    }
+
+    fn exists(&self, cell: i32) -> bool {
+        match self.settings.tape_mode() {
+            TapeMode::Fixed => (0..self.settings.tape_length() as i32).contains(&cell),
+            TapeMode::GrowRight => cell >= 0,
+            TapeMode::GrowBoth => true,
+        }
+    }
+}
+
+impl EngineObserver for Stops {
+    fn before_instruction(&mut self, pc: usize, _: Instruction, _: EngineView<'_>) {
+        self.pc = pc;
+    }
+
+    fn on_write(&mut self, position: i32, from: u32, to: u32) {
+        if from != to && self.watches.contains(&position) && self.stop.is_none() {
+            self.stop = Some(Stop::Watch {
+                pc: self.pc,
+                cell: position,
+                from,
+                to,
+            });
+        }
+        for condition in &mut self.conditions {
+            if condition.cell != position {
+                continue;
+            }
+            let held = condition.holds(to);
+            if held && !condition.held && self.stop.is_none() {
+                self.stop = Some(Stop::Condition {
+                    pc: self.pc,
+                    condition: condition.to_string(),
+                });
+            }
+            condition.held = held;
+        }
+    }
+
+    fn on_breakpoint(&mut self, _: usize, position: u32) {
+        self.stop.get_or_insert(Stop::Breakpoint {
+            position: position as usize,
+            defined: true,
+        });
+    }
+
+    fn should_pause(&mut self, view: EngineView<'_>) -> bool {
+        if let Some(pc) = self.resumed_at.take() {
+            // The cells may have been changed from the REPL.
+            for condition in &mut self.conditions {
+                condition.held = view
+                    .cell(condition.cell)
+                    .is_some_and(|v| condition.holds(v));
+            }
+            if pc == view.pc() {
+                return false;
+            }
+        }
+        if self.stop.is_none() {
+            if let Some(&Some(position)) = self.positions.get(view.pc()) {
+                if self.breakpoints.contains(&position) {
+                    self.stop = Some(Stop::Breakpoint {
+                        position,
+                        defined: false,
+                    });
+                }
+            }
+        }
+        self.stop.is_some()
+    }
+}
+
+impl Condition {
+    fn parse(text: &str) -> Option<Self> {
+        let (cell, rest) = text.strip_prefix("cell[")?.split_once(']')?;
+        let (comparison, value) = [
+            ("==", Comparison::Eq),
+            ("!=", Comparison::Ne),
+            ("<=", Comparison::Le),
+            (">=", Comparison::Ge),
+            ("<", Comparison::Lt),
+            (">", Comparison::Gt),
+        ]
+        .into_iter()
+        .find_map(|(op, comparison)| Some((comparison, rest.strip_prefix(op)?)))?;
+
+        Some(Self {
+            cell: cell.trim().parse().ok()?,
+            comparison,
+            value: value.trim().parse().ok()?,
+            held: false,
+        })
+    }
+
+    #[must_use]
+    fn holds(&self, cell: u32) -> bool {
+        match self.comparison {
+            Comparison::Eq => cell == self.value,
+            Comparison::Ne => cell != self.value,
+            Comparison::Le => cell <= self.value,
+            Comparison::Ge => cell >= self.value,
+            Comparison::Lt => cell < self.value,
+            Comparison::Gt => cell > self.value,
+        }
+    }
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self.comparison {
+            Comparison::Eq => "==",
+            Comparison::Ne => "!=",
+            Comparison::Le => "<=",
+            Comparison::Ge => ">=",
+            Comparison::Lt => "<",
+            Comparison::Gt => ">",
+        };
+        write!(f, "cell[{}]{op}{}", self.cell, self.value)
+    }
+}
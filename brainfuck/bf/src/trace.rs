@@ -0,0 +1,27 @@
+use std::{fs::File, io::BufWriter, path::Path};
+
+use brainlib::interpreter::{Engine, StdEngine, StdIo, TraceFormat, Tracer};
+
+type TracedEngine = Engine<StdIo, Tracer<BufWriter<File>>>;
+
+/// Returns the engine with tracing enabled, writing the records to the file.
+pub(crate) fn trace_to_file(
+    eng: StdEngine,
+    path: &Path,
+    format: TraceFormat,
+) -> Result<TracedEngine, String> {
+    let file = File::create(path).map_err(|_| write_error(path))?;
+    Ok(eng.with_tracing(BufWriter::new(file), format))
+}
+
+/// Writes the remaining records to the file, reporting any error which occurred while tracing.
+pub(crate) fn finish_trace(eng: &mut TracedEngine, path: &Path) -> Result<(), String> {
+    eng.observer_mut().flush().map_err(|_| write_error(path))
+}
+
+fn write_error(path: &Path) -> String {
+    format!(
+        "InterpreterError: Could not write trace file at path: {}",
+        path.display()
+    )
+}
@@ -0,0 +1,64 @@
+use std::{fs, process::Command};
+
+use predicates::str;
+use util::{backend_tests, bf, emit, Compiled};
+
+mod util;
+
+/// Emits the code as C with the given arguments, and builds it with the system C compiler.
+fn build(name: &str, args: &[&str]) -> Compiled {
+    let source = emit(name, "c", args, "c");
+    let binary = source.with_extension("out");
+    let status = Command::new("cc")
+        .args([
+            "-std=c99",
+            "-pedantic",
+            "-Wall",
+            "-Wextra",
+            "-Werror",
+            "-O2",
+            "-o",
+        ])
+        .arg(&binary)
+        .arg(&source)
+        .status()
+        .expect("Unable to run the C compiler!");
+    fs::remove_file(&source).unwrap();
+    assert!(status.success());
+    Compiled::binary(binary)
+}
+
+backend_tests!(build);
+
+#[test]
+fn emits_c_source() {
+    bf().arg("-c")
+        .arg(",[.,]")
+        .arg("-t")
+        .arg("c")
+        .assert()
+        .success()
+        .stdout(str::starts_with("#include"))
+        .stdout(str::contains("int main(void) {"));
+}
+
+#[test]
+fn strict_cell_overflow_exits_with_diagnostic() {
+    build("strict-cell", &["-c", "+.-.-", "--strict"])
+        .command()
+        .assert()
+        .failure()
+        .stderr(str::contains("RuntimeError: Cell overflowed."))
+        .stderr(str::contains(
+            "Attempted to change a cell equal to 0 by -1 at address 0.",
+        ));
+}
+
+#[test]
+fn strict_tape_overflow_exits_with_diagnostic() {
+    build("strict-tape", &["-c", ">><<<", "--strict", "--length", "3"])
+        .command()
+        .assert()
+        .failure()
+        .stderr(str::contains("RuntimeError: Tape address overflowed."));
+}
@@ -0,0 +1,174 @@
+use predicates::str;
+use util::bf;
+
+mod util;
+
+// Sets the first cell to 256 and prints "A" if it didn't wrap around to zero.
+const PRINT_IF_256: &str = "++++++++[>++++++++<-]>[<++++>-]<[>+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.<[-]]";
+
+#[test]
+fn cells_are_8_bit_by_default() {
+    bf().arg("-c")
+        .arg(PRINT_IF_256)
+        .assert()
+        .success()
+        .stdout("");
+}
+
+#[test]
+fn wider_cells_can_be_used() {
+    for bits in ["16", "32"] {
+        bf().arg("-c")
+            .arg(PRINT_IF_256)
+            .arg("--cell-bits")
+            .arg(bits)
+            .assert()
+            .success()
+            .stdout("A");
+    }
+}
+
+#[test]
+fn cell_bits_must_be_supported() {
+    bf().arg("-c")
+        .arg("+")
+        .arg("--cell-bits")
+        .arg("12")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn wider_cells_overflow_with_strict() {
+    bf().arg("-c")
+        .arg("-")
+        .arg("--cell-bits")
+        .arg("16")
+        .arg("--strict")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn strict_errors_underline_source() {
+    bf().arg("-c")
+        .arg("+>>\n<<<")
+        .arg("--strict")
+        .assert()
+        .failure()
+        .stderr(str::contains("^ caused by this code\n  |        at 2:3"));
+}
+
+#[test]
+fn overflows_underline_move_past_the_edge() {
+    bf().arg("-c")
+        .arg(".>>\n<<<.")
+        .arg("--tape")
+        .arg("grow-right")
+        .assert()
+        .failure()
+        .stderr(str::contains("^ caused by this code\n  |        at 2:3"));
+}
+
+#[test]
+fn eof_is_zero_by_default() {
+    bf().arg("-c").arg("+,.").assert().success().stdout("\0");
+}
+
+#[test]
+fn eof_behavior_can_be_changed() {
+    for (eof, output) in [("zero", &[0]), ("unchanged", &[1]), ("max", &[255])] {
+        bf().arg("-c")
+            .arg("+,.")
+            .arg("--eof")
+            .arg(eof)
+            .assert()
+            .success()
+            .stdout(output.as_slice());
+    }
+}
+
+#[test]
+fn eof_behavior_must_be_supported() {
+    bf().arg("-c")
+        .arg("+,.")
+        .arg("--eof")
+        .arg("-1")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn tape_wraps_around_by_default() {
+    bf().arg("-c")
+        .arg("+<<<.")
+        .arg("--length")
+        .arg("3")
+        .assert()
+        .success()
+        .stdout("\x01");
+}
+
+#[test]
+fn tape_can_grow_to_the_right() {
+    for tape in ["grow-right", "grow-both"] {
+        bf().arg("-c")
+            .arg(">>>>>>+.<<<<<<.")
+            .arg("--length")
+            .arg("3")
+            .arg("--tape")
+            .arg(tape)
+            .assert()
+            .success()
+            .stdout("\x01\0");
+    }
+}
+
+#[test]
+fn tape_can_grow_to_the_left() {
+    bf().arg("-c")
+        .arg("+<<<<<<.>>>>>>.")
+        .arg("--tape")
+        .arg("grow-both")
+        .assert()
+        .success()
+        .stdout("\0\x01");
+}
+
+#[test]
+fn tape_growing_right_cannot_go_left() {
+    bf().arg("-c")
+        .arg("<+")
+        .arg("--tape")
+        .arg("grow-right")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn tape_growing_right_behaves_like_unoptimized_code() {
+    // The debugger doesn't optimize the code, and is told to quit after reporting an error.
+    let run = |code: &str, target: &str| {
+        let output = bf()
+            .arg("-c")
+            .arg(code)
+            .arg("-t")
+            .arg(target)
+            .arg("--tape")
+            .arg("grow-right")
+            .write_stdin(":q")
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        let error = stderr.lines().find(|line| line.starts_with("Attempted"));
+        (output.stdout, error.map(str::to_owned))
+    };
+    for code in [
+        "[-<+>]+++.",
+        ">+<+[->+>>+<<<]>.>>.",
+        "<>+.",
+        "+>+[-<<+>>]<.",
+    ] {
+        assert_eq!(run(code, "debug"), run(code, "run"));
+    }
+}
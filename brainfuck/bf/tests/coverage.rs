@@ -0,0 +1,45 @@
+use std::{env, fs, process};
+
+use predicates::str;
+use util::bf;
+
+mod util;
+
+#[test]
+fn marks_lines_which_were_not_executed() {
+    bf().arg("-c")
+        .arg("skip the loop\n[\n-\n]+")
+        .arg("-t")
+        .arg("coverage")
+        .assert()
+        .success()
+        .stderr(str::contains(
+            "Coverage: Executed 2 of 3 lines with code, entered 0 of 1 loops.",
+        ))
+        .stderr(str::contains(
+            "        - | skip the loop\n        1 | [\n          | ^ loop was never entered\n    ##### | -\n        1 | ]+",
+        ));
+}
+
+#[test]
+fn writes_lcov_file() {
+    let path = env::temp_dir().join(format!("bf-lcov-{}.info", process::id()));
+
+    bf().arg("-c")
+        .arg("++[-]\n[+]")
+        .arg("-t")
+        .arg("coverage")
+        .arg("--lcov")
+        .arg(&path)
+        .assert()
+        .success();
+
+    let lcov = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(
+        lcov,
+        "TN:\nSF:<code>\n\
+         BRDA:1,0,0,1\nBRDA:1,0,1,0\nBRDA:2,1,0,0\nBRDA:2,1,1,1\nBRF:4\nBRH:2\n\
+         DA:1,2\nDA:2,1\nLF:2\nLH:2\nend_of_record\n"
+    );
+}
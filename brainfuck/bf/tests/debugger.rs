@@ -26,6 +26,21 @@ fn can_inspect_memory() {
         .stderr(str::contains("[001] [002] [003] [004] [005]"));
 }
 
+#[test]
+fn can_inspect_memory_left_of_growing_tape() {
+    bf().arg("-c")
+        .arg("<<+++#")
+        .arg("-t")
+        .arg("debug")
+        .arg("--tape")
+        .arg("grow-both")
+        .write_stdin(":m")
+        .assert()
+        .stderr(str::contains(
+            "[000] [000] [003] [000] [000]\n                ^ #-2",
+        ));
+}
+
 #[test]
 fn shows_surrounding_code_on_breakpoint_hit() {
     bf().arg("-c")
@@ -35,3 +50,90 @@ fn shows_surrounding_code_on_breakpoint_hit() {
         .assert()
         .stderr(str::contains("abc#def"));
 }
+
+#[test]
+fn can_step_back() {
+    bf().arg("-c")
+        .arg("+>++#")
+        .arg("-t")
+        .arg("debug")
+        .write_stdin(":back 3\n:m 1")
+        .assert()
+        .stderr(str::contains("[000] [001] [000] [000] [000]"));
+}
+
+#[test]
+fn can_reverse_continue_to_previous_breakpoint() {
+    bf().arg("-c")
+        .arg("+#>++#")
+        .arg("-t")
+        .arg("debug")
+        .write_stdin(":r\n:rc\n:m 0")
+        .assert()
+        .stderr(str::contains("Stopped at the previous breakpoint."))
+        .stderr(str::contains("[000] [000] [001] [000] [000]"));
+}
+
+#[test]
+fn can_break_at_source_position() {
+    bf().arg("-c")
+        .arg("+>++>+++")
+        .arg("-t")
+        .arg("debug")
+        .arg("--pause")
+        .write_stdin(":break 4\n:r\n:m 1")
+        .assert()
+        .stderr(str::contains("breakpoint set here"))
+        .stderr(str::contains("[000] [001] [002] [000] [000]"));
+}
+
+#[test]
+fn can_break_at_line_and_column() {
+    bf().arg("-c")
+        .arg("+\n+-")
+        .arg("-t")
+        .arg("debug")
+        .arg("--pause")
+        .write_stdin(":break 2:2\n:r\n:m")
+        .assert()
+        .stderr(str::contains("at 2:2"))
+        .stderr(str::contains("[000] [000] [002] [000] [000]"));
+}
+
+#[test]
+fn can_watch_cell() {
+    bf().arg("-c")
+        .arg("+>+++>+<<+")
+        .arg("-t")
+        .arg("debug")
+        .arg("--pause")
+        .write_stdin(":watch 2\n:r\n:m 2")
+        .assert()
+        .stderr(str::contains("due to a change of cell #2 (0 -> 1)"))
+        .stderr(str::contains("[001] [003] [001] [000] [000]"));
+}
+
+#[test]
+fn can_break_on_condition() {
+    bf().arg("-c")
+        .arg("+++++")
+        .arg("-t")
+        .arg("debug")
+        .arg("--pause")
+        .write_stdin(":break-if cell[0]==3\n:r\n:m")
+        .assert()
+        .stderr(str::contains("since cell[0]==3 became true"))
+        .stderr(str::contains("[000] [000] [003] [000] [000]"));
+}
+
+#[test]
+fn rejects_invalid_condition() {
+    bf().arg("-c")
+        .arg("+")
+        .arg("-t")
+        .arg("debug")
+        .arg("--pause")
+        .write_stdin(":break-if cell[0]=3")
+        .assert()
+        .stderr(str::contains("Invalid condition!"));
+}
@@ -0,0 +1,67 @@
+use predicates::str;
+use util::bf;
+
+mod util;
+
+#[test]
+fn fast_engine_runs_programs() {
+    bf().arg("-c")
+        .arg(",[.,]")
+        .arg("--engine")
+        .arg("fast")
+        .write_stdin("Hello, world!")
+        .assert()
+        .success()
+        .stdout("Hello, world!");
+}
+
+#[test]
+fn fast_engine_errors_underline_source() {
+    bf().arg("-c")
+        .arg("+>>\n<<<")
+        .arg("--engine")
+        .arg("fast")
+        .arg("--strict")
+        .assert()
+        .failure()
+        .stderr(str::contains("^ caused by this code\n  |        at 2:3"));
+}
+
+#[test]
+fn fast_engine_respects_step_limit() {
+    bf().arg("-c")
+        .arg("+[]")
+        .arg("--engine")
+        .arg("fast")
+        .arg("--max-steps")
+        .arg("1000")
+        .assert()
+        .failure()
+        .stderr(str::contains("Step limit exceeded."));
+}
+
+#[test]
+fn fast_engine_only_runs_code() {
+    bf().arg("-c")
+        .arg("+")
+        .arg("--engine")
+        .arg("fast")
+        .arg("-t")
+        .arg("profile")
+        .assert()
+        .failure()
+        .stderr(str::contains("can only be used with the run target"));
+}
+
+#[test]
+fn fast_engine_cannot_trace() {
+    bf().arg("-c")
+        .arg("+")
+        .arg("--engine")
+        .arg("fast")
+        .arg("--trace")
+        .arg("trace.jsonl")
+        .assert()
+        .failure()
+        .stderr(str::contains("The fast engine does not support --trace."));
+}
@@ -0,0 +1,43 @@
+#![cfg(feature = "jit")]
+
+use predicates::str;
+use util::bf;
+
+mod util;
+
+#[test]
+fn jit_runs_programs() {
+    bf().arg("-c")
+        .arg(",[.,]")
+        .arg("-t")
+        .arg("jit")
+        .write_stdin("Hello, world!")
+        .assert()
+        .success()
+        .stdout("Hello, world!");
+}
+
+#[test]
+fn jit_errors_underline_source() {
+    bf().arg("-c")
+        .arg("+>>\n<<<")
+        .arg("-t")
+        .arg("jit")
+        .arg("--strict")
+        .assert()
+        .failure()
+        .stderr(str::contains("^ caused by this code\n  |        at 2:3"));
+}
+
+#[test]
+fn jit_rejects_step_limit() {
+    bf().arg("-c")
+        .arg("+[]")
+        .arg("-t")
+        .arg("jit")
+        .arg("--max-steps")
+        .arg("1000")
+        .assert()
+        .failure()
+        .stderr(str::contains("does not support --max-steps"));
+}
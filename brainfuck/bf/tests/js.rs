@@ -0,0 +1,68 @@
+use assert_cmd::Command;
+use predicates::str;
+use util::{backend_tests, bf, emit, Compiled};
+
+mod util;
+
+/// Runs the module given as the first argument with Node.js, on the standard I/O.
+///
+/// A `RuntimeError` is reported like by the compiled Rust code, after writing its output.
+const RUNNER: &str = r#"
+import { readFileSync } from "fs";
+import { pathToFileURL } from "url";
+
+const { run, RuntimeError } = await import(pathToFileURL(process.argv[1]));
+try {
+    process.stdout.write(run(readFileSync(0)));
+} catch (error) {
+    if (!(error instanceof RuntimeError)) {
+        throw error;
+    }
+    process.stdout.write(error.output);
+    process.stderr.write(`RuntimeError: ${error.message}.\n`);
+    process.exit(1);
+}
+"#;
+
+/// Emits the code as a JavaScript module with the given arguments, which is run by Node.js.
+fn build(name: &str, args: &[&str]) -> Compiled {
+    let module = emit(name, "js", args, "mjs");
+    let mut command = Command::new("node");
+    command
+        .args(["--input-type=module", "-e", RUNNER])
+        .arg(&module);
+    Compiled::new(command, vec![module])
+}
+
+backend_tests!(build);
+
+#[test]
+fn emits_js_module() {
+    bf().arg("-c")
+        .arg(",[.,]")
+        .arg("-t")
+        .arg("js")
+        .assert()
+        .success()
+        .stdout(str::starts_with("const TAPE_LENGTH = 30000;"))
+        .stdout(str::contains("export class RuntimeError extends Error {"))
+        .stdout(str::contains("export function run(input) {"));
+}
+
+#[test]
+fn strict_cell_overflow_throws_runtime_error() {
+    build("strict-cell", &["-c", "+.-.-", "--strict"])
+        .command()
+        .assert()
+        .failure()
+        .stderr("RuntimeError: cell overflow when changing 0 by -1 at 0.\n");
+}
+
+#[test]
+fn strict_tape_overflow_throws_runtime_error() {
+    build("strict-tape", &["-c", ">><<<", "--strict", "--length", "3"])
+        .command()
+        .assert()
+        .failure()
+        .stderr("RuntimeError: tape overflow when changing 0 by -1.\n");
+}
@@ -0,0 +1,26 @@
+use predicates::str;
+use util::bf;
+
+mod util;
+
+#[test]
+fn step_limit_stops_infinite_loops() {
+    bf().arg("-c")
+        .arg("+[]")
+        .arg("--max-steps")
+        .arg("1000")
+        .assert()
+        .failure()
+        .stderr(str::contains("Step limit exceeded."));
+}
+
+#[test]
+fn step_limit_allows_halting_programs() {
+    bf().arg("-c")
+        .arg("++++++++[>++++++++<-]>+.")
+        .arg("--max-steps")
+        .arg("1000")
+        .assert()
+        .success()
+        .stdout("A");
+}
@@ -0,0 +1,68 @@
+use std::{fs, process::Command};
+
+use predicates::str;
+use util::{backend_tests, bf, emit, Compiled};
+
+mod util;
+
+/// Returns the LLVM static compiler command, enabling opaque pointers on versions older than LLVM 15.
+fn llc() -> Command {
+    let output = Command::new("llc")
+        .arg("--version")
+        .output()
+        .expect("Unable to run the LLVM static compiler!");
+    let version = String::from_utf8_lossy(&output.stdout);
+    let major = version
+        .split("LLVM version ")
+        .nth(1)
+        .and_then(|rest| rest.split('.').next())
+        .and_then(|major| major.parse::<u32>().ok());
+
+    let mut command = Command::new("llc");
+    if major.is_some_and(|major| major < 15) {
+        command.arg("-opaque-pointers");
+    }
+    command
+}
+
+/// Emits the code as LLVM IR with the given arguments, and builds it with `llc` and the system C compiler.
+fn build(name: &str, args: &[&str]) -> Compiled {
+    let source = emit(name, "llvm-ir", args, "ll");
+    let object = source.with_extension("o");
+    let binary = source.with_extension("out");
+    let compiled = llc()
+        .args(["-O2", "-filetype=obj", "-relocation-model=pic", "-o"])
+        .arg(&object)
+        .arg(&source)
+        .status()
+        .unwrap();
+    fs::remove_file(&source).unwrap();
+    assert!(compiled.success());
+    let linked = Command::new("cc")
+        .arg("-o")
+        .arg(&binary)
+        .arg(&object)
+        .status()
+        .expect("Unable to run the C compiler!");
+    fs::remove_file(&object).unwrap();
+    assert!(linked.success());
+    Compiled::binary(binary)
+}
+
+backend_tests!(build);
+
+#[test]
+fn emits_llvm_ir() {
+    bf().arg("-c")
+        .arg(",[.,]")
+        .arg("-t")
+        .arg("llvm-ir")
+        .assert()
+        .success()
+        .stdout(str::starts_with(
+            "@tape = internal global [30000 x i8] zeroinitializer",
+        ))
+        .stdout(str::contains("define i32 @main()"))
+        .stdout(str::contains("call i32 @getchar()"))
+        .stdout(str::contains("label %loop.1, label %loop.1.end"));
+}
@@ -0,0 +1,89 @@
+#![cfg(all(target_arch = "x86_64", target_os = "linux"))]
+
+use std::{fs, os::unix::fs::PermissionsExt, process::Command};
+
+use predicates::str;
+use util::{backend_tests, bf, emit, Compiled};
+
+mod util;
+
+/// Emits the code as a static ELF executable with the given arguments.
+fn build_elf(name: &str, args: &[&str]) -> Compiled {
+    let binary = emit(name, "elf", args, "elf");
+    fs::set_permissions(&binary, fs::Permissions::from_mode(0o755)).unwrap();
+    Compiled::binary(binary)
+}
+
+/// Emits the code as assembly with the given arguments, and builds it with the GNU assembler and linker.
+fn build_asm(name: &str, args: &[&str]) -> Compiled {
+    let source = emit(name, "x86-64-asm", args, "s");
+    let object = source.with_extension("o");
+    let binary = source.with_extension("out");
+    let assembled = Command::new("as")
+        .arg("-o")
+        .arg(&object)
+        .arg(&source)
+        .status()
+        .expect("Unable to run the assembler!");
+    fs::remove_file(&source).unwrap();
+    assert!(assembled.success());
+    let linked = Command::new("ld")
+        .arg("-o")
+        .arg(&binary)
+        .arg(&object)
+        .status()
+        .expect("Unable to run the linker!");
+    fs::remove_file(&object).unwrap();
+    assert!(linked.success());
+    Compiled::binary(binary)
+}
+
+mod elf {
+    super::backend_tests!(super::build_elf);
+}
+
+mod asm {
+    super::backend_tests!(super::build_asm);
+}
+
+#[test]
+fn emits_assembly() {
+    bf().arg("-c")
+        .arg(",[.,]")
+        .arg("-t")
+        .arg("x86-64-asm")
+        .assert()
+        .success()
+        .stdout(str::starts_with("    .intel_syntax noprefix"))
+        .stdout(str::contains("_start:"))
+        .stdout(str::contains("syscall"));
+}
+
+#[test]
+fn emits_elf_header() {
+    let output = bf().args(["-c", ",[.,]", "-t", "elf"]).output().unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.starts_with(b"\x7FELF\x02\x01\x01"));
+}
+
+#[test]
+fn strict_cell_overflow_exits_with_diagnostic() {
+    build_elf("strict-cell", &["-c", "+.-.-", "--strict"])
+        .command()
+        .assert()
+        .failure()
+        .stderr(
+            "RuntimeError: Cell overflowed.\nAttempted to change a cell equal to 0 by -1 at address 0.\n",
+        );
+}
+
+#[test]
+fn strict_tape_overflow_exits_with_diagnostic() {
+    build_elf("strict-tape", &["-c", ">><<<", "--strict", "--length", "3"])
+        .command()
+        .assert()
+        .failure()
+        .stderr(
+            "RuntimeError: Tape address overflowed.\nAttempted to change pointer equal to 0 by -1.\n",
+        );
+}
@@ -0,0 +1,50 @@
+use std::{env, fs, process};
+
+use predicates::str;
+use util::bf;
+
+mod util;
+
+#[test]
+fn reports_hottest_loops_first() {
+    bf().arg("-c")
+        .arg("++[>.<-]\n+++++[>.<-]")
+        .arg("-t")
+        .arg("profile")
+        .assert()
+        .success()
+        .stdout("\0".repeat(7))
+        .stderr(str::contains(
+            "#1 at 2:6: 66.67% of instructions (26), 5 iterations",
+        ))
+        .stderr(str::contains("#2 at 1:3:"));
+}
+
+#[test]
+fn reports_programs_without_loops() {
+    bf().arg("-c")
+        .arg("+++")
+        .arg("-t")
+        .arg("profile")
+        .assert()
+        .success()
+        .stderr(str::contains("no loop was entered"));
+}
+
+#[test]
+fn writes_folded_stacks() {
+    let path = env::temp_dir().join(format!("bf-folded-{}.txt", process::id()));
+
+    bf().arg("-c")
+        .arg("++[>.<-]")
+        .arg("-t")
+        .arg("profile")
+        .arg("--folded-stacks")
+        .arg(&path)
+        .assert()
+        .success();
+
+    let folded = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(folded, "main 1\nmain;loop@1:3 11\n");
+}
@@ -0,0 +1,80 @@
+use std::{fs, process::Command};
+
+use predicates::str;
+use util::{backend_tests, bf, emit, Compiled};
+
+mod util;
+
+/// Emits the code as Rust with the given arguments, and builds it with the Rust compiler.
+fn build(name: &str, args: &[&str]) -> Compiled {
+    let source = emit(name, "rust-main", args, "rs");
+    let binary = source.with_extension("out");
+    let status = Command::new("rustc")
+        .args(["--edition", "2021", "-D", "warnings", "-O", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .status()
+        .expect("Unable to run the Rust compiler!");
+    fs::remove_file(&source).unwrap();
+    assert!(status.success());
+    Compiled::binary(binary)
+}
+
+backend_tests!(build);
+
+#[test]
+fn emits_rust_module() {
+    bf().arg("-c")
+        .arg(",[.,]")
+        .arg("-t")
+        .arg("rust")
+        .assert()
+        .success()
+        .stdout(str::starts_with(
+            "//! A Brainfuck program compiled to Rust.",
+        ))
+        .stdout(str::contains(
+            "pub fn run(input: impl Read, output: impl Write) -> Result<(), RuntimeError> {",
+        ));
+}
+
+#[test]
+fn builds_rust_module_as_library() {
+    let source = emit("module", "rust", &["-c", ",[.,]"], "rs");
+    let library = source.with_extension("rlib");
+    let status = Command::new("rustc")
+        .args([
+            "--edition",
+            "2021",
+            "--crate-type",
+            "lib",
+            "-D",
+            "warnings",
+            "-o",
+        ])
+        .arg(&library)
+        .arg(&source)
+        .status()
+        .expect("Unable to run the Rust compiler!");
+    fs::remove_file(&source).unwrap();
+    assert!(status.success());
+    fs::remove_file(library).unwrap();
+}
+
+#[test]
+fn strict_cell_overflow_exits_with_error() {
+    build("strict-cell", &["-c", "+.-.-", "--strict"])
+        .command()
+        .assert()
+        .failure()
+        .stderr("RuntimeError: cell overflow when changing 0 by -1 at 0.\n");
+}
+
+#[test]
+fn strict_tape_overflow_exits_with_error() {
+    build("strict-tape", &["-c", ">><<<", "--strict", "--length", "3"])
+        .command()
+        .assert()
+        .failure()
+        .stderr("RuntimeError: tape overflow when changing 0 by -1.\n");
+}
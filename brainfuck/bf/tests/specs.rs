@@ -5,9 +5,13 @@ use util::bf;
 mod util;
 
 fn run_spec(name: &'static str) {
+    run_spec_with(name, &[])
+}
+
+fn run_spec_with(name: &'static str, args: &[&str]) {
     let root = format!("specs/{name}");
     let mut command = bf();
-    command.arg("-f").arg(format!("{root}.code.bf"));
+    command.args(args).arg("-f").arg(format!("{root}.code.bf"));
     if let Ok(input) = fs::read_to_string(format!("{root}.in.txt")) {
         command.write_stdin(input);
     }
@@ -47,8 +51,49 @@ fn hello_normal_spec() {
     run_spec("hello-normal")
 }
 
-#[ignore = "this is currently too slow"]
 #[test]
 fn mandelbrot_spec() {
-    run_spec("mandelbrot")
+    run_spec_with("mandelbrot", &["--engine", "fast"])
+}
+
+#[cfg(feature = "jit")]
+#[test]
+fn add_spec_jit() {
+    run_spec_with("add", &["-t", "jit"])
+}
+
+#[cfg(feature = "jit")]
+#[test]
+fn cat_spec_jit() {
+    run_spec_with("cat", &["-t", "jit"])
+}
+
+#[cfg(feature = "jit")]
+#[test]
+fn even_spec_jit() {
+    run_spec_with("even", &["-t", "jit"])
+}
+
+#[cfg(feature = "jit")]
+#[test]
+fn fibonacci_spec_jit() {
+    run_spec_with("fibonacci", &["-t", "jit"])
+}
+
+#[cfg(feature = "jit")]
+#[test]
+fn hello_golf_spec_jit() {
+    run_spec_with("hello-golf", &["-t", "jit"])
+}
+
+#[cfg(feature = "jit")]
+#[test]
+fn hello_normal_spec_jit() {
+    run_spec_with("hello-normal", &["-t", "jit"])
+}
+
+#[cfg(feature = "jit")]
+#[test]
+fn mandelbrot_spec_jit() {
+    run_spec_with("mandelbrot", &["-t", "jit"])
 }
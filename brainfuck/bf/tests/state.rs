@@ -0,0 +1,72 @@
+use std::{env, fs, path::PathBuf, process};
+
+use predicates::str;
+use util::bf;
+
+mod util;
+
+fn state_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("bf-{name}-{}.bfst", process::id()))
+}
+
+#[test]
+fn execution_can_be_resumed_from_saved_state() {
+    let path = state_path("resume");
+
+    bf().arg("-c")
+        .arg(",[.,]")
+        .arg("--max-steps")
+        .arg("7")
+        .arg("--save-state")
+        .arg(&path)
+        .write_stdin("Hello, world!")
+        .assert()
+        .failure()
+        .stdout("He");
+
+    bf().arg("-c")
+        .arg(",[.,]")
+        .arg("--load-state")
+        .arg(&path)
+        .write_stdin("Hello, world!")
+        .assert()
+        .success()
+        .stdout("llo, world!");
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn state_must_match_conventions() {
+    let path = state_path("conventions");
+
+    bf().arg("-c")
+        .arg("+")
+        .arg("--save-state")
+        .arg(&path)
+        .assert()
+        .success();
+
+    bf().arg("-c")
+        .arg("+")
+        .arg("--length")
+        .arg("100")
+        .arg("--load-state")
+        .arg(&path)
+        .assert()
+        .failure()
+        .stderr(str::contains("StateError"));
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn state_file_must_be_valid() {
+    bf().arg("-c")
+        .arg("+")
+        .arg("--load-state")
+        .arg("Cargo.toml")
+        .assert()
+        .failure()
+        .stderr(str::contains("Invalid state file"));
+}
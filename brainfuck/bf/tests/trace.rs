@@ -0,0 +1,66 @@
+use std::{env, fs, process};
+
+use predicates::str;
+use util::bf;
+
+mod util;
+
+#[test]
+fn writes_json_lines_trace() {
+    let path = env::temp_dir().join(format!("bf-trace-{}.jsonl", process::id()));
+
+    bf().arg("-c")
+        .arg(",[.,]")
+        .arg("--trace")
+        .arg(&path)
+        .write_stdin("A")
+        .assert()
+        .success()
+        .stdout("A");
+
+    let trace = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    let lines = trace.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 5);
+    assert_eq!(
+        lines[2],
+        r#"{"pc":2,"instruction":".","pointer":0,"cell":0,"before":65,"after":65,"input":null,"output":65}"#
+    );
+}
+
+#[test]
+fn writes_binary_trace() {
+    let path = env::temp_dir().join(format!("bf-trace-{}.bin", process::id()));
+
+    bf().arg("-c")
+        .arg("+.")
+        .arg("--trace")
+        .arg(&path)
+        .arg("--trace-format")
+        .arg("binary")
+        .assert()
+        .success();
+
+    let trace = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(trace, [0, 0, 0, 0, 1, 0, 1, 0, 0, 1, 1, 2, 1]);
+}
+
+#[test]
+fn traces_instruction_causing_error() {
+    let path = env::temp_dir().join(format!("bf-trace-error-{}.jsonl", process::id()));
+
+    bf().arg("-c")
+        .arg("+[]")
+        .arg("--max-steps")
+        .arg("10")
+        .arg("--trace")
+        .arg(&path)
+        .assert()
+        .failure()
+        .stderr(str::contains("Step limit exceeded"));
+
+    let trace = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(trace.lines().count(), 10);
+}
@@ -1,5 +1,182 @@
+// Each test crate uses only some of the helpers.
+#![allow(dead_code)]
+
+use std::{env, fs, path::PathBuf, process};
+
 use assert_cmd::Command;
 
 pub(crate) fn bf() -> Command {
     Command::cargo_bin("bf").expect("Unable to create the bf command!")
 }
+
+/// Emits the code selected by `args` for the given target to a temporary file named after `name`.
+pub(crate) fn emit(name: &str, target: &str, args: &[&str], extension: &str) -> PathBuf {
+    let output = bf().arg("-t").arg(target).args(args).output().unwrap();
+    assert!(output.status.success());
+
+    let path = env::temp_dir().join(format!("bf-{name}-{target}-{}.{extension}", process::id()));
+    fs::write(&path, output.stdout).unwrap();
+    path
+}
+
+/// A program compiled by one of the backends, its temporary files are removed when it is dropped.
+pub(crate) struct Compiled {
+    command: Command,
+    files: Vec<PathBuf>,
+}
+
+impl Compiled {
+    /// Runs the program with the given command, which uses the given temporary files.
+    pub(crate) fn new(command: Command, files: Vec<PathBuf>) -> Self {
+        Self { command, files }
+    }
+
+    /// Runs the given executable directly.
+    pub(crate) fn binary(path: PathBuf) -> Self {
+        Self::new(Command::new(&path), vec![path])
+    }
+
+    pub(crate) fn command(&mut self) -> &mut Command {
+        &mut self.command
+    }
+}
+
+impl Drop for Compiled {
+    fn drop(&mut self) {
+        for file in &self.files {
+            let _ = fs::remove_file(file);
+        }
+    }
+}
+
+/// Runs the spec compiled by `build`, which receives the arguments selecting its code, and checks its output.
+pub(crate) fn run_spec(name: &str, build: impl FnOnce(&[&str]) -> Compiled) {
+    let root = format!("specs/{name}");
+    let mut compiled = build(&["-f", &format!("{root}.code.bf")]);
+    let command = compiled.command();
+    if let Ok(input) = fs::read_to_string(format!("{root}.in.txt")) {
+        command.write_stdin(input);
+    }
+    let assert = command.assert().success();
+    if let Ok(output) = fs::read_to_string(format!("{root}.out.txt")) {
+        assert.stdout(output);
+    }
+}
+
+/// Checks that the code compiled by `build` with the given arguments behaves like the unoptimized code,
+/// which is run by the debugger.
+///
+/// Both are given `:q` as their input, which is read by the code, or quits the debugger once it fails.
+pub(crate) fn run_like_unoptimized(
+    code: &str,
+    args: &[&str],
+    build: impl FnOnce(&[&str]) -> Compiled,
+) {
+    let unoptimized = bf()
+        .args(["-c", code, "-t", "debug"])
+        .args(args)
+        .write_stdin(":q")
+        .output()
+        .unwrap();
+    let failed = String::from_utf8_lossy(&unoptimized.stderr).contains("RuntimeError");
+
+    let output = build(&[&["-c", code], args].concat())
+        .command()
+        .write_stdin(":q")
+        .output()
+        .unwrap();
+    assert_eq!(output.stdout, unoptimized.stdout);
+    assert_eq!(output.status.success(), !failed);
+}
+
+/// Defines a test for each spec and tests of the tape edges, for the programs compiled by `$build`.
+///
+/// `$build` is called with a name for the temporary files and the arguments selecting the code.
+#[allow(unused_macros)]
+macro_rules! backend_tests {
+    ($build:expr) => {
+        $crate::util::backend_tests!(
+            $build;
+            add_spec: "add",
+            cat_spec: "cat",
+            even_spec: "even",
+            fibonacci_spec: "fibonacci",
+            hello_golf_spec: "hello-golf",
+            hello_normal_spec: "hello-normal",
+            mandelbrot_spec: "mandelbrot",
+        );
+
+        #[test]
+        fn tape_growing_right_behaves_like_unoptimized_code() {
+            for code in ["[-<+>]+++.", "<>+.", "+>+[-<<+>>]<."] {
+                $crate::util::run_like_unoptimized(code, &["--tape", "grow-right"], |args| {
+                    $build("growing-right", args)
+                });
+            }
+        }
+
+        #[test]
+        fn tape_growing_both_behaves_like_unoptimized_code() {
+            $crate::util::run_like_unoptimized(
+                "<<<<<+[>>>>>+<<<<<-]>>>>>.",
+                &["--tape", "grow-both"],
+                |args| $build("growing-both", args),
+            );
+        }
+
+        #[test]
+        fn fixed_tape_behaves_like_unoptimized_code() {
+            $crate::util::run_like_unoptimized("<+[>+<-]>.", &["--length", "3"], |args| {
+                $build("wrapping", args)
+            });
+        }
+
+        #[test]
+        fn wider_cells_behave_like_unoptimized_code() {
+            // Outputs whether 65536 fits in a cell, then the maximum value.
+            let code = "++++[>++++<-]>[>++++++++++++++++<-]>[>++++++++++++++++<-]>\
+                [>++++++++++++++++<-]>[>+<[-]]>.<<<<<-.";
+            for bits in ["16", "32"] {
+                $crate::util::run_like_unoptimized(code, &["--cell-bits", bits], |args| {
+                    $build(&format!("cell-bits-{bits}"), args)
+                });
+            }
+        }
+
+        #[test]
+        fn end_of_input_behaves_like_unoptimized_code() {
+            for eof in ["zero", "unchanged", "max"] {
+                $crate::util::run_like_unoptimized(",.,.+,.", &["--eof", eof], |args| {
+                    $build(&format!("eof-{eof}"), args)
+                });
+            }
+        }
+
+        #[test]
+        fn strict_cell_overflow_behaves_like_unoptimized_code() {
+            $crate::util::run_like_unoptimized("+.-.-", &["--strict"], |args| {
+                $build("strict-cell", args)
+            });
+        }
+
+        #[test]
+        fn strict_tape_overflow_behaves_like_unoptimized_code() {
+            $crate::util::run_like_unoptimized(
+                ">><<<",
+                &["--strict", "--length", "3"],
+                |args| $build("strict-tape", args),
+            );
+        }
+    };
+    ($build:expr; $($test:ident: $name:literal,)+) => {
+        $(
+            #[test]
+            fn $test() {
+                $crate::util::run_spec($name, |args| $build($name, args))
+            }
+        )+
+    };
+}
+
+#[allow(unused_imports)]
+pub(crate) use backend_tests;
@@ -0,0 +1,142 @@
+use assert_cmd::Command;
+use predicates::{function::function, str};
+use util::{backend_tests, bf, emit, Compiled};
+
+mod util;
+
+/// Runs the loader given as the first argument with Node.js, on the module given as the second one.
+///
+/// A trap fails the process, after writing the output held by the error.
+const LOADER_RUNNER: &str = r#"
+import { readFileSync } from "fs";
+import { pathToFileURL } from "url";
+
+const { run } = await import(pathToFileURL(process.argv[1]));
+try {
+    process.stdout.write(await run(readFileSync(process.argv[2]), readFileSync(0)));
+} catch (error) {
+    if (!(error instanceof WebAssembly.RuntimeError)) {
+        throw error;
+    }
+    process.stdout.write(error.output);
+    process.exit(1);
+}
+"#;
+
+/// Compiles the module given on the standard input with Node.js, which fails if it is invalid.
+const VALIDATOR: &str = r#"
+import { readFileSync } from "fs";
+
+try {
+    new WebAssembly.Module(readFileSync(0));
+} catch (error) {
+    process.stderr.write(`${error.message}\n`);
+    process.exit(1);
+}
+"#;
+
+/// Emits the code as a binary module and its loader for the given targets, which are run by Node.js.
+fn build_with_loader(
+    name: &str,
+    args: &[&str],
+    module_target: &str,
+    loader_target: &str,
+) -> Compiled {
+    let module = emit(name, module_target, args, "wasm");
+    let loader = emit(name, loader_target, args, "mjs");
+    let mut command = Command::new("node");
+    command
+        .args(["--input-type=module", "-e", LOADER_RUNNER])
+        .arg(&loader)
+        .arg(&module);
+    Compiled::new(command, vec![module, loader])
+}
+
+mod loader {
+    super::backend_tests!(|name, args| super::build_with_loader(name, args, "wasm", "wasm-loader"));
+}
+
+mod wasi_loader {
+    super::backend_tests!(|name, args| {
+        super::build_with_loader(name, args, "wasm-wasi", "wasm-wasi-loader")
+    });
+}
+
+#[test]
+fn emits_wasm_text() {
+    bf().arg("-c")
+        .arg(",[.,]")
+        .arg("-t")
+        .arg("wasm-text")
+        .assert()
+        .success()
+        .stdout(str::starts_with("(module"));
+}
+
+#[test]
+fn emits_wasm_binary() {
+    bf().arg("-c")
+        .arg(",[.,]")
+        .arg("-t")
+        .arg("wasm")
+        .assert()
+        .success()
+        .stdout(function(|out: &[u8]| out.starts_with(b"\0asm\x01\0\0\0")));
+}
+
+#[test]
+fn emits_wasi_imports_in_binary() {
+    bf().arg("-c")
+        .arg(",[.,]")
+        .arg("-t")
+        .arg("wasm-wasi")
+        .assert()
+        .success()
+        .stdout(function(|out: &[u8]| {
+            out.windows(13).any(|w| w == b"wasi_unstable")
+        }));
+}
+
+#[test]
+fn emits_valid_modules() {
+    let conventions: [&[&str]; 4] = [
+        &[],
+        &["--strict"],
+        &["--tape", "grow-right", "--cell-bits", "16", "--eof", "max"],
+        &[
+            "--tape",
+            "grow-both",
+            "--cell-bits",
+            "32",
+            "--eof",
+            "unchanged",
+        ],
+    ];
+    for target in ["wasm", "wasm-wasi"] {
+        for args in conventions {
+            let module = bf()
+                .args(["-f", "specs/mandelbrot.code.bf", "-t", target])
+                .args(args)
+                .output()
+                .unwrap();
+            assert!(module.status.success());
+            Command::new("node")
+                .args(["--input-type=module", "-e", VALIDATOR])
+                .write_stdin(module.stdout)
+                .assert()
+                .success();
+        }
+    }
+}
+
+#[test]
+fn emits_js_loader() {
+    bf().arg("-c")
+        .arg(",[.,]")
+        .arg("-t")
+        .arg("wasm-loader")
+        .assert()
+        .success()
+        .stdout(str::contains("export async function run(wasm, input) {"))
+        .stdout(str::contains("bf: {"));
+}
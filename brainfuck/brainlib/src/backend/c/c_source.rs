@@ -0,0 +1,377 @@
+use std::io::{self, Write};
+
+use crate::backend::common::{
+    Addressing, CellWidth, EofBehavior, Instruction as CI, Program, Settings, SourceWriter,
+    TapeMode,
+};
+
+/// Represents a standalone C99 translation unit for a given [`Program`].
+///
+/// A translation unit can be created from a [`Program`] using [`CSource::compile_from`],
+/// and emitted using [`CSource::emit`].
+///
+/// The produced code only depends on the C standard library, and can be compiled with any C99 compiler,
+/// e.g. `cc -std=c99 -O2 program.c`.
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CSource(String);
+
+impl CSource {
+    /// Creates a new [`CSource`] from a [`Program`].
+    ///
+    /// The entire program code is placed in the `main` function. The cells are stored in the `tape` array,
+    /// using the smallest unsigned integer type of the configured [`CellWidth`], and `ptr` holds the index
+    /// of the current cell. A fixed tape is a static array, while a growable one is allocated with `realloc`.
+    ///
+    /// Input is read from `stdin` through the `read_cell` function, which handles the configured [`EofBehavior`].
+    /// Output is written to `stdout` through the `write_cell` function.
+    ///
+    /// The code behaves like the interpreter for the given [`Settings`]. The runtime errors
+    /// (overflows in `strict` mode, failed I/O) print a diagnostic to `stderr` and exit with `EXIT_FAILURE`.
+    ///
+    /// All programs produce a valid translation unit, panics can only occur due to internal errors.
+    pub fn compile_from(program: &Program, settings: &Settings) -> Self {
+        let addressing = Addressing::from(settings);
+        let mut out = SourceWriter::new("    ");
+
+        let helpers = Helpers::of(program, settings, addressing);
+        prelude(&mut out, settings, addressing, helpers);
+
+        out.open("int main(void) {");
+        if helpers.index {
+            out.line("size_t i;");
+        }
+        if addressing == Addressing::Growable {
+            out.line("resize(TAPE_LENGTH);");
+        }
+        if helpers.index || addressing == Addressing::Growable {
+            out.line("");
+        }
+
+        for instr in program.code.iter() {
+            match *instr {
+                CI::MutPointer(change) => {
+                    out.line(format!("ptr = {};", index(settings, addressing, change)));
+                },
+                CI::MutCell { offset, delta } => {
+                    let cell = cell(&mut out, settings, addressing, offset);
+                    match (settings.strict(), delta < 0) {
+                        (true, _) => out.line(format!("add({cell}, {delta});")),
+                        (false, false) => out.line(format!("tape[{cell}] += {delta};")),
+                        (false, true) =>
+                            out.line(format!("tape[{cell}] -= {};", delta.unsigned_abs())),
+                    }
+                },
+                CI::SetCell { offset, value } => {
+                    let cell = cell(&mut out, settings, addressing, offset);
+                    out.line(format!("tape[{cell}] = {value};"));
+                },
+                CI::MulAdd { offset, factor } => {
+                    let cell = cell(&mut out, settings, addressing, offset);
+                    // The multiplication is unsigned, so that it wraps around instead of overflowing.
+                    let operator = if factor < 0 { '-' } else { '+' };
+                    out.line(format!(
+                        "tape[{cell}] {operator}= tape[ptr] * {}u;",
+                        factor.unsigned_abs()
+                    ));
+                },
+                CI::ScanRight(stride) => scan(&mut out, settings, addressing, stride as i32),
+                CI::ScanLeft(stride) => scan(&mut out, settings, addressing, -(stride as i32)),
+                CI::JumpRightZ(_) => out.open("while (tape[ptr]) {"),
+                CI::JumpLeftNz(_) => out.close("}"),
+                CI::Input => out.line("read_cell(&tape[ptr]);"),
+                CI::Output => out.line("write_cell(tape[ptr]);"),
+                CI::Breakpoint(_) => (),
+            }
+        }
+
+        out.line("");
+        out.open("if (fflush(stdout) == EOF) {");
+        out.line("io_error(\"write to output\");");
+        out.close("}");
+        out.line("return EXIT_SUCCESS;");
+        out.close("}");
+
+        Self(out.finish())
+    }
+
+    /// Emits the C source code to the given [`Write`].
+    pub fn emit(&self, mut write: impl Write) -> io::Result<()> {
+        write.write_all(self.0.as_bytes())
+    }
+}
+
+/// The parts of the prelude which are used by a program.
+///
+/// Only those are emitted, so that the code compiles without warnings about unused functions.
+#[derive(Debug, Clone, Copy)]
+struct Helpers {
+    /// The `tape` array, which may be unused by a program which only moves the pointer.
+    tape: bool,
+    /// The `ptr` variable, which is unused only by a program without instructions.
+    ptr: bool,
+    /// The `i` variable, which holds the index of a cell other than the current one.
+    index: bool,
+    at: bool,
+    add: bool,
+    read_cell: bool,
+    write_cell: bool,
+}
+
+impl Helpers {
+    fn of(program: &Program, settings: &Settings, addressing: Addressing) -> Self {
+        let any = |predicate: fn(&CI) -> bool| program.code.iter().any(predicate);
+        let index = any(|instr| {
+            matches!(instr,
+                CI::MutCell { offset, .. } | CI::SetCell { offset, .. } | CI::MulAdd { offset, .. }
+                    if *offset != 0
+            )
+        });
+        let moves = any(|instr| {
+            matches!(
+                instr,
+                CI::MutPointer(_) | CI::ScanRight(_) | CI::ScanLeft(_)
+            )
+        });
+        Self {
+            tape: any(|instr| !matches!(instr, CI::MutPointer(_) | CI::Breakpoint(_))),
+            ptr: any(|instr| !matches!(instr, CI::Breakpoint(_))),
+            index,
+            at: addressing != Addressing::Wrapping && (index || moves),
+            add: settings.strict() && any(|instr| matches!(instr, CI::MutCell { .. })),
+            read_cell: any(|instr| matches!(instr, CI::Input)),
+            write_cell: any(|instr| matches!(instr, CI::Output)),
+        }
+    }
+}
+
+/// Writes the includes, the tape and the helper functions used by `main`.
+fn prelude(out: &mut SourceWriter, settings: &Settings, addressing: Addressing, helpers: Helpers) {
+    out.lines(INCLUDES);
+    out.line("");
+    out.line(format!("#define TAPE_LENGTH {}", settings.tape_length()));
+    if addressing == Addressing::Growable {
+        out.line(format!("#define MAX_LENGTH {}", Settings::MAX_LENGTH));
+    }
+    if helpers.add || (helpers.read_cell && settings.eof_behavior() == EofBehavior::Max) {
+        out.line(format!("#define CELL_MAX {}", settings.cell_width().max()));
+    }
+    out.line("");
+    out.line(match settings.cell_width() {
+        CellWidth::U8 => "typedef uint8_t cell;",
+        CellWidth::U16 => "typedef uint16_t cell;",
+        CellWidth::U32 => "typedef uint32_t cell;",
+    });
+    out.line("");
+    match addressing {
+        Addressing::Wrapping | Addressing::Bounded if helpers.tape =>
+            out.line("static cell tape[TAPE_LENGTH];"),
+        Addressing::Growable => out.line("static cell *tape;"),
+        Addressing::Wrapping | Addressing::Bounded => (),
+    }
+    // The origin is the index of the initial first cell, it only moves when a tape grows to the left.
+    let variables = [
+        (addressing == Addressing::Growable).then_some("length"),
+        helpers.ptr.then_some("ptr"),
+        (helpers.at || helpers.add).then_some("origin"),
+    ];
+    let variables = variables.into_iter().flatten().collect::<Vec<_>>();
+    if !variables.is_empty() {
+        out.line(format!("static size_t {};", variables.join(", ")));
+    }
+
+    out.line("");
+    out.lines(IO_ERROR);
+    if helpers.at {
+        out.line("");
+        out.lines(TAPE_OVERFLOW);
+    }
+    if addressing == Addressing::Growable {
+        out.line("");
+        out.lines(RESIZE);
+    }
+    match addressing {
+        Addressing::Wrapping => (),
+        Addressing::Bounded if helpers.at => {
+            out.line("");
+            out.lines(BOUNDED_AT);
+        },
+        Addressing::Growable if helpers.at => {
+            out.line("");
+            out.lines(&growable_at(settings.tape_mode()));
+        },
+        Addressing::Bounded | Addressing::Growable => (),
+    }
+    if helpers.add {
+        out.line("");
+        out.lines(CELL_OVERFLOW);
+        out.line("");
+        out.lines(ADD);
+    }
+
+    if helpers.read_cell {
+        out.line("");
+        out.lines(&read_cell(settings.eof_behavior()));
+    }
+    if helpers.write_cell {
+        out.line("");
+        out.lines(WRITE_CELL);
+    }
+    out.line("");
+}
+
+/// Returns the expression of the index of the cell `offset` cells away from the pointer.
+fn index(settings: &Settings, addressing: Addressing, offset: i32) -> String {
+    match addressing {
+        Addressing::Wrapping => format!(
+            "(ptr + {}) % TAPE_LENGTH",
+            offset.rem_euclid(settings.tape_length() as i32)
+        ),
+        Addressing::Bounded | Addressing::Growable => format!("at({offset})"),
+    }
+}
+
+/// Returns the expression of the index of the cell `offset` cells away from the pointer,
+/// storing it in `i` first if it has to be computed.
+///
+/// Growing the tape moves it in memory, so the index is always computed in a separate statement.
+fn cell(
+    out: &mut SourceWriter,
+    settings: &Settings,
+    addressing: Addressing,
+    offset: i32,
+) -> &'static str {
+    if offset == 0 {
+        return "ptr";
+    }
+    out.line(format!("i = {};", index(settings, addressing, offset)));
+    "i"
+}
+
+fn scan(out: &mut SourceWriter, settings: &Settings, addressing: Addressing, stride: i32) {
+    out.open("while (tape[ptr]) {");
+    out.line(format!("ptr = {};", index(settings, addressing, stride)));
+    out.close("}");
+}
+
+const INCLUDES: &str = "\
+#include <errno.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>";
+
+const IO_ERROR: &str = r#"static void io_error(const char *action) {
+    int error = errno;
+    fflush(stdout);
+    fprintf(stderr, "RuntimeError: Could not %s.\nThe underlying error was: %s.\n", action, strerror(error));
+    exit(EXIT_FAILURE);
+}"#;
+
+const TAPE_OVERFLOW: &str = r#"static void tape_overflow(long long by) {
+    fflush(stdout);
+    fprintf(stderr, "RuntimeError: Tape address overflowed.\nAttempted to change pointer equal to %lld by %lld.\n",
+        (long long)ptr - (long long)origin, by);
+    exit(EXIT_FAILURE);
+}"#;
+
+const CELL_OVERFLOW: &str = r#"static void cell_overflow(size_t index, long long by) {
+    fflush(stdout);
+    fprintf(stderr, "RuntimeError: Cell overflowed.\nAttempted to change a cell equal to %lu by %lld at address %lld.\n",
+        (unsigned long)tape[index], by, (long long)index - (long long)origin);
+    exit(EXIT_FAILURE);
+}"#;
+
+const ADD: &str = "\
+static void add(size_t index, long long delta) {
+    long long value = (long long)tape[index] + delta;
+    if (value < 0 || value > CELL_MAX) {
+        cell_overflow(index, delta);
+    }
+    tape[index] = (cell)value;
+}";
+
+const BOUNDED_AT: &str = "\
+static size_t at(long long offset) {
+    long long index = (long long)ptr + offset;
+    if (index < 0 || index >= TAPE_LENGTH) {
+        tape_overflow(offset);
+    }
+    return (size_t)index;
+}";
+
+const RESIZE: &str = r#"static void resize(size_t new_length) {
+    cell *resized = realloc(tape, new_length * sizeof(cell));
+    if (resized == NULL) {
+        fflush(stdout);
+        fputs("RuntimeError: Could not allocate the tape.\n", stderr);
+        exit(EXIT_FAILURE);
+    }
+    memset(resized + length, 0, (new_length - length) * sizeof(cell));
+    tape = resized;
+    length = new_length;
+}"#;
+
+/// Returns the `at` function of a growable tape, which grows it so that it contains the cell
+/// `offset` cells away from the pointer, and returns the index of that cell.
+fn growable_at(tape_mode: TapeMode) -> String {
+    let grow_left = match tape_mode {
+        TapeMode::GrowBoth => GROW_LEFT,
+        TapeMode::Fixed | TapeMode::GrowRight => "        tape_overflow(offset);",
+    };
+    format!(
+        "\
+static size_t at(long long offset) {{
+    long long index = (long long)ptr + offset;
+    long long room = MAX_LENGTH - (long long)length;
+    long long least = (long long)length < room ? (long long)length : room;
+    if (index < 0) {{
+{grow_left}
+    }} else if (index >= (long long)length) {{
+        long long grown = (long long)length + least;
+        if (index >= (long long)length + room) {{
+            tape_overflow(offset);
+        }}
+        resize((size_t)(index + 1 > grown ? index + 1 : grown));
+    }}
+    return (size_t)index;
+}}"
+    )
+}
+
+/// Returns the `read_cell` function, which stores the read byte or handles the end of input.
+fn read_cell(eof_behavior: EofBehavior) -> String {
+    let at_eof = match eof_behavior {
+        EofBehavior::Zero => " else {\n        *target = 0;\n    }",
+        EofBehavior::Unchanged => "",
+        EofBehavior::Max => " else {\n        *target = CELL_MAX;\n    }",
+    };
+    format!(
+        r#"static void read_cell(cell *target) {{
+    int byte = getchar();
+    if (byte != EOF) {{
+        *target = (cell)byte;
+    }} else if (ferror(stdin)) {{
+        io_error("read from input");
+    }}{at_eof}
+}}"#
+    )
+}
+
+/// The part of `at` which reallocates the tape and shifts it with `memmove` to add cells on the left.
+const GROW_LEFT: &str = "        long long added = -index > least ? -index : least;
+        if (-index > room) {
+            tape_overflow(offset);
+        }
+        resize(length + (size_t)added);
+        memmove(tape + added, tape, (length - (size_t)added) * sizeof(cell));
+        memset(tape, 0, (size_t)added * sizeof(cell));
+        origin += (size_t)added;
+        ptr += (size_t)added;
+        index += added;";
+
+const WRITE_CELL: &str = r#"static void write_cell(cell value) {
+    if (putchar((unsigned char)value) == EOF) {
+        io_error("write to output");
+    }
+}"#;
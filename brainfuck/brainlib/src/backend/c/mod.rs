@@ -0,0 +1,28 @@
+//! Functionalities related to transpilation from Brainfuck to C.
+//!
+//! The features are exposed through the [`CSource`] type, which holds a standalone C99 translation unit.
+//! It only depends on the standard library, reading the input from `stdin` and writing the output to `stdout`,
+//! so it can be built for targets on which WebAssembly isn't available.
+//!
+//! # Examples
+//! ```
+//! # use std::io::stdout;
+//! use brainlib::{c::CSource, Program, Settings};
+//!
+//! let settings = Settings::default();
+//! let source = ",[.,]";
+//! let program = Program::compile(source, &settings)?;
+//!
+//! let c_source = CSource::compile_from(&program, &settings);
+//! c_source.emit(stdout())?;
+//!
+//! let mut text = Vec::new();
+//! c_source.emit(&mut text)?;
+//! assert!(text.starts_with(b"#include"));
+//!
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+mod c_source;
+
+pub use self::c_source::CSource;
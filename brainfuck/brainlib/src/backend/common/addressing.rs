@@ -0,0 +1,24 @@
+use super::{Settings, TapeMode};
+
+/// The way in which the code emitted by a backend computes cell indices,
+/// depending on the [`TapeMode`] and `strict` mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Addressing {
+    /// A fixed tape, on which the indices wrap around the edges.
+    Wrapping,
+    /// A fixed tape, on which leaving the tape is an error.
+    Bounded,
+    /// A growable tape, which grows exactly like the tape of the interpreter,
+    /// by at least doubling, so that the same programs fail with the same errors.
+    Growable,
+}
+
+impl From<&Settings> for Addressing {
+    fn from(settings: &Settings) -> Self {
+        match (settings.tape_mode(), settings.strict()) {
+            (TapeMode::Fixed, false) => Self::Wrapping,
+            (TapeMode::Fixed, true) => Self::Bounded,
+            (TapeMode::GrowRight | TapeMode::GrowBoth, _) => Self::Growable,
+        }
+    }
+}
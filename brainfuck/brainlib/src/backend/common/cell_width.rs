@@ -0,0 +1,60 @@
+/// The size of a single data cell on the tape.
+///
+/// Cells are always unsigned, wrapping around (or overflowing in `strict` mode) at their maximum value.
+///
+/// # Examples
+/// ```
+/// # use brainlib::{CellWidth, Settings};
+/// let settings = Settings::new().with_cell_width(CellWidth::U16);
+/// assert_eq!(settings.cell_width().max(), 65_535);
+/// ```
+#[must_use]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CellWidth {
+    /// 8-bit cells, holding values from 0 to 255.
+    ///
+    /// This is the conventional [`CellWidth::default`].
+    #[default]
+    U8,
+
+    /// 16-bit cells, holding values from 0 to 65 535.
+    U16,
+
+    /// 32-bit cells, holding values from 0 to 4 294 967 295.
+    U32,
+}
+
+impl CellWidth {
+    /// Returns the [`CellWidth`] with the given number of bits, if it is one of 8, 16 or 32.
+    #[must_use]
+    pub const fn from_bits(bits: u32) -> Option<Self> {
+        match bits {
+            8 => Some(Self::U8),
+            16 => Some(Self::U16),
+            32 => Some(Self::U32),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of bits in a single cell.
+    #[must_use]
+    pub const fn bits(&self) -> u32 {
+        match self {
+            Self::U8 => 8,
+            Self::U16 => 16,
+            Self::U32 => 32,
+        }
+    }
+
+    /// Returns the number of bytes in a single cell.
+    #[must_use]
+    pub const fn bytes(&self) -> u32 {
+        self.bits() / 8
+    }
+
+    /// Returns the maximum value which can be stored in a single cell.
+    #[must_use]
+    pub const fn max(&self) -> u32 {
+        u32::MAX >> (32 - self.bits())
+    }
+}
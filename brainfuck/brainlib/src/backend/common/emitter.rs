@@ -10,8 +10,14 @@ pub(crate) fn emit(ast: &Tree) -> Program {
         match node {
             N::Right => result.push(I::MutPointer(1)),
             N::Left => result.push(I::MutPointer(-1)),
-            N::Increment => result.push(I::MutCell(1)),
-            N::Decrement => result.push(I::MutCell(-1)),
+            N::Increment => result.push(I::MutCell {
+                offset: 0,
+                delta: 1,
+            }),
+            N::Decrement => result.push(I::MutCell {
+                offset: 0,
+                delta: -1,
+            }),
             N::Output => result.push(I::Output),
             N::Input => result.push(I::Input),
             N::Loop(subtree) => {
@@ -19,13 +25,13 @@ pub(crate) fn emit(ast: &Tree) -> Program {
                 let jump = subcode.len() as u32 + 1;
 
                 result.push(I::JumpRightZ(jump));
-                result.append(&mut subcode.0);
+                result.append(&mut subcode.code);
                 result.push(I::JumpLeftNz(jump));
             },
             N::Breakpoint(pos) => result.push(I::Breakpoint(*pos as u32)),
         };
     }
-    Program(result)
+    Program::from_code(result)
 }
 
 #[cfg(test)]
@@ -36,7 +42,14 @@ mod tests {
     fn emits_correct_loop_offsets() {
         assert_eq!(
             emit(&vec![N::Loop(vec![N::Decrement].into_boxed_slice())].into_boxed_slice()),
-            Program(vec![I::JumpRightZ(2), I::MutCell(-1), I::JumpLeftNz(2)])
+            Program::from_code(vec![
+                I::JumpRightZ(2),
+                I::MutCell {
+                    offset: 0,
+                    delta: -1
+                },
+                I::JumpLeftNz(2)
+            ])
         );
     }
 
@@ -49,10 +62,13 @@ mod tests {
                 )]
                 .into_boxed_slice()
             ),
-            Program(vec![
+            Program::from_code(vec![
                 I::JumpRightZ(4),
                 I::JumpRightZ(2),
-                I::MutCell(-1),
+                I::MutCell {
+                    offset: 0,
+                    delta: -1
+                },
                 I::JumpLeftNz(2),
                 I::JumpLeftNz(4)
             ])
@@ -0,0 +1,25 @@
+/// The value stored in the cell when the `,` instruction reaches the end of input.
+///
+/// Brainfuck implementations disagree on this, so programs are usually written for one of the conventions.
+///
+/// # Examples
+/// ```
+/// # use brainlib::{EofBehavior, Settings};
+/// let settings = Settings::new().with_eof_behavior(EofBehavior::Unchanged);
+/// assert_eq!(settings.eof_behavior(), EofBehavior::Unchanged);
+/// ```
+#[must_use]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EofBehavior {
+    /// Store `0` in the cell.
+    ///
+    /// This is the [`EofBehavior::default`].
+    #[default]
+    Zero,
+
+    /// Leave the cell unchanged.
+    Unchanged,
+
+    /// Store the maximum cell value in the cell, which is `255` (or `-1`) for 8-bit cells.
+    Max,
+}
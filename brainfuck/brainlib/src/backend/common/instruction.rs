@@ -15,19 +15,59 @@ pub enum Instruction {
     /// - `<` -> `MutPointer(-1)`
     MutPointer(i32),
 
-    /// Change the value of the cell under the pointer by the given `i8` amount.
+    /// Change the value of the cell at `offset` from the pointer by the given `delta`.
     ///
-    /// Positive values increase the value, negative values decrease it.
+    /// Positive deltas increase the value, negative deltas decrease it.
+    /// The pointer itself is left unchanged.
     ///
     /// Character representation:
-    /// - `+` -> `MutCell(1)`
-    /// - `-` -> `MutCell(-1)`
-    MutCell(i8),
+    /// - `+` -> `MutCell { offset: 0, delta: 1 }`
+    /// - `-` -> `MutCell { offset: 0, delta: -1 }`
+    ///
+    /// Non-zero offsets are only created during optimization.
+    MutCell {
+        /// The distance from the pointer to the changed cell.
+        offset: i32,
+        /// The amount added to the value of the cell, wrapped to the configured cell width.
+        delta: i32,
+    },
 
-    /// Set the value of the cell under the pointer to the given `u8` value.
+    /// Set the value of the cell at `offset` from the pointer to the given `value`.
+    ///
+    /// The pointer itself is left unchanged.
     ///
     /// No possible representation in source code, this is only created during optimization.
-    SetCell(u8),
+    SetCell {
+        /// The distance from the pointer to the changed cell.
+        offset: i32,
+        /// The new value of the cell, which fits in the configured cell width.
+        value: u32,
+    },
+
+    /// Add the value of the cell under the pointer multiplied by `factor` to the cell at `offset` from the pointer.
+    ///
+    /// The cell under the pointer and the pointer itself are left unchanged.
+    ///
+    /// No possible representation in source code, this is only created during optimization,
+    /// from loops like `[->+>++<<]`, which are replaced with a series of `MulAdd`s followed by a `SetCell { offset: 0, value: 0 }`.
+    MulAdd {
+        /// The distance from the pointer to the changed cell.
+        offset: i32,
+        /// The multiplier applied to the value of the cell under the pointer.
+        factor: i32,
+    },
+
+    /// Move the pointer to the right by the given `u32` stride until it points to a zero cell.
+    ///
+    /// No possible representation in source code, this is only created during optimization,
+    /// from loops like `[>]` or `[>>]`.
+    ScanRight(u32),
+
+    /// Move the pointer to the left by the given `u32` stride until it points to a zero cell.
+    ///
+    /// No possible representation in source code, this is only created during optimization,
+    /// from loops like `[<]` or `[<<]`.
+    ScanLeft(u32),
 
     /// Increase the pointer by the given `u32` amount if the cell under the pointer is zero.
     ///
@@ -70,9 +110,9 @@ impl Instruction {
     }
 
     #[must_use]
-    pub(crate) fn unwrap_mut_cell(&self) -> i8 {
+    pub(crate) fn unwrap_mut_cell(&self) -> i32 {
         match self {
-            Instruction::MutCell(value) => *value,
+            Instruction::MutCell { delta, .. } => *delta,
             _ => panic!(),
         }
     }
@@ -86,16 +126,59 @@ impl Display for Instruction {
     /// # Examples
     /// ```
     /// # use brainlib::Instruction;
-    /// assert_eq!(Instruction::MutCell(1).to_string(), "+");
-    /// assert_eq!(Instruction::MutCell(-1).to_string(), "-");
-    /// assert_eq!(Instruction::MutCell(10).to_string(), "¿");
+    /// assert_eq!(
+    ///     Instruction::MutCell {
+    ///         offset: 0,
+    ///         delta: 1
+    ///     }
+    ///     .to_string(),
+    ///     "+"
+    /// );
+    /// assert_eq!(
+    ///     Instruction::MutCell {
+    ///         offset: 0,
+    ///         delta: -1
+    ///     }
+    ///     .to_string(),
+    ///     "-"
+    /// );
+    /// assert_eq!(
+    ///     Instruction::MutCell {
+    ///         offset: 0,
+    ///         delta: 10
+    ///     }
+    ///     .to_string(),
+    ///     "¿"
+    /// );
+    /// assert_eq!(
+    ///     Instruction::MutCell {
+    ///         offset: 1,
+    ///         delta: 1
+    ///     }
+    ///     .to_string(),
+    ///     "¿"
+    /// );
+    /// assert_eq!(
+    ///     Instruction::MulAdd {
+    ///         offset: 1,
+    ///         factor: 2
+    ///     }
+    ///     .to_string(),
+    ///     "¿"
+    /// );
     /// ```
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Self::MutPointer(1) => write!(f, ">"),
             Self::MutPointer(-1) => write!(f, "<"),
-            Self::MutCell(1) => write!(f, "+"),
-            Self::MutCell(-1) => write!(f, "-"),
+            Self::MutCell {
+                offset: 0,
+                delta: 1,
+            } => write!(f, "+"),
+            Self::MutCell {
+                offset: 0,
+                delta: -1,
+            } => write!(f, "-"),
             Self::Output => write!(f, "."),
             Self::Input => write!(f, ","),
             Self::JumpRightZ(_) => write!(f, "["),
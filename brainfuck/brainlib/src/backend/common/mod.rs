@@ -1,8 +1,18 @@
+mod addressing;
+mod cell_width;
 mod emitter;
+mod eof_behavior;
 mod instruction;
 mod optimizer;
 mod program;
 mod settings;
+mod source_writer;
+mod tape_mode;
 
-pub(crate) use self::{emitter::emit, optimizer::optimize};
-pub use self::{instruction::Instruction, program::Program, settings::Settings};
+pub(crate) use self::{
+    addressing::Addressing, emitter::emit, optimizer::optimize, source_writer::SourceWriter,
+};
+pub use self::{
+    cell_width::CellWidth, eof_behavior::EofBehavior, instruction::Instruction, program::Program,
+    settings::Settings, tape_mode::TapeMode,
+};
@@ -1,7 +1,7 @@
 use std::{collections::VecDeque, mem};
 
 use self::builder::Builder;
-use crate::backend::common::{Instruction as I, Program, Settings};
+use crate::backend::common::{Instruction as I, Program, Settings, TapeMode};
 
 pub(crate) fn optimize(program: Program, settings: &Settings) -> Program {
     if settings.debug() {
@@ -11,13 +11,16 @@ pub(crate) fn optimize(program: Program, settings: &Settings) -> Program {
     let program = remove_breakpoints(program);
     let program = merge_muts(program, settings);
     let program = create_sets(program, settings);
+    let program = create_mul_adds(program, settings);
+    let program = create_scans(program, settings);
     let program = remove_unused_loops(program);
-    reduce_cell_chains(program, settings)
+    let program = reduce_cell_chains(program, settings);
+    create_offsets(program, settings)
 }
 
 fn remove_breakpoints(input: Program) -> Program {
-    let mut builder = Builder::with_capacity(input.len());
-    for instr in input.0 {
+    let mut builder = Builder::new(&input);
+    for instr in input.code {
         if let I::Breakpoint(_) = instr {
             builder.omit(1);
         } else {
@@ -32,27 +35,34 @@ fn merge_muts(input: Program, settings: &Settings) -> Program {
         return input;
     }
 
-    let mut input = input.0.into_iter().peekable();
-    let mut builder = Builder::with_capacity(input.len());
+    let mut builder = Builder::new(&input);
+    let mut input = input.code.into_iter().peekable();
 
     while let Some(instr) = input.next() {
         match instr {
-            I::MutPointer(mut value) => {
+            I::MutPointer(mut value) if settings.merges_moves() => {
                 builder.omit(1);
                 while let Some(I::MutPointer(_)) = input.peek() {
                     builder.omit(1);
-                    value = (value + input.next().unwrap().unwrap_mut_pointer())
-                        .rem_euclid(settings.tape_length() as i32);
+                    value = settings
+                        .wrap_pointer_change(value + input.next().unwrap().unwrap_mut_pointer());
                 }
-                builder.include(I::MutPointer(value));
+                builder.include(I::MutPointer(settings.normalize_pointer_change(value)));
             },
-            I::MutCell(mut value) => {
+            I::MutCell {
+                offset: 0,
+                delta: mut value,
+            } => {
                 builder.omit(1);
-                while let Some(I::MutCell(_)) = input.peek() {
+                while let Some(I::MutCell { offset: 0, .. }) = input.peek() {
                     builder.omit(1);
-                    value = value.wrapping_add(input.next().unwrap().unwrap_mut_cell());
+                    value = settings
+                        .wrap_change(value.wrapping_add(input.next().unwrap().unwrap_mut_cell()));
                 }
-                builder.include(I::MutCell(value));
+                builder.include(I::MutCell {
+                    offset: 0,
+                    delta: value,
+                });
             },
             other => builder.preserve(other),
         }
@@ -62,18 +72,24 @@ fn merge_muts(input: Program, settings: &Settings) -> Program {
 }
 
 fn create_sets(input: Program, settings: &Settings) -> Program {
-    let mut builder = Builder::with_capacity(input.len());
+    let mut builder = Builder::new(&input);
     let mut queue = VecDeque::with_capacity(3);
 
-    for instr in input.0 {
+    for instr in input.code {
         if queue.len() == 3 {
             builder.preserve(queue.pop_front().unwrap());
         }
         queue.push_back(instr);
 
         if queue.len() == 3 {
-            if let (I::JumpRightZ(_), I::MutCell(value), I::JumpLeftNz(_)) =
-                (queue[0], queue[1], queue[2])
+            if let (
+                I::JumpRightZ(_),
+                I::MutCell {
+                    offset: 0,
+                    delta: value,
+                },
+                I::JumpLeftNz(_),
+            ) = (queue[0], queue[1], queue[2])
             {
                 if value == 1 && settings.strict() {
                     builder.preserve(queue[0]);
@@ -81,7 +97,10 @@ fn create_sets(input: Program, settings: &Settings) -> Program {
                     builder.preserve(queue[2]);
                 } else if value == -1 || value == 1 {
                     builder.omit(3);
-                    builder.include(I::SetCell(0));
+                    builder.include(I::SetCell {
+                        offset: 0,
+                        value: 0,
+                    });
                 }
                 queue.clear();
             }
@@ -95,19 +114,128 @@ fn create_sets(input: Program, settings: &Settings) -> Program {
     builder.build()
 }
 
+fn replace_loops(input: Program, replace: impl Fn(&[I]) -> Option<Vec<I>>) -> Program {
+    let mut builder = Builder::new(&input);
+    let code = input.code;
+    let mut index = 0;
+
+    while index < code.len() {
+        if let I::JumpRightZ(jump) = code[index] {
+            let end = index + jump as usize;
+            if let Some(replacement) = replace(&code[index + 1..end]) {
+                builder.omit(jump as usize + 1);
+                for instr in replacement {
+                    builder.include(instr);
+                }
+                index = end + 1;
+                continue;
+            }
+        }
+        builder.preserve(code[index]);
+        index += 1;
+    }
+
+    builder.build()
+}
+
+fn create_mul_adds(input: Program, settings: &Settings) -> Program {
+    // The target of a multiplication is accessed even if the loop would not run, which could grow the tape.
+    if settings.strict() || settings.tape_mode() != TapeMode::Fixed {
+        return input;
+    }
+
+    replace_loops(input, |body| {
+        let mut mul_adds = collect_mul_adds(body, settings)?;
+        mul_adds.push(I::SetCell {
+            offset: 0,
+            value: 0,
+        });
+        Some(mul_adds)
+    })
+}
+
+fn collect_mul_adds(body: &[I], settings: &Settings) -> Option<Vec<I>> {
+    let mut offset = 0;
+    let mut changes: Vec<(i32, i32)> = Vec::new();
+
+    for instr in body {
+        match *instr {
+            I::MutPointer(change) => offset = settings.wrap_pointer_change(offset + change),
+            I::MutCell { offset: at, delta } => {
+                let at = settings.wrap_pointer_change(offset + at);
+                match changes.iter_mut().find(|(o, _)| *o == at) {
+                    Some((_, total)) => *total = settings.wrap_change(total.wrapping_add(delta)),
+                    None => changes.push((at, settings.wrap_change(delta))),
+                }
+            },
+            _ => return None,
+        }
+    }
+
+    if offset != 0 {
+        return None;
+    }
+
+    let step = changes.iter().find(|(o, _)| *o == 0)?.1;
+    if step != 1 && step != -1 {
+        return None;
+    }
+
+    Some(
+        changes
+            .into_iter()
+            .filter(|&(offset, change)| offset != 0 && change != 0)
+            .map(|(offset, change)| I::MulAdd {
+                offset: settings.normalize_pointer_change(offset),
+                factor: settings.wrap_change(change.wrapping_mul(-step)),
+            })
+            .collect(),
+    )
+}
+
+fn create_scans(input: Program, settings: &Settings) -> Program {
+    replace_loops(input, |body| {
+        let mut moves = body.iter().map(|instr| match instr {
+            I::MutPointer(change) => Some(*change as i64),
+            _ => None,
+        });
+
+        let (first, rest) = (moves.next()??, moves.collect::<Option<Vec<_>>>()?);
+        // Merged moves would overflow the tape by a whole stride, instead of by a single move.
+        if !settings.merges_moves() && !rest.is_empty() {
+            return None;
+        }
+        if rest.iter().any(|change| change.signum() != first.signum()) {
+            return None;
+        }
+
+        let change = i32::try_from(first + rest.iter().sum::<i64>()).ok()?;
+        let change = settings.normalize_pointer_change(settings.wrap_pointer_change(change));
+
+        match change {
+            0 => None,
+            right if right > 0 => Some(vec![I::ScanRight(right.unsigned_abs())]),
+            left => Some(vec![I::ScanLeft(left.unsigned_abs())]),
+        }
+    })
+}
+
 fn reduce_cell_chains(input: Program, settings: &Settings) -> Program {
-    let mut builder = Builder::with_capacity(input.len());
+    let mut builder = Builder::new(&input);
     let mut chain = (None, Vec::new());
 
-    let include_all_changes = |builder: &mut Builder, changes: &[i8]| {
+    let include_all_changes = |builder: &mut Builder, changes: &[i32]| {
         for change in changes {
             if *change != 0 {
-                builder.include(I::MutCell(*change));
+                builder.include(I::MutCell {
+                    offset: 0,
+                    delta: *change,
+                });
             }
         }
     };
 
-    let change_value = |mut value: u8, changes: &[i8]| {
+    let change_value = |mut value: u32, changes: &[i32]| {
         for change in changes {
             if let Some(new) = settings.mut_cell(value, *change) {
                 value = new;
@@ -123,40 +251,49 @@ fn reduce_cell_chains(input: Program, settings: &Settings) -> Program {
             let (set, changes) = mem::take(&mut chain);
             match set {
                 Some(value) => match change_value(value, &changes) {
-                    Ok(new) => builder.include(I::SetCell(new)),
-                    Err(_) => return builder.overflow(),
+                    Ok(new) => builder.include(I::SetCell {
+                        offset: 0,
+                        value: new,
+                    }),
+                    Err(_) => return builder.overflow(settings),
                 },
                 None =>
                     if settings.strict() {
                         include_all_changes(&mut builder, &changes);
                     } else {
-                        let value = changes
-                            .iter()
-                            .fold(0i8, |acc, &change| acc.wrapping_add(change));
+                        let value = changes.iter().fold(0i32, |acc, &change| {
+                            settings.wrap_change(acc.wrapping_add(change))
+                        });
                         if value != 0 {
-                            builder.include(I::MutCell(value));
+                            builder.include(I::MutCell {
+                                offset: 0,
+                                delta: value,
+                            });
                         }
                     },
             };
         };
     }
 
-    for instr in input.0 {
+    for instr in input.code {
         match instr {
-            I::SetCell(value) => {
+            I::SetCell { offset: 0, value } => {
                 builder.omit(1);
                 if settings.strict() {
                     match chain.0 {
                         Some(value) =>
                             if change_value(value, &chain.1).is_err() {
-                                return builder.overflow();
+                                return builder.overflow(settings);
                             },
                         None => include_all_changes(&mut builder, &chain.1),
                     }
                 }
                 chain = (Some(value), Vec::new());
             },
-            I::MutCell(change) => {
+            I::MutCell {
+                offset: 0,
+                delta: change,
+            } => {
                 builder.omit(1);
                 chain.1.push(change);
             },
@@ -172,21 +309,99 @@ fn reduce_cell_chains(input: Program, settings: &Settings) -> Program {
     builder.build()
 }
 
+fn create_offsets(input: Program, settings: &Settings) -> Program {
+    if !settings.merges_moves() {
+        return input;
+    }
+
+    let mut builder = Builder::new(&input);
+    let mut offset = 0;
+    let mut cells: Vec<(i32, Option<u32>, i32)> = Vec::new();
+
+    let finish_block =
+        |builder: &mut Builder, offset: &mut i32, cells: &mut Vec<(i32, Option<u32>, i32)>| {
+            for (at, set, delta) in cells.drain(..) {
+                let at = settings.normalize_pointer_change(at);
+                match set {
+                    Some(value) => builder.include(I::SetCell {
+                        offset: at,
+                        value: value.wrapping_add_signed(delta) & settings.cell_width().max(),
+                    }),
+                    None if delta != 0 => builder.include(I::MutCell { offset: at, delta }),
+                    None => (),
+                }
+            }
+            if *offset != 0 {
+                builder.include(I::MutPointer(
+                    settings.normalize_pointer_change(mem::take(offset)),
+                ));
+            }
+        };
+
+    fn cell(cells: &mut Vec<(i32, Option<u32>, i32)>, at: i32) -> &mut (i32, Option<u32>, i32) {
+        match cells.iter().position(|(o, ..)| *o == at) {
+            Some(index) => &mut cells[index],
+            None => {
+                cells.push((at, None, 0));
+                cells.last_mut().unwrap()
+            },
+        }
+    }
+
+    for instr in input.code {
+        match instr {
+            I::MutPointer(change) => {
+                builder.omit(1);
+                offset = settings.wrap_pointer_change(offset + change);
+            },
+            I::MutCell { offset: at, delta } => {
+                builder.omit(1);
+                let cell = cell(&mut cells, settings.wrap_pointer_change(offset + at));
+                cell.2 = settings.wrap_change(cell.2.wrapping_add(delta));
+            },
+            I::SetCell { offset: at, value } => {
+                builder.omit(1);
+                let cell = cell(&mut cells, settings.wrap_pointer_change(offset + at));
+                (cell.1, cell.2) = (Some(value), 0);
+            },
+            other => {
+                finish_block(&mut builder, &mut offset, &mut cells);
+                builder.preserve(other);
+            },
+        }
+    }
+
+    finish_block(&mut builder, &mut offset, &mut cells);
+
+    builder.build()
+}
+
 fn remove_unused_loops(input: Program) -> Program {
-    let mut input = input.0.into_iter();
-    let mut builder = Builder::with_capacity(input.len());
+    let mut builder = Builder::new(&input);
+    let mut input = input.code.into_iter();
     let mut can_remove = true;
 
     while let Some(instr) = input.next() {
         match instr {
-            I::SetCell(0) => {
+            I::SetCell {
+                offset: 0,
+                value: 0,
+            } => {
                 builder.preserve(instr);
                 can_remove = true;
             },
-            I::SetCell(_) | I::MutPointer(_) | I::MutCell(_) | I::Input => {
+            I::SetCell { .. }
+            | I::MutPointer(_)
+            | I::MutCell { .. }
+            | I::MulAdd { .. }
+            | I::Input => {
                 builder.preserve(instr);
                 can_remove = false;
             },
+            I::ScanRight(_) | I::ScanLeft(_) => {
+                builder.preserve(instr);
+                can_remove = true;
+            },
             I::JumpLeftNz(_) | I::Breakpoint(_) | I::Output => builder.preserve(instr),
             I::JumpRightZ(_) =>
                 if can_remove {
@@ -215,7 +430,9 @@ fn remove_unused_loops(input: Program) -> Program {
 }
 
 mod builder {
-    use super::{Program, I};
+    use std::{ops::Range, vec};
+
+    use super::{Program, Settings, I};
 
     struct JumpEntry {
         index: usize,
@@ -229,20 +446,42 @@ mod builder {
         }
     }
 
+    /// Builds the output of an optimizer pass, fixing jumps and keeping track of source spans.
+    ///
+    /// Each input instruction must be either preserved or omitted, in order.
+    /// Included instructions cover the spans of all instructions omitted right before them.
     pub struct Builder {
         jumps: Vec<JumpEntry>,
         result: Vec<I>,
+        spans: Vec<Range<usize>>,
+        input_spans: Option<vec::IntoIter<Range<usize>>>,
+        omitted: Option<Range<usize>>,
+        included: bool,
     }
 
     impl Builder {
-        pub fn with_capacity(capacity: usize) -> Self {
+        pub fn new(input: &Program) -> Self {
             Self {
                 jumps: Vec::new(),
-                result: Vec::with_capacity(capacity),
+                result: Vec::with_capacity(input.len()),
+                spans: Vec::with_capacity(input.spans.len()),
+                // Programs without spans produce programs without spans.
+                input_spans: (input.spans.len() == input.len())
+                    .then(|| input.spans.clone().into_iter()),
+                omitted: None,
+                included: false,
             }
         }
 
+        fn next_span(&mut self) -> Option<Range<usize>> {
+            self.input_spans.as_mut().and_then(Iterator::next)
+        }
+
         pub fn preserve(&mut self, instr: I) {
+            if let Some(span) = self.next_span() {
+                self.spans.push(span);
+            }
+            self.omitted = None;
             self.result.push(instr);
             let last = self.result.len() - 1;
             match instr {
@@ -261,6 +500,11 @@ mod builder {
         }
 
         pub fn include(&mut self, instr: I) {
+            if self.input_spans.is_some() {
+                let end = self.spans.last().map_or(0, |span| span.end);
+                self.spans.push(self.omitted.clone().unwrap_or(end..end));
+            }
+            self.included = true;
             self.result.push(instr);
             for entry in &mut self.jumps {
                 entry.changed += 1;
@@ -268,19 +512,40 @@ mod builder {
         }
 
         pub fn omit(&mut self, count: usize) {
+            if self.included {
+                self.omitted = None;
+                self.included = false;
+            }
+            for _ in 0..count {
+                if let Some(span) = self.next_span() {
+                    self.omitted = Some(match self.omitted.take() {
+                        Some(omitted) => omitted.start.min(span.start)..omitted.end.max(span.end),
+                        None => span,
+                    });
+                }
+            }
             for entry in &mut self.jumps {
                 entry.changed -= count as i32;
             }
         }
 
         pub fn build(self) -> Program {
-            Program(self.result)
+            Program {
+                code: self.result,
+                spans: self.spans,
+            }
         }
 
-        pub fn overflow(mut self) -> Program {
-            self.result.push(I::SetCell(255));
-            self.result.push(I::MutCell(1));
-            Program(self.result)
+        pub fn overflow(mut self, settings: &Settings) -> Program {
+            self.include(I::SetCell {
+                offset: 0,
+                value: settings.cell_width().max(),
+            });
+            self.include(I::MutCell {
+                offset: 0,
+                delta: 1,
+            });
+            self.build()
         }
     }
 }
@@ -294,7 +559,10 @@ mod tests {
     use test_case::test_case;
 
     use super::{optimize, Program, Settings, I};
-    use crate::interpreter::Engine;
+    use crate::{
+        interpreter::{BrainIo, Engine},
+        CellWidth, TapeMode,
+    };
 
     fn rand_range<T, R>(gen: &mut Gen, range: R) -> T
     where
@@ -310,12 +578,18 @@ mod tests {
     impl Arbitrary for SimpleProgram {
         fn arbitrary(gen: &mut Gen) -> Self {
             let len = rand_range(gen, 0..256);
-            Self(Program(
+            Self(Program::from_code(
                 (0..len)
                     .map(|_| {
-                        let mut_cell = I::MutCell(rand_range(gen, -128..=127));
+                        let mut_cell = I::MutCell {
+                            offset: 0,
+                            delta: rand_range(gen, -128..=127),
+                        };
                         let mut_pointer = I::MutPointer(rand_range(gen, -100..=100));
-                        let set_cell = I::SetCell(rand_range(gen, 0..=255));
+                        let set_cell = I::SetCell {
+                            offset: 0,
+                            value: rand_range(gen, 0..=255),
+                        };
                         let breakpoint = I::Breakpoint(rand_range(gen, 0..len));
                         *gen.choose(&[mut_cell, mut_pointer, set_cell, breakpoint])
                             .unwrap()
@@ -328,11 +602,17 @@ mod tests {
     impl Arbitrary for Settings {
         fn arbitrary(g: &mut Gen) -> Self {
             let len = *g.choose(&[3, 10, 100, 256, 1024]).unwrap();
-            *g.choose(&[
+            let cell_width = *g
+                .choose(&[CellWidth::U8, CellWidth::U16, CellWidth::U32])
+                .unwrap();
+            let tape_mode = *g.choose(&[TapeMode::Fixed, TapeMode::GrowBoth]).unwrap();
+            g.choose(&[
                 Settings::try_new(len, false, false).unwrap(),
                 Settings::try_new(len, true, false).unwrap(),
             ])
             .unwrap()
+            .with_cell_width(cell_width)
+            .with_tape_mode(tape_mode)
         }
     }
 
@@ -340,23 +620,87 @@ mod tests {
     fn merges_mut_cells_without_strict() {
         assert_eq!(
             optimize(
-                Program(vec![I::MutCell(3), I::MutCell(127), I::MutCell(-128)]),
+                Program::from_code(vec![
+                    I::MutCell {
+                        offset: 0,
+                        delta: 3
+                    },
+                    I::MutCell {
+                        offset: 0,
+                        delta: 127
+                    },
+                    I::MutCell {
+                        offset: 0,
+                        delta: -128
+                    }
+                ]),
                 &Settings::new(),
             ),
-            Program(vec![I::MutCell(2)])
+            Program::from_code(vec![I::MutCell {
+                offset: 0,
+                delta: 2
+            }])
         );
 
         assert_eq!(
             optimize(
-                Program(vec![
-                    I::MutCell(127),
-                    I::MutCell(1),
+                Program::from_code(vec![
+                    I::MutCell {
+                        offset: 0,
+                        delta: 127
+                    },
+                    I::MutCell {
+                        offset: 0,
+                        delta: 1
+                    },
                     I::MutPointer(1),
-                    I::MutCell(-13)
+                    I::MutCell {
+                        offset: 0,
+                        delta: -13
+                    }
                 ]),
                 &Settings::new(),
             ),
-            Program(vec![I::MutCell(-128), I::MutPointer(1), I::MutCell(-13)])
+            Program::from_code(vec![
+                I::MutCell {
+                    offset: 0,
+                    delta: -128
+                },
+                I::MutCell {
+                    offset: 1,
+                    delta: -13
+                },
+                I::MutPointer(1)
+            ])
+        );
+    }
+
+    #[test]
+    fn merges_pointer_moves_into_shortest_change() {
+        assert_eq!(
+            Program::compile("<<<>.", &Settings::new()).unwrap().code,
+            [I::MutPointer(-2), I::Output]
+        );
+    }
+
+    #[test]
+    fn merges_mut_cells_with_wider_cells() {
+        let settings = Settings::new().with_cell_width(CellWidth::U16);
+        assert_eq!(
+            Program::compile("+".repeat(300), &settings).unwrap().code,
+            vec![I::MutCell {
+                offset: 0,
+                delta: 300
+            }]
+        );
+        assert_eq!(
+            Program::compile("-".repeat(65_537), &settings)
+                .unwrap()
+                .code,
+            vec![I::MutCell {
+                offset: 0,
+                delta: -1
+            }]
         );
     }
 
@@ -364,10 +708,32 @@ mod tests {
     fn does_not_merge_mut_cells_with_strict() {
         assert_eq!(
             optimize(
-                Program(vec![I::SetCell(250), I::MutCell(10), I::MutCell(-9)]),
+                Program::from_code(vec![
+                    I::SetCell {
+                        offset: 0,
+                        value: 250
+                    },
+                    I::MutCell {
+                        offset: 0,
+                        delta: 10
+                    },
+                    I::MutCell {
+                        offset: 0,
+                        delta: -9
+                    }
+                ]),
                 &Settings::new().with_strict()
             ),
-            Program(vec![I::SetCell(255), I::MutCell(1)])
+            Program::from_code(vec![
+                I::SetCell {
+                    offset: 0,
+                    value: 255
+                },
+                I::MutCell {
+                    offset: 0,
+                    delta: 1
+                }
+            ])
         )
     }
 
@@ -376,21 +742,39 @@ mod tests {
     fn edits_jumps(settings: Settings) {
         assert_eq!(
             optimize(
-                Program(vec![
-                    I::SetCell(1),
+                Program::from_code(vec![
+                    I::SetCell {
+                        offset: 0,
+                        value: 1
+                    },
                     I::JumpRightZ(5),
-                    I::SetCell(1),
-                    I::SetCell(2),
-                    I::SetCell(3),
+                    I::SetCell {
+                        offset: 0,
+                        value: 1
+                    },
+                    I::SetCell {
+                        offset: 0,
+                        value: 2
+                    },
+                    I::SetCell {
+                        offset: 0,
+                        value: 3
+                    },
                     I::MutPointer(1),
                     I::JumpLeftNz(5)
                 ]),
                 &settings
             ),
-            Program(vec![
-                I::SetCell(1),
+            Program::from_code(vec![
+                I::SetCell {
+                    offset: 0,
+                    value: 1
+                },
                 I::JumpRightZ(3),
-                I::SetCell(3),
+                I::SetCell {
+                    offset: 0,
+                    value: 3
+                },
                 I::MutPointer(1),
                 I::JumpLeftNz(3)
             ])
@@ -401,17 +785,26 @@ mod tests {
     fn merges_and_creates_sets_without_stricts() {
         assert_eq!(
             optimize(
-                Program(vec![
+                Program::from_code(vec![
                     I::MutPointer(5),
                     I::JumpRightZ(3),
-                    I::MutCell(3),
-                    I::MutCell(-4),
+                    I::MutCell {
+                        offset: 0,
+                        delta: 3
+                    },
+                    I::MutCell {
+                        offset: 0,
+                        delta: -4
+                    },
                     I::JumpLeftNz(3),
                     I::MutPointer(-5)
                 ]),
                 &Settings::new()
             ),
-            Program(vec![I::MutPointer(5), I::SetCell(0), I::MutPointer(-5)])
+            Program::from_code(vec![I::SetCell {
+                offset: 5,
+                value: 0
+            }])
         )
     }
 
@@ -420,10 +813,20 @@ mod tests {
     fn creates_sets(settings: Settings) {
         assert_eq!(
             optimize(
-                Program(vec![I::JumpRightZ(3), I::MutCell(-1), I::JumpLeftNz(3)]),
+                Program::from_code(vec![
+                    I::JumpRightZ(3),
+                    I::MutCell {
+                        offset: 0,
+                        delta: -1
+                    },
+                    I::JumpLeftNz(3)
+                ]),
                 &settings
             ),
-            Program(vec![I::SetCell(0)])
+            Program::from_code(vec![I::SetCell {
+                offset: 0,
+                value: 0
+            }])
         )
     }
 
@@ -431,19 +834,31 @@ mod tests {
     fn preserves_loop_overflow_with_strict() {
         assert_eq!(
             optimize(
-                Program(vec![
-                    I::SetCell(1),
+                Program::from_code(vec![
+                    I::SetCell {
+                        offset: 0,
+                        value: 1
+                    },
                     I::JumpRightZ(3),
-                    I::MutCell(1),
+                    I::MutCell {
+                        offset: 0,
+                        delta: 1
+                    },
                     I::JumpLeftNz(3),
                     I::MutPointer(3)
                 ]),
                 &Settings::new().with_strict()
             ),
-            Program(vec![
-                I::SetCell(1),
+            Program::from_code(vec![
+                I::SetCell {
+                    offset: 0,
+                    value: 1
+                },
                 I::JumpRightZ(3),
-                I::MutCell(1),
+                I::MutCell {
+                    offset: 0,
+                    delta: 1
+                },
                 I::JumpLeftNz(3),
                 I::MutPointer(3)
             ])
@@ -454,15 +869,30 @@ mod tests {
     fn removes_leading_muts_without_strict() {
         assert_eq!(
             optimize(
-                Program(vec![
-                    I::MutCell(5),
-                    I::MutCell(-3),
-                    I::SetCell(10),
-                    I::MutCell(-2)
+                Program::from_code(vec![
+                    I::MutCell {
+                        offset: 0,
+                        delta: 5
+                    },
+                    I::MutCell {
+                        offset: 0,
+                        delta: -3
+                    },
+                    I::SetCell {
+                        offset: 0,
+                        value: 10
+                    },
+                    I::MutCell {
+                        offset: 0,
+                        delta: -2
+                    }
                 ]),
                 &Settings::new()
             ),
-            Program(vec![I::SetCell(8)])
+            Program::from_code(vec![I::SetCell {
+                offset: 0,
+                value: 8
+            }])
         )
     }
 
@@ -470,15 +900,40 @@ mod tests {
     fn does_not_remove_leading_muts_with_strict() {
         assert_eq!(
             optimize(
-                Program(vec![
-                    I::MutCell(5),
-                    I::MutCell(-3),
-                    I::SetCell(10),
-                    I::MutCell(-2)
+                Program::from_code(vec![
+                    I::MutCell {
+                        offset: 0,
+                        delta: 5
+                    },
+                    I::MutCell {
+                        offset: 0,
+                        delta: -3
+                    },
+                    I::SetCell {
+                        offset: 0,
+                        value: 10
+                    },
+                    I::MutCell {
+                        offset: 0,
+                        delta: -2
+                    }
                 ]),
                 &Settings::new().with_strict()
             ),
-            Program(vec![I::MutCell(5), I::MutCell(-3), I::SetCell(8)])
+            Program::from_code(vec![
+                I::MutCell {
+                    offset: 0,
+                    delta: 5
+                },
+                I::MutCell {
+                    offset: 0,
+                    delta: -3
+                },
+                I::SetCell {
+                    offset: 0,
+                    value: 8
+                }
+            ])
         )
     }
 
@@ -486,18 +941,37 @@ mod tests {
     fn removes_instructions_after_overflow() {
         assert_eq!(
             optimize(
-                Program(vec![
+                Program::from_code(vec![
                     I::MutPointer(-3),
-                    I::SetCell(200),
-                    I::MutCell(100),
+                    I::SetCell {
+                        offset: 0,
+                        value: 200
+                    },
+                    I::MutCell {
+                        offset: 0,
+                        delta: 100
+                    },
                     I::MutPointer(3),
                     I::JumpRightZ(2),
-                    I::MutCell(-1),
+                    I::MutCell {
+                        offset: 0,
+                        delta: -1
+                    },
                     I::JumpLeftNz(2),
                 ]),
                 &Settings::new().with_strict()
             ),
-            Program(vec![I::MutPointer(-3), I::SetCell(255), I::MutCell(1)])
+            Program::from_code(vec![
+                I::MutPointer(-3),
+                I::SetCell {
+                    offset: 0,
+                    value: 255
+                },
+                I::MutCell {
+                    offset: 0,
+                    delta: 1
+                }
+            ])
         );
     }
 
@@ -505,7 +979,7 @@ mod tests {
     fn removes_loops_which_cant_be_entered() {
         assert_eq!(
             optimize(
-                Program(vec![
+                Program::from_code(vec![
                     I::JumpRightZ(3),
                     I::MutPointer(1),
                     I::JumpLeftNz(3),
@@ -514,24 +988,448 @@ mod tests {
                     I::MutPointer(1),
                     I::JumpLeftNz(3),
                     I::MutPointer(3),
-                    I::SetCell(0),
+                    I::SetCell {
+                        offset: 0,
+                        value: 0
+                    },
                     I::JumpRightZ(3),
                     I::MutPointer(1),
                     I::JumpLeftNz(3)
                 ]),
                 &Settings::new()
             ),
-            Program(vec![
+            Program::from_code(vec![
                 I::MutPointer(3),
                 I::JumpRightZ(3),
                 I::MutPointer(1),
                 I::JumpLeftNz(3),
+                I::SetCell {
+                    offset: 3,
+                    value: 0
+                },
                 I::MutPointer(3),
-                I::SetCell(0),
             ])
         )
     }
 
+    #[test]
+    fn creates_mul_adds_without_strict() {
+        assert_eq!(
+            optimize(
+                Program::from_code(vec![
+                    I::MutCell {
+                        offset: 0,
+                        delta: 5
+                    },
+                    I::JumpRightZ(7),
+                    I::MutCell {
+                        offset: 0,
+                        delta: -1
+                    },
+                    I::MutPointer(1),
+                    I::MutCell {
+                        offset: 0,
+                        delta: 1
+                    },
+                    I::MutPointer(1),
+                    I::MutCell {
+                        offset: 0,
+                        delta: 2
+                    },
+                    I::MutPointer(-2),
+                    I::JumpLeftNz(7),
+                ]),
+                &Settings::new()
+            ),
+            Program::from_code(vec![
+                I::MutCell {
+                    offset: 0,
+                    delta: 5
+                },
+                I::MulAdd {
+                    offset: 1,
+                    factor: 1
+                },
+                I::MulAdd {
+                    offset: 2,
+                    factor: 2
+                },
+                I::SetCell {
+                    offset: 0,
+                    value: 0
+                },
+            ])
+        )
+    }
+
+    #[test]
+    fn creates_mul_adds_for_incrementing_loops() {
+        assert_eq!(
+            optimize(
+                Program::from_code(vec![
+                    I::MutCell {
+                        offset: 0,
+                        delta: 5
+                    },
+                    I::JumpRightZ(5),
+                    I::MutPointer(-1),
+                    I::MutCell {
+                        offset: 0,
+                        delta: 3
+                    },
+                    I::MutPointer(1),
+                    I::MutCell {
+                        offset: 0,
+                        delta: 1
+                    },
+                    I::JumpLeftNz(5),
+                ]),
+                &Settings::new()
+            ),
+            Program::from_code(vec![
+                I::MutCell {
+                    offset: 0,
+                    delta: 5
+                },
+                I::MulAdd {
+                    offset: -1,
+                    factor: -3
+                },
+                I::SetCell {
+                    offset: 0,
+                    value: 0
+                },
+            ])
+        )
+    }
+
+    #[test]
+    fn does_not_create_mul_adds_with_strict() {
+        let program = Program::from_code(vec![
+            I::MutCell {
+                offset: 0,
+                delta: 5,
+            },
+            I::JumpRightZ(5),
+            I::MutCell {
+                offset: 0,
+                delta: -1,
+            },
+            I::MutPointer(1),
+            I::MutCell {
+                offset: 0,
+                delta: 1,
+            },
+            I::MutPointer(-1),
+            I::JumpLeftNz(5),
+        ]);
+        assert_eq!(
+            optimize(program.clone(), &Settings::new().with_strict()),
+            program
+        )
+    }
+
+    #[test_case(TapeMode::GrowRight; "growing right")]
+    #[test_case(TapeMode::GrowBoth; "growing both ways")]
+    fn does_not_create_mul_adds_with_growing_tape(tape_mode: TapeMode) {
+        let program = Program::from_code(vec![
+            I::JumpRightZ(5),
+            I::MutCell {
+                offset: 0,
+                delta: -1,
+            },
+            I::MutPointer(-1),
+            I::MutCell {
+                offset: 0,
+                delta: 1,
+            },
+            I::MutPointer(1),
+            I::JumpLeftNz(5),
+        ]);
+        let optimized = optimize(program, &Settings::new().with_tape_mode(tape_mode));
+        assert!(!optimized
+            .code()
+            .iter()
+            .any(|i| matches!(i, I::MulAdd { .. })));
+    }
+
+    #[test]
+    fn creates_scans_without_strict() {
+        assert_eq!(
+            optimize(
+                Program::from_code(vec![
+                    I::MutCell {
+                        offset: 0,
+                        delta: 1
+                    },
+                    I::JumpRightZ(2),
+                    I::MutPointer(2),
+                    I::JumpLeftNz(2),
+                    I::JumpRightZ(3),
+                    I::MutPointer(-1),
+                    I::MutPointer(-1),
+                    I::JumpLeftNz(3),
+                ]),
+                &Settings::new()
+            ),
+            Program::from_code(vec![
+                I::MutCell {
+                    offset: 0,
+                    delta: 1
+                },
+                I::ScanRight(2),
+                I::ScanLeft(2)
+            ])
+        )
+    }
+
+    #[test]
+    fn creates_scans_with_strict() {
+        assert_eq!(
+            optimize(
+                Program::from_code(vec![
+                    I::MutCell {
+                        offset: 0,
+                        delta: 1
+                    },
+                    I::JumpRightZ(2),
+                    I::MutPointer(2),
+                    I::JumpLeftNz(2),
+                    I::JumpRightZ(2),
+                    I::MutPointer(-3),
+                    I::JumpLeftNz(2),
+                ]),
+                &Settings::new().with_strict()
+            ),
+            Program::from_code(vec![
+                I::MutCell {
+                    offset: 0,
+                    delta: 1
+                },
+                I::ScanRight(2),
+                I::ScanLeft(3)
+            ])
+        )
+    }
+
+    #[test]
+    fn does_not_create_scans_for_merged_moves_with_strict() {
+        let program = Program::from_code(vec![
+            I::MutCell {
+                offset: 0,
+                delta: 1,
+            },
+            I::JumpRightZ(3),
+            I::MutPointer(1),
+            I::MutPointer(1),
+            I::JumpLeftNz(3),
+        ]);
+        assert_eq!(
+            optimize(program.clone(), &Settings::new().with_strict()),
+            program
+        )
+    }
+
+    #[test]
+    fn creates_scans_for_wrapping_moves() {
+        assert_eq!(
+            optimize(
+                Program::from_code(vec![
+                    I::MutCell {
+                        offset: 0,
+                        delta: 1
+                    },
+                    I::JumpRightZ(2),
+                    I::MutPointer(Settings::DEFAULT_LENGTH as i32 - 1),
+                    I::JumpLeftNz(2),
+                ]),
+                &Settings::new()
+            ),
+            Program::from_code(vec![
+                I::MutCell {
+                    offset: 0,
+                    delta: 1
+                },
+                I::ScanLeft(1)
+            ])
+        )
+    }
+
+    #[test]
+    fn does_not_create_scans_for_mixed_moves() {
+        let program = Program::from_code(vec![
+            I::MutCell {
+                offset: 0,
+                delta: 1,
+            },
+            I::JumpRightZ(3),
+            I::MutPointer(2),
+            I::MutPointer(-1),
+            I::JumpLeftNz(3),
+        ]);
+        assert_eq!(
+            optimize(program.clone(), &Settings::new().with_strict()),
+            program
+        )
+    }
+
+    #[test]
+    fn creates_offsets_without_strict() {
+        assert_eq!(
+            Program::compile(">+>++<<--->>>>", &Settings::new())
+                .unwrap()
+                .code,
+            vec![
+                I::MutCell {
+                    offset: 1,
+                    delta: 1
+                },
+                I::MutCell {
+                    offset: 2,
+                    delta: 2
+                },
+                I::MutCell {
+                    offset: 0,
+                    delta: -3
+                },
+                I::MutPointer(4),
+            ]
+        )
+    }
+
+    #[test]
+    fn creates_offsets_around_loops() {
+        assert_eq!(
+            Program::compile("<-[>+<-]>>+", &Settings::new())
+                .unwrap()
+                .code,
+            vec![
+                I::MutCell {
+                    offset: -1,
+                    delta: -1
+                },
+                I::MutPointer(-1),
+                I::MulAdd {
+                    offset: 1,
+                    factor: 1
+                },
+                I::SetCell {
+                    offset: 0,
+                    value: 0
+                },
+                I::MutCell {
+                    offset: 2,
+                    delta: 1
+                },
+                I::MutPointer(2),
+            ]
+        )
+    }
+
+    #[test]
+    fn does_not_create_offsets_with_strict() {
+        let program = Program::from_code(vec![
+            I::MutPointer(1),
+            I::MutCell {
+                offset: 0,
+                delta: 1,
+            },
+            I::MutPointer(-1),
+        ]);
+        assert_eq!(
+            optimize(program.clone(), &Settings::new().with_strict()),
+            program
+        )
+    }
+
+    #[test_case("+++++[->+>++<<]"; "copy and multiply")]
+    #[test_case(">>+++[-<<+++++>>]"; "negative offset")]
+    #[test_case("+++[+>-<]"; "incrementing loop")]
+    #[test_case("++[>+++[->++<]<-]"; "nested loops")]
+    #[test_case("+[->+>>>+<<<<]"; "wrapping offset")]
+    #[test_case("++[->+<>>]"; "unbalanced loop")]
+    #[test_case("+>+>+>>+<<<<[>]"; "scan right")]
+    #[test_case(">+>+<+[<<]"; "scan left with stride")]
+    #[test_case(">+<+<+[>]"; "wrapping scan")]
+    #[test_case("+>++>+++<<[>+<-]>>>-<<<"; "offset block")]
+    #[test_case("+++>-<[-]<<<<+>>>->+"; "wrapping offset block")]
+    fn creates_equivalent_loops(source: &str) {
+        assert_equivalent_loops(source, Settings::try_new(5, false, false).unwrap());
+    }
+
+    #[test_case("+[->+>>>+<<<<]"; "offset past the edge")]
+    #[test_case("<<+>+>+[<]"; "scan past the edge")]
+    #[test_case("+++>-<[-]<<<<+>>>->+"; "offset block past the edge")]
+    fn creates_equivalent_loops_with_growing_tape(source: &str) {
+        assert_equivalent_loops(
+            source,
+            Settings::try_new(5, false, false)
+                .unwrap()
+                .with_tape_mode(TapeMode::GrowBoth),
+        );
+    }
+
+    #[test_case("+++++[->+>++<<]", CellWidth::U16; "copy and multiply with 16-bit cells")]
+    #[test_case("+++++[->+>++<<]", CellWidth::U32; "copy and multiply with 32-bit cells")]
+    #[test_case("-[->+<]>[-<+>]", CellWidth::U16; "moving wide value")]
+    #[test_case("++>+++<[->[->+>+<<]>>[-<<+>>]<<<]", CellWidth::U32; "multiplication")]
+    fn creates_equivalent_loops_with_wider_cells(source: &str, cell_width: CellWidth) {
+        let settings = Settings::try_new(5, false, false).unwrap();
+        assert_equivalent_loops(source, settings.with_cell_width(cell_width));
+    }
+
+    fn assert_equivalent_loops(source: &str, settings: Settings) {
+        let before = Program::compile(source, &settings.with_debug()).unwrap();
+        let mut before_eng = Engine::new_std(before, settings);
+        before_eng.run().unwrap();
+
+        let after = Program::compile(source, &settings).unwrap();
+        let mut after_eng = Engine::new_std(after, settings);
+        after_eng.run().unwrap();
+
+        assert_eq!(cells(&before_eng), cells(&after_eng));
+        assert_eq!(before_eng.position(), after_eng.position());
+    }
+
+    #[test_case("+>+>+>+>+<<<<[>>]"; "scan right")]
+    #[test_case("+>+>+>+>+[<<]"; "scan left")]
+    #[test_case(">>+<<<"; "moves")]
+    #[test_case("++++[->+<]>[->>>+<<<]"; "loops")]
+    fn reports_same_errors_with_strict(source: &str) {
+        assert_same_errors(source, Settings::try_new(5, true, false).unwrap());
+    }
+
+    #[test_case("<>+"; "canceled moves")]
+    #[test_case("+>+<<"; "moves")]
+    #[test_case("+>+>+>+>+[<<]"; "scan left")]
+    #[test_case(">+<<+>"; "offset block")]
+    #[test_case(">+[-<<+>>]"; "multiplication loop")]
+    fn reports_same_errors_with_tape_growing_right(source: &str) {
+        assert_same_errors(
+            source,
+            Settings::try_new(5, false, false)
+                .unwrap()
+                .with_tape_mode(TapeMode::GrowRight),
+        );
+    }
+
+    fn assert_same_errors(source: &str, settings: Settings) {
+        let before = Program::compile(source, &settings.with_debug()).unwrap();
+        let after = Program::compile(source, &settings).unwrap();
+        assert_eq!(
+            Engine::new_std(before, settings).run(),
+            Engine::new_std(after, settings).run()
+        );
+    }
+
+    /// Returns the non-zero cells with their positions, since growable tapes may grow differently.
+    fn cells(eng: &Engine<impl BrainIo>) -> Vec<(i32, u32)> {
+        (eng.memory().iter().enumerate())
+            .filter(|(_, cell)| **cell != 0)
+            .map(|(index, cell)| (index as i32 - eng.origin() as i32, *cell))
+            .collect()
+    }
+
     #[quickcheck]
     fn reduces_instruction_count(simple_program: SimpleProgram, settings: Settings) -> bool {
         let SimpleProgram(program) = simple_program;
@@ -550,7 +1448,7 @@ mod tests {
         let after_res = after_eng.run();
 
         match before_res.is_ok() {
-            true => after_res.is_ok() && before_eng.memory() == after_eng.memory(),
+            true => after_res.is_ok() && cells(&before_eng) == cells(&after_eng),
             false => after_res.is_err(),
         }
     }
@@ -561,4 +1459,31 @@ mod tests {
         let out = optimize(program, &settings);
         !out.code().iter().any(|i| matches!(i, I::Breakpoint(_)))
     }
+
+    #[test]
+    fn merges_spans_of_merged_instructions() {
+        let program = Program::compile("++ .", &Settings::new()).unwrap();
+        assert_eq!(program.spans, [0..2, 3..4]);
+    }
+
+    #[test]
+    fn covers_whole_loop_with_set_span() {
+        let program = Program::compile("+.[-].", &Settings::new()).unwrap();
+        assert_eq!(program.spans, [0..1, 1..2, 2..5, 5..6]);
+    }
+
+    #[test]
+    fn covers_whole_loop_with_mul_add_spans() {
+        let program = Program::compile(".[->+<].", &Settings::new()).unwrap();
+        assert_eq!(program.spans, [0..1, 1..7, 1..7, 7..8]);
+    }
+
+    #[quickcheck]
+    fn keeps_span_for_each_instruction(program: SimpleProgram, settings: Settings) -> bool {
+        let source = program.0.to_string();
+        let optimized = Program::compile(&source, &settings).unwrap();
+        optimized.spans.len() == optimized.len()
+            && (optimized.spans.iter())
+                .all(|span| span.start < span.end && span.end <= source.len())
+    }
 }
@@ -1,4 +1,7 @@
-use std::fmt::{self, Display};
+use std::{
+    fmt::{self, Display},
+    ops::Range,
+};
 
 use crate::{
     backend::common::{emit, optimize, Instruction},
@@ -11,7 +14,11 @@ use crate::{
 /// The list can be obtained from [`Program::compile`].
 #[must_use]
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
-pub struct Program(pub(crate) Vec<Instruction>);
+pub struct Program {
+    pub(crate) code: Vec<Instruction>,
+    /// Source span of each instruction, empty if the spans are not known.
+    pub(crate) spans: Vec<Range<usize>>,
+}
 
 impl Program {
     /// Produces a [`Program`] from a string of Brainfuck source code.
@@ -27,12 +34,28 @@ impl Program {
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
     pub fn compile(source: impl AsRef<str>, settings: &Settings) -> Result<Self, ParseError> {
-        let tokens = tokenize(source.as_ref());
+        let tokens = tokenize(source.as_ref()).collect::<Vec<_>>();
+        let spans = tokens
+            .iter()
+            .map(|token| token.pos..token.pos + 1)
+            .collect();
         let ast = parse(tokens)?;
-        let program = emit(&ast);
+        // Each token is emitted as exactly one instruction, in the same order.
+        let program = Self {
+            spans,
+            ..emit(&ast)
+        };
         Ok(optimize(program, settings))
     }
 
+    /// Creates a [`Program`] from instructions, without any source spans.
+    pub(crate) fn from_code(code: Vec<Instruction>) -> Self {
+        Self {
+            code,
+            spans: Vec::new(),
+        }
+    }
+
     /// Same as [`Program::default`], returns an empty [`Program`].
     pub fn new() -> Self {
         Self::default()
@@ -44,29 +67,62 @@ impl Program {
     /// ```
     /// # use brainlib::{Program, Instruction, Settings};
     /// let program = Program::compile("+++", &Settings::new())?;
-    /// assert_eq!(program.code(), &[Instruction::MutCell(3)]);
+    /// assert_eq!(
+    ///     program.code(),
+    ///     &[Instruction::MutCell {
+    ///         offset: 0,
+    ///         delta: 3
+    ///     }]
+    /// );
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
     pub fn code(&self) -> &[Instruction] {
-        &self.0
+        &self.code
+    }
+
+    /// Returns the range of the source code (as character indices) which produced the instruction at `pc`.
+    ///
+    /// Instructions created by the optimizer cover the source code of all instructions they replace.
+    ///
+    /// # Examples
+    /// ```
+    /// # use brainlib::{Program, Settings};
+    /// let program = Program::compile("+.[-]", &Settings::new().with_debug())?;
+    /// assert_eq!(program.span(2), Some(2..3));
+    ///
+    /// let optimized = Program::compile("+.[-]", &Settings::new())?;
+    /// assert_eq!(optimized.span(2), Some(2..5));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[must_use]
+    pub fn span(&self, pc: usize) -> Option<Range<usize>> {
+        self.spans.get(pc).cloned()
+    }
+
+    /// Returns the indices of matching [`Instruction::JumpRightZ`] and [`Instruction::JumpLeftNz`] pairs, in program order.
+    pub(crate) fn loops(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.code.iter().enumerate()).filter_map(|(pc, instr)| match instr {
+            Instruction::JumpRightZ(jump) => Some((pc, pc + *jump as usize)),
+            _ => None,
+        })
     }
 
     /// Returns the number of [`Instruction`]s contained in the [`Program`].
     #[must_use]
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.code.len()
     }
 
     /// Returns `true` if the [`Program`] contains no [`Instruction`]s.
     #[must_use]
     pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.code.is_empty()
     }
 }
 
 impl Display for Program {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for instruction in &self.0 {
+        for instruction in &self.code {
             write!(f, "{}", instruction)?;
         }
         Ok(())
@@ -1,7 +1,12 @@
+use crate::backend::common::{CellWidth, EofBehavior, TapeMode};
+
 /// Conventions used for compilation and interpretation.
 ///
 /// This includes:
-/// - **tape length** - how many data cells are available
+/// - **tape length** - how many data cells are available (initially, if the tape is growable)
+/// - **tape mode** - whether the tape has a fixed length or grows when needed
+/// - **cell width** - how many bits are stored in a single data cell
+/// - **EOF behavior** - what is stored in a cell when reading past the end of input
 /// - **strictness** - should an overflow in a data cell or the pointer be treated as an error
 /// - **debugging** - should the breakpoints be enabled or ignored
 ///
@@ -15,6 +20,9 @@
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Settings {
     tape_length: u32,
+    tape_mode: TapeMode,
+    cell_width: CellWidth,
+    eof_behavior: EofBehavior,
     strict: bool,
     debug: bool,
 }
@@ -23,6 +31,9 @@ impl Default for Settings {
     fn default() -> Self {
         Self {
             tape_length: Self::DEFAULT_LENGTH,
+            tape_mode: TapeMode::Fixed,
+            cell_width: CellWidth::U8,
+            eof_behavior: EofBehavior::Zero,
             strict: false,
             debug: false,
         }
@@ -32,22 +43,31 @@ impl Default for Settings {
 impl Settings {
     /// Default tape length, conventionally equal to 30 000.
     pub const DEFAULT_LENGTH: u32 = 30_000;
+    /// Maximum tape length, equal to 1 billion.
+    ///
+    /// Growable tapes never grow past this length either.
+    pub const MAX_LENGTH: u32 = 1_000_000_000;
 
     /// Same as [`Settings::default`], returns the default settings.
     ///
-    /// Here, tape length is 30 000, strictness is disabled and debugging is disabled.
+    /// Here, tape length is 30 000 and fixed, cells are 8-bit, EOF is read as zero, strictness is disabled and debugging is disabled.
     pub fn new() -> Self {
         Self::default()
     }
 
-    /// Creates new settings with given parameters.
+    /// Creates new settings with given parameters, a fixed tape, 8-bit cells and EOF read as zero.
     ///
     /// `tape_length` must be between 3 and 1 billion (inclusive).
+    /// Use [`Settings::with_tape_mode`], [`Settings::with_cell_width`] and
+    /// [`Settings::with_eof_behavior`] to change the rest.
     #[must_use]
     pub const fn try_new(tape_length: u32, strict: bool, debug: bool) -> Option<Self> {
         match tape_length {
-            3..=1_000_000_000 => Some(Self {
+            3..=Self::MAX_LENGTH => Some(Self {
                 tape_length,
+                tape_mode: TapeMode::Fixed,
+                cell_width: CellWidth::U8,
+                eof_behavior: EofBehavior::Zero,
                 strict,
                 debug,
             }),
@@ -61,6 +81,21 @@ impl Settings {
         self.tape_length
     }
 
+    /// Returns the set tape mode.
+    pub const fn tape_mode(&self) -> TapeMode {
+        self.tape_mode
+    }
+
+    /// Returns the set cell width.
+    pub const fn cell_width(&self) -> CellWidth {
+        self.cell_width
+    }
+
+    /// Returns the set EOF behavior.
+    pub const fn eof_behavior(&self) -> EofBehavior {
+        self.eof_behavior
+    }
+
     /// Returns the set strictness.
     #[must_use]
     pub const fn strict(&self) -> bool {
@@ -73,6 +108,24 @@ impl Settings {
         self.debug
     }
 
+    /// Returns the [`Settings`] with same parameters, but with the given tape mode.
+    pub const fn with_tape_mode(self, tape_mode: TapeMode) -> Self {
+        Self { tape_mode, ..self }
+    }
+
+    /// Returns the [`Settings`] with same parameters, but with the given cell width.
+    pub const fn with_cell_width(self, cell_width: CellWidth) -> Self {
+        Self { cell_width, ..self }
+    }
+
+    /// Returns the [`Settings`] with same parameters, but with the given EOF behavior.
+    pub const fn with_eof_behavior(self, eof_behavior: EofBehavior) -> Self {
+        Self {
+            eof_behavior,
+            ..self
+        }
+    }
+
     /// Returns the [`Settings`] with same parameters, but with strictness enabled.
     pub const fn with_strict(self) -> Self {
         Self {
@@ -106,10 +159,59 @@ impl Settings {
     }
 
     #[must_use]
-    pub(crate) const fn mut_cell(&self, cell: u8, change: i8) -> Option<u8> {
+    pub(crate) const fn mut_cell(&self, cell: u32, change: i32) -> Option<u32> {
+        let max = self.cell_width.max();
         match self.strict {
-            true => cell.checked_add_signed(change),
-            false => Some(cell.wrapping_add_signed(change)),
+            true => match cell.checked_add_signed(change) {
+                Some(new) if new <= max => Some(new),
+                _ => None,
+            },
+            false => Some(cell.wrapping_add_signed(change) & max),
+        }
+    }
+
+    /// Returns the value stored in a cell equal to `cell` after reaching the end of input.
+    #[must_use]
+    pub(crate) const fn eof_value(&self, cell: u32) -> u32 {
+        match self.eof_behavior {
+            EofBehavior::Zero => 0,
+            EofBehavior::Unchanged => cell,
+            EofBehavior::Max => self.cell_width.max(),
+        }
+    }
+
+    /// Wraps the cell change to the range of the signed type with the same width as cells.
+    #[must_use]
+    pub(crate) const fn wrap_change(&self, change: i32) -> i32 {
+        let shift = 32 - self.cell_width.bits();
+        (change << shift) >> shift
+    }
+
+    /// Returns whether consecutive pointer moves can be merged, which isn't the case when moving past an edge of the tape is an error.
+    ///
+    /// The error has to be reported for the move which crossed the edge, and a move past the left edge
+    /// can't be canceled by a move back.
+    #[must_use]
+    pub(crate) const fn merges_moves(&self) -> bool {
+        !self.strict && !matches!(self.tape_mode, TapeMode::GrowRight)
+    }
+
+    /// Reduces the pointer change modulo the tape length, if the pointer wraps around the edges.
+    #[must_use]
+    pub(crate) const fn wrap_pointer_change(&self, change: i32) -> i32 {
+        match (self.strict, self.tape_mode) {
+            (false, TapeMode::Fixed) => change.rem_euclid(self.tape_length as i32),
+            _ => change,
+        }
+    }
+
+    /// Turns a change reduced with [`Settings::wrap_pointer_change`] into the shortest equivalent one.
+    #[must_use]
+    pub(crate) const fn normalize_pointer_change(&self, change: i32) -> i32 {
+        match (self.strict, self.tape_mode) {
+            (false, TapeMode::Fixed) if change > self.tape_length as i32 / 2 =>
+                change - self.tape_length as i32,
+            _ => change,
         }
     }
 
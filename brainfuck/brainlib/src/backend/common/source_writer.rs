@@ -0,0 +1,57 @@
+use std::fmt::Display;
+
+/// Builds the source code emitted by the textual backends, one indented line at a time.
+#[derive(Debug)]
+pub(crate) struct SourceWriter {
+    source: String,
+    indent: &'static str,
+    depth: usize,
+}
+
+impl SourceWriter {
+    /// Creates an empty [`SourceWriter`], which indents nested blocks with `indent`.
+    pub(crate) const fn new(indent: &'static str) -> Self {
+        Self {
+            source: String::new(),
+            indent,
+            depth: 0,
+        }
+    }
+
+    /// Writes a line at the current depth, empty lines are left unindented.
+    pub(crate) fn line(&mut self, line: impl Display) {
+        let line = line.to_string();
+        if !line.is_empty() {
+            for _ in 0..self.depth {
+                self.source.push_str(self.indent);
+            }
+            self.source.push_str(&line);
+        }
+        self.source.push('\n');
+    }
+
+    /// Writes each line of `text` at the current depth.
+    pub(crate) fn lines(&mut self, text: &str) {
+        for line in text.lines() {
+            self.line(line);
+        }
+    }
+
+    /// Writes a line which opens a block, the following lines are nested in it.
+    pub(crate) fn open(&mut self, line: impl Display) {
+        self.line(line);
+        self.depth += 1;
+    }
+
+    /// Writes a line which closes the innermost block.
+    pub(crate) fn close(&mut self, line: impl Display) {
+        self.depth = self.depth.checked_sub(1).expect("no block to close");
+        self.line(line);
+    }
+
+    /// Returns the written source code.
+    pub(crate) fn finish(self) -> String {
+        debug_assert_eq!(self.depth, 0, "unclosed block");
+        self.source
+    }
+}
@@ -0,0 +1,32 @@
+/// The shape of the tape, determining what happens when the pointer moves past its edges.
+///
+/// # Examples
+/// ```
+/// # use brainlib::{Settings, TapeMode};
+/// let settings = Settings::try_new(16, false, false)
+///     .ok_or("invalid settings")?
+///     .with_tape_mode(TapeMode::GrowBoth);
+/// assert_eq!(settings.tape_mode(), TapeMode::GrowBoth);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[must_use]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TapeMode {
+    /// The tape has a fixed length, which is allocated up front.
+    ///
+    /// Moving past an edge wraps around to the other side, or is an error in `strict` mode.
+    ///
+    /// This is the [`TapeMode::default`].
+    #[default]
+    Fixed,
+
+    /// The tape starts with the set length and grows to the right when needed.
+    ///
+    /// Moving to the left of the first cell is always an error.
+    GrowRight,
+
+    /// The tape starts with the set length and grows in both directions when needed.
+    ///
+    /// Cells to the left of the first cell have negative indices.
+    GrowBoth,
+}
@@ -0,0 +1,391 @@
+use std::{
+    future::{poll_fn, Future},
+    io, mem,
+    num::NonZeroU64,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::{
+    interpreter::{BrainIo, Engine, EngineObserver, RuntimeError},
+    Instruction, Program, Settings,
+};
+
+/// The asynchronous counterpart of [`BrainIo`], used by an [`AsyncEngine`].
+///
+/// The methods follow the usual polling protocol: [`Poll::Pending`] is returned when the operation
+/// can't complete yet, after arranging for the task to be woken up once it can make progress.
+/// This allows wrapping the I/O types of any async runtime.
+pub trait AsyncBrainIo {
+    /// Attempts to read a single byte, returns `None` at the end of input.
+    fn poll_read_byte(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<Option<u8>>>;
+
+    /// Attempts to write a single byte.
+    fn poll_write_byte(&mut self, cx: &mut Context<'_>, byte: u8) -> Poll<io::Result<()>>;
+
+    /// Attempts to write out any buffered output.
+    fn poll_flush(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let _ = cx;
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// An [`AsyncBrainIo`] which wraps a [`BrainIo`], completing every operation immediately.
+///
+/// It is only suitable for I/O which never blocks, such as [`ByteIo`](crate::interpreter::ByteIo).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncIo<Io: BrainIo>(pub Io);
+
+impl<Io: BrainIo> AsyncBrainIo for SyncIo<Io> {
+    fn poll_read_byte(&mut self, _: &mut Context<'_>) -> Poll<io::Result<Option<u8>>> {
+        Poll::Ready(self.0.read_byte())
+    }
+
+    fn poll_write_byte(&mut self, _: &mut Context<'_>, byte: u8) -> Poll<io::Result<()>> {
+        Poll::Ready(self.0.write_byte(byte))
+    }
+
+    fn poll_flush(&mut self, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.0.flush())
+    }
+}
+
+/// The asynchronous counterpart of [`Engine`], reading and writing through an [`AsyncBrainIo`].
+///
+/// It awaits the I/O instead of blocking, and [`AsyncEngine::run`] yields to the executor every
+/// few instructions, so that many programs can be multiplexed cooperatively on a single thread.
+/// The instructions are executed by an inner [`Engine`], which can be inspected with [`AsyncEngine::engine`].
+///
+/// # Examples
+/// ```
+/// # use brainlib::{interpreter::{AsyncEngine, ByteIo, SyncIo}, Program, Settings};
+/// # use std::{future::Future, pin::pin, task::{Context, Poll, Waker}};
+/// let program = Program::compile(",[.,]", &Settings::default())?;
+/// let mut output = Vec::new();
+/// let mut engine = AsyncEngine::new(
+///     program,
+///     Settings::default(),
+///     SyncIo(ByteIo::new(b"ABC", &mut output)),
+/// );
+///
+/// // Use the executor of your choice instead, e.g. `engine.run().await`.
+/// let result = {
+///     let mut run = pin!(engine.run());
+///     loop {
+///         if let Poll::Ready(result) = run.as_mut().poll(&mut Context::from_waker(Waker::noop()))
+///         {
+///             break result;
+///         }
+///     }
+/// };
+///
+/// assert_eq!(result, Ok(()));
+/// drop(engine);
+/// assert_eq!(output, b"ABC");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[must_use]
+#[derive(Debug)]
+pub struct AsyncEngine<Io: AsyncBrainIo, Obs: EngineObserver = ()> {
+    engine: Engine<StagedIo, Obs>,
+    io: Io,
+    yield_interval: NonZeroU64,
+}
+
+/// Passes the bytes between the inner [`Engine`] and the [`AsyncBrainIo`], which are awaited outside of it.
+#[derive(Debug, Default)]
+struct StagedIo {
+    /// The result of reading a byte, awaited before executing an [`Instruction::Input`].
+    input: Option<io::Result<Option<u8>>>,
+    output: Vec<u8>,
+    flush: bool,
+}
+
+impl BrainIo for StagedIo {
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        self.input.take().unwrap_or(Ok(None))
+    }
+
+    fn write_byte(&mut self, byte: u8) -> io::Result<()> {
+        self.output.push(byte);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush = true;
+        Ok(())
+    }
+}
+
+impl<Io: AsyncBrainIo> AsyncEngine<Io> {
+    /// Creates a new [`AsyncEngine`] with the provided [`Program`], [`Settings`] and [`AsyncBrainIo`].
+    pub fn new(program: Program, settings: Settings, io: Io) -> Self {
+        Self {
+            engine: Engine::new(program, settings, StagedIo::default()),
+            io,
+            yield_interval: Self::DEFAULT_YIELD_INTERVAL,
+        }
+    }
+}
+
+impl<Io: AsyncBrainIo, Obs: EngineObserver> AsyncEngine<Io, Obs> {
+    /// How many instructions [`AsyncEngine::run`] executes between yields by default.
+    pub const DEFAULT_YIELD_INTERVAL: NonZeroU64 = NonZeroU64::new(1024).unwrap();
+
+    /// Returns the [`AsyncEngine`] yielding to the executor after every `interval` instructions.
+    pub fn with_yield_interval(self, interval: NonZeroU64) -> Self {
+        Self {
+            yield_interval: interval,
+            ..self
+        }
+    }
+
+    /// Returns the [`AsyncEngine`] with the given [`EngineObserver`], see [`Engine::with_observer`].
+    pub fn with_observer<O: EngineObserver>(self, observer: O) -> AsyncEngine<Io, O> {
+        AsyncEngine {
+            engine: self.engine.with_observer(observer),
+            io: self.io,
+            yield_interval: self.yield_interval,
+        }
+    }
+
+    /// Returns the inner [`Engine`], which gives access to the execution state.
+    pub const fn engine(&self) -> &Engine<impl BrainIo, Obs> {
+        &self.engine
+    }
+
+    /// Returns the used [`EngineObserver`].
+    pub const fn observer(&self) -> &Obs {
+        self.engine.observer()
+    }
+
+    /// Returns the used [`AsyncBrainIo`].
+    pub const fn io(&self) -> &Io {
+        &self.io
+    }
+
+    /// Executes a single [`Instruction`] and returns it or a [`RuntimeError`], see [`Engine::step`].
+    pub async fn step(&mut self) -> Option<Result<Instruction, RuntimeError>> {
+        let instruction = *self.engine.program().code().get(self.engine.pc())?;
+        if instruction == Instruction::Input && self.engine.io().input.is_none() {
+            let byte = poll_fn(|cx| self.io.poll_read_byte(cx)).await;
+            self.engine.io_mut().input = Some(byte);
+        }
+
+        let result = self.engine.step()?;
+        Some(self.write_staged().await.and(result))
+    }
+
+    /// Runs the [`AsyncEngine`] until it halts or a [`RuntimeError`] occurs, see [`Engine::run`].
+    pub async fn run(&mut self) -> Result<(), RuntimeError> {
+        self.run_with_fuel(u64::MAX).await
+    }
+
+    /// Same as [`AsyncEngine::run`], but executes at most `fuel` instructions, see [`Engine::run_with_fuel`].
+    pub async fn run_with_fuel(&mut self, fuel: u64) -> Result<(), RuntimeError> {
+        let result = self.run_steps(fuel).await;
+        let result = self.engine.stop(result);
+        let written = self.write_staged().await;
+        match result {
+            Err(RuntimeError::OutOfFuel | RuntimeError::Paused) => written.and(result),
+            _ => result.and(written),
+        }
+    }
+
+    /// Same as [`AsyncEngine::run_with_fuel`], but leaves the output staged.
+    async fn run_steps(&mut self, fuel: u64) -> Result<(), RuntimeError> {
+        for steps in 1..=fuel {
+            self.engine.check_pause()?;
+            match self.step().await {
+                Some(result) => {
+                    let _: Instruction = result?;
+                },
+                None => return Ok(()),
+            }
+            if steps % self.yield_interval.get() == 0 {
+                yield_now().await;
+            }
+        }
+        match self.engine.pc() < self.engine.program().len() {
+            true => Err(RuntimeError::OutOfFuel),
+            false => Ok(()),
+        }
+    }
+
+    /// Writes out the output staged by the inner [`Engine`], and flushes it if requested.
+    async fn write_staged(&mut self) -> Result<(), RuntimeError> {
+        let staged = self.engine.io_mut();
+        let output = mem::take(&mut staged.output);
+        let flush = mem::take(&mut staged.flush);
+
+        for byte in output {
+            (poll_fn(|cx| self.io.poll_write_byte(cx, byte)).await)
+                .map_err(RuntimeError::output)?;
+        }
+        if flush {
+            (poll_fn(|cx| self.io.poll_flush(cx)).await).map_err(RuntimeError::output)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns [`Poll::Pending`] once, so that the executor can run other tasks.
+fn yield_now() -> impl Future<Output = ()> {
+    struct YieldNow(bool);
+
+    impl Future for YieldNow {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                return Poll::Ready(());
+            }
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    YieldNow(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        future::Future,
+        io,
+        num::NonZeroU64,
+        pin::{pin, Pin},
+        task::{Context, Poll, Waker},
+    };
+
+    use super::{AsyncBrainIo, AsyncEngine, SyncIo};
+    use crate::{
+        interpreter::{ByteIo, RuntimeError},
+        Program, Settings,
+    };
+
+    /// Input which is not available until it is pushed, like a network socket.
+    #[derive(Default)]
+    struct Socket {
+        input: VecDeque<u8>,
+        closed: bool,
+        output: Vec<u8>,
+        flushes: usize,
+    }
+
+    impl AsyncBrainIo for Socket {
+        fn poll_read_byte(&mut self, _: &mut Context<'_>) -> Poll<io::Result<Option<u8>>> {
+            match self.input.pop_front() {
+                Some(byte) => Poll::Ready(Ok(Some(byte))),
+                None if self.closed => Poll::Ready(Ok(None)),
+                None => Poll::Pending,
+            }
+        }
+
+        fn poll_write_byte(&mut self, _: &mut Context<'_>, byte: u8) -> Poll<io::Result<()>> {
+            self.output.push(byte);
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_flush(&mut self, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            self.flushes += 1;
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn poll<F: Future>(future: Pin<&mut F>) -> Poll<F::Output> {
+        future.poll(&mut Context::from_waker(Waker::noop()))
+    }
+
+    #[test]
+    fn runs_to_completion() {
+        let program = Program::compile("++++++++[>++++++++<-]>+.", &Settings::default()).unwrap();
+        let mut output = Vec::new();
+        let mut eng = AsyncEngine::new(
+            program,
+            Settings::default(),
+            SyncIo(ByteIo::new(&[], &mut output)),
+        );
+        let result = {
+            let mut run = pin!(eng.run());
+            loop {
+                if let Poll::Ready(result) = poll(run.as_mut()) {
+                    break result;
+                }
+            }
+        };
+        assert_eq!(result, Ok(()));
+        drop(eng);
+        assert_eq!(output, b"A");
+    }
+
+    #[test]
+    fn waits_for_input() {
+        let program = Program::compile(",[.,]", &Settings::default()).unwrap();
+        let mut eng = AsyncEngine::new(program, Settings::default(), Socket::default());
+
+        assert!(poll(pin!(eng.step())).is_pending());
+        assert!(poll(pin!(eng.run())).is_pending());
+        assert_eq!(eng.engine().pc(), 0);
+    }
+
+    #[test]
+    fn flushes_output_at_halt() {
+        let program = Program::compile(",[.,]", &Settings::default()).unwrap();
+        let io = Socket {
+            input: VecDeque::from(*b"hi"),
+            closed: true,
+            ..Socket::default()
+        };
+        let mut eng = AsyncEngine::new(program, Settings::default(), io);
+        assert_eq!(poll(pin!(eng.run())), Poll::Ready(Ok(())));
+        assert_eq!(eng.io().output, b"hi");
+        assert_eq!(eng.io().flushes, 1);
+    }
+
+    #[test]
+    fn yields_every_interval() {
+        let program = Program::compile("+[]", &Settings::default()).unwrap();
+        let mut eng = AsyncEngine::new(program, Settings::default(), Socket::default())
+            .with_yield_interval(NonZeroU64::new(10).unwrap());
+        {
+            let mut run = pin!(eng.run());
+            for _ in 0..3 {
+                assert!(poll(run.as_mut()).is_pending());
+            }
+        }
+        assert!(eng.engine().pc() > 0);
+    }
+
+    #[test]
+    fn multiplexes_programs_on_one_thread() {
+        let program = Program::compile("+[]", &Settings::default()).unwrap();
+        let new = || {
+            AsyncEngine::new(program.clone(), Settings::default(), Socket::default())
+                .with_yield_interval(NonZeroU64::new(100).unwrap())
+        };
+        let (mut first, mut second) = (new(), new());
+        let mut runs = [
+            pin!(first.run_with_fuel(1000)),
+            pin!(second.run_with_fuel(1000)),
+        ];
+
+        let mut order = Vec::new();
+        while order.len() < 22 {
+            for (i, run) in runs.iter_mut().enumerate() {
+                match poll(run.as_mut()) {
+                    Poll::Pending => order.push(i),
+                    Poll::Ready(result) => {
+                        assert_eq!(result, Err(RuntimeError::OutOfFuel));
+                        order.push(i + 2);
+                    },
+                }
+            }
+        }
+        // Both programs made progress in turns, and ran out of fuel in the same round.
+        assert_eq!(order[..4], [0, 1, 0, 1]);
+        assert_eq!(order[order.len() - 2..], [2, 3]);
+    }
+}
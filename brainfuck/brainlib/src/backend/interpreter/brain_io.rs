@@ -0,0 +1,320 @@
+use std::{
+    fmt::{self, Debug},
+    io::{self, stdin, stdout, BufRead, BufWriter, ErrorKind, Read, Stdin, Stdout, Write},
+    sync::mpsc::{Receiver, Sender},
+};
+
+/// The input and output of an [`Engine`](crate::interpreter::Engine).
+///
+/// Errors are returned to the caller inside [`RuntimeError::InputError`](crate::interpreter::RuntimeError::InputError)
+/// and [`RuntimeError::OutputError`](crate::interpreter::RuntimeError::OutputError).
+/// Output may be buffered, since the engine calls [`BrainIo::flush`] once it halts or stops.
+///
+/// Ready-made implementations:
+/// - [`StdIo`] - reads lines from [`Stdin`] and writes to a buffered [`Stdout`]
+/// - [`ByteIo`] - reads from `&[u8]` and writes to `&mut Vec<u8>`
+/// - [`ReadWriteIo`] - reads from any [`Read`] type and writes to any [`Write`] type
+/// - [`ChannelIo`] - receives from and sends to [`mpsc`](std::sync::mpsc) channels
+/// - [`FnIo`] - calls a closure for each read and written byte
+pub trait BrainIo {
+    /// Reads a single byte, returns `None` at the end of input.
+    fn read_byte(&mut self) -> io::Result<Option<u8>>;
+
+    /// Writes a single byte.
+    fn write_byte(&mut self, byte: u8) -> io::Result<()>;
+
+    /// Writes out any buffered output.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A [`BrainIo`] which operates on [`Stdin`] and [`Stdout`].
+///
+/// The input is read a line at a time, so that an interactive user can edit it before it is consumed.
+/// The output is buffered, and flushed before waiting for the next line of input.
+#[derive(Debug)]
+pub struct StdIo {
+    input: Stdin,
+    line: Vec<u8>,
+    consumed: usize,
+    output: BufWriter<Stdout>,
+}
+
+impl StdIo {
+    /// Creates a new [`StdIo`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            input: stdin(),
+            line: Vec::new(),
+            consumed: 0,
+            output: BufWriter::new(stdout()),
+        }
+    }
+}
+
+impl Default for StdIo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BrainIo for StdIo {
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        if self.consumed == self.line.len() {
+            // The output may be a prompt, which has to be seen before the user answers it.
+            // Errors are not lost, since the unwritten output is flushed again later.
+            let _ = self.output.flush();
+            self.line.clear();
+            self.consumed = 0;
+            self.input.lock().read_until(b'\n', &mut self.line)?;
+        }
+        let byte = self.line.get(self.consumed).copied();
+        self.consumed += usize::from(byte.is_some());
+        Ok(byte)
+    }
+
+    fn write_byte(&mut self, byte: u8) -> io::Result<()> {
+        self.output.write_all(&[byte])
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
+
+/// A [`BrainIo`] which reads from `&[u8]` and writes to `&mut Vec<u8>`.
+///
+/// # Examples
+/// ```
+/// # use brainlib::interpreter::{BrainIo, ByteIo};
+/// let mut output = Vec::new();
+/// let mut io = ByteIo::new(b"A", &mut output);
+/// assert_eq!(io.read_byte()?, Some(b'A'));
+/// assert_eq!(io.read_byte()?, None);
+/// io.write_byte(b'B')?;
+/// assert_eq!(output, b"B");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+pub struct ByteIo<'io> {
+    input: &'io [u8],
+    output: &'io mut Vec<u8>,
+}
+
+impl<'io> ByteIo<'io> {
+    /// Creates a new [`ByteIo`], consuming the `input` from the start and appending to the `output`.
+    pub fn new(input: &'io [u8], output: &'io mut Vec<u8>) -> Self {
+        Self { input, output }
+    }
+}
+
+impl BrainIo for ByteIo<'_> {
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        let Some((&byte, rest)) = self.input.split_first() else {
+            return Ok(None);
+        };
+        self.input = rest;
+        Ok(Some(byte))
+    }
+
+    fn write_byte(&mut self, byte: u8) -> io::Result<()> {
+        self.output.push(byte);
+        Ok(())
+    }
+}
+
+/// A [`BrainIo`] which reads from any [`Read`] type and writes to any [`Write`] type, byte by byte.
+///
+/// Wrap them in a [`BufReader`](std::io::BufReader) or a [`BufWriter`] if they are slow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadWriteIo<R: Read, W: Write> {
+    read: R,
+    write: W,
+}
+
+impl<R: Read, W: Write> ReadWriteIo<R, W> {
+    /// Creates a new [`ReadWriteIo`].
+    pub const fn new(read: R, write: W) -> Self {
+        Self { read, write }
+    }
+
+    /// Returns the underlying reader and writer.
+    pub fn into_inner(self) -> (R, W) {
+        (self.read, self.write)
+    }
+}
+
+impl<R: Read, W: Write> BrainIo for ReadWriteIo<R, W> {
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        let mut buffer = [0];
+        match self.read.read_exact(&mut buffer) {
+            Ok(()) => Ok(Some(buffer[0])),
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn write_byte(&mut self, byte: u8) -> io::Result<()> {
+        self.write.write_all(&[byte])
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write.flush()
+    }
+}
+
+/// A [`BrainIo`] which receives the input from a channel and sends the output to another one.
+///
+/// The input ends once all of its senders are dropped.
+/// Writing fails with [`ErrorKind::BrokenPipe`] once the output receiver is dropped.
+///
+/// # Examples
+/// ```
+/// # use std::{sync::mpsc::channel, thread};
+/// # use brainlib::{interpreter::{ChannelIo, Engine}, Program, Settings};
+/// let (input, input_rx) = channel();
+/// let (output_tx, output) = channel();
+///
+/// let program = Program::compile(",[+.,]", &Settings::default())?;
+/// let worker = thread::spawn(move || {
+///     Engine::new(
+///         program,
+///         Settings::default(),
+///         ChannelIo::new(input_rx, output_tx),
+///     )
+///     .run()
+/// });
+///
+/// input.send(b'A')?;
+/// assert_eq!(output.recv()?, b'B');
+/// drop(input);
+/// worker.join().unwrap()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct ChannelIo {
+    input: Receiver<u8>,
+    output: Sender<u8>,
+}
+
+impl ChannelIo {
+    /// Creates a new [`ChannelIo`].
+    #[must_use]
+    pub const fn new(input: Receiver<u8>, output: Sender<u8>) -> Self {
+        Self { input, output }
+    }
+}
+
+impl BrainIo for ChannelIo {
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        Ok(self.input.recv().ok())
+    }
+
+    fn write_byte(&mut self, byte: u8) -> io::Result<()> {
+        (self.output.send(byte)).map_err(|err| io::Error::new(ErrorKind::BrokenPipe, err))
+    }
+}
+
+/// A [`BrainIo`] which calls the `read` closure for each input byte and the `write` closure for each output byte.
+///
+/// # Examples
+/// ```
+/// # use brainlib::{interpreter::{Engine, FnIo, RuntimeError}, Program, Settings};
+/// # use std::io;
+/// let program = Program::compile(",.", &Settings::default())?;
+/// let io = FnIo::new(|| Ok(Some(b'A')), |_| Err(io::Error::other("closed")));
+/// let mut engine = Engine::new(program, Settings::default(), io);
+///
+/// let Err(RuntimeError::OutputError(err)) = engine.run() else {
+///     panic!("the output should fail");
+/// };
+/// assert_eq!(err.to_string(), "closed");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct FnIo<R, W>
+where
+    R: FnMut() -> io::Result<Option<u8>>,
+    W: FnMut(u8) -> io::Result<()>,
+{
+    read: R,
+    write: W,
+}
+
+impl<R, W> FnIo<R, W>
+where
+    R: FnMut() -> io::Result<Option<u8>>,
+    W: FnMut(u8) -> io::Result<()>,
+{
+    /// Creates a new [`FnIo`].
+    pub const fn new(read: R, write: W) -> Self {
+        Self { read, write }
+    }
+}
+
+impl<R, W> Debug for FnIo<R, W>
+where
+    R: FnMut() -> io::Result<Option<u8>>,
+    W: FnMut(u8) -> io::Result<()>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FnIo").finish_non_exhaustive()
+    }
+}
+
+impl<R, W> BrainIo for FnIo<R, W>
+where
+    R: FnMut() -> io::Result<Option<u8>>,
+    W: FnMut(u8) -> io::Result<()>,
+{
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        (self.read)()
+    }
+
+    fn write_byte(&mut self, byte: u8) -> io::Result<()> {
+        (self.write)(byte)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, ErrorKind};
+
+    use super::{BrainIo, ReadWriteIo};
+
+    struct Failing;
+
+    impl io::Read for Failing {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::new(ErrorKind::PermissionDenied, "denied"))
+        }
+    }
+
+    #[test]
+    fn reads_bytes_until_eof() {
+        let mut io = ReadWriteIo::new("AB".as_bytes(), Vec::new());
+        assert_eq!(io.read_byte().unwrap(), Some(b'A'));
+        assert_eq!(io.read_byte().unwrap(), Some(b'B'));
+        assert_eq!(io.read_byte().unwrap(), None);
+    }
+
+    #[test]
+    fn preserves_read_errors() {
+        let mut io = ReadWriteIo::new(Failing, Vec::new());
+        let err = io.read_byte().unwrap_err();
+        assert_eq!(
+            (err.kind(), err.to_string()),
+            (ErrorKind::PermissionDenied, "denied".into())
+        );
+    }
+
+    #[test]
+    fn writes_bytes_without_flushing_each() {
+        let mut io = ReadWriteIo::new(io::empty(), io::BufWriter::new(Vec::new()));
+        io.write_byte(b'A').unwrap();
+        assert!(io.write.get_ref().is_empty());
+        io.flush().unwrap();
+        assert_eq!(io.into_inner().1.into_inner().unwrap(), b"A");
+    }
+}
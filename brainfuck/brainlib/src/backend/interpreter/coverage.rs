@@ -0,0 +1,153 @@
+use crate::{
+    interpreter::{EngineObserver, EngineView, RuntimeError},
+    Instruction, Program,
+};
+
+/// An [`EngineObserver`] which records the executed instructions, and which loops were entered or skipped.
+///
+/// It is enabled with [`Engine::with_coverage`](crate::interpreter::Engine::with_coverage)
+/// and read with [`Engine::observer`](crate::interpreter::Engine::observer).
+/// Use [`Program::span`] to map the instructions back to the source code.
+///
+/// # Examples
+/// ```
+/// # use brainlib::{interpreter::Engine, Program, Settings};
+/// let program = Program::compile("+[-][+]", &Settings::new().with_debug())?;
+/// let mut engine = Engine::new_std_default(program).with_coverage();
+/// engine.run()?;
+///
+/// let coverage = engine.observer();
+/// assert!(coverage.is_covered(2));
+/// assert!(!coverage.is_covered(5));
+///
+/// let loops = coverage.loops();
+/// assert_eq!((loops[0].entered(), loops[0].skipped()), (1, 0));
+/// assert_eq!((loops[1].entered(), loops[1].skipped()), (0, 1));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    counts: Vec<u64>,
+    /// How many times each instruction jumped, instead of moving to the next instruction.
+    jumps: Vec<u64>,
+    /// Indices of matching [`JumpRightZ`](crate::Instruction::JumpRightZ) and
+    /// [`JumpLeftNz`](crate::Instruction::JumpLeftNz) pairs, in program order.
+    loops: Vec<(usize, usize)>,
+}
+
+/// Coverage of a single loop, see [`Coverage::loops`].
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LoopCoverage {
+    start: usize,
+    end: usize,
+    entered: u64,
+    skipped: u64,
+}
+
+impl Coverage {
+    /// Creates an empty coverage of the [`Program`].
+    pub fn new(program: &Program) -> Self {
+        Self {
+            counts: vec![0; program.len()],
+            jumps: vec![0; program.len()],
+            loops: program.loops().collect(),
+        }
+    }
+
+    fn record(&mut self, pc: usize, jumped: bool) {
+        self.counts[pc] += 1;
+        if jumped {
+            self.jumps[pc] += 1;
+        }
+    }
+
+    /// Returns how many times each instruction was executed, indexed by the program counter.
+    #[must_use]
+    pub fn counts(&self) -> &[u64] {
+        &self.counts
+    }
+
+    /// Returns `true` if the instruction at `pc` was executed at least once.
+    #[must_use]
+    pub fn is_covered(&self, pc: usize) -> bool {
+        self.counts.get(pc).is_some_and(|&count| count > 0)
+    }
+
+    /// Returns the coverage of every loop, in program order.
+    #[must_use]
+    pub fn loops(&self) -> Vec<LoopCoverage> {
+        (self.loops.iter())
+            .map(|&(start, end)| LoopCoverage {
+                start,
+                end,
+                entered: self.counts[start] - self.jumps[start],
+                skipped: self.jumps[start],
+            })
+            .collect()
+    }
+}
+
+impl EngineObserver for Coverage {
+    fn after_instruction(&mut self, pc: usize, _: Instruction, view: EngineView<'_>) {
+        self.record(pc, view.pc() != pc + 1);
+    }
+
+    fn on_error(&mut self, pc: usize, _: &RuntimeError) {
+        self.record(pc, false);
+    }
+}
+
+impl LoopCoverage {
+    /// Returns the index of the loop's [`JumpRightZ`](crate::Instruction::JumpRightZ).
+    #[must_use]
+    pub const fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the index of the loop's [`JumpLeftNz`](crate::Instruction::JumpLeftNz).
+    #[must_use]
+    pub const fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Returns how many times the loop was reached with a non-zero cell, executing its body.
+    #[must_use]
+    pub const fn entered(&self) -> u64 {
+        self.entered
+    }
+
+    /// Returns how many times the loop was reached with a zero cell, skipping its body.
+    #[must_use]
+    pub const fn skipped(&self) -> u64 {
+        self.skipped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Coverage;
+    use crate::{interpreter::Engine, Program, Settings};
+
+    fn coverage(source: &str) -> Coverage {
+        let program = Program::compile(source, &Settings::new().with_debug()).unwrap();
+        let mut eng = Engine::new_std_default(program).with_coverage();
+        eng.run().unwrap();
+        eng.observer().clone()
+    }
+
+    #[test]
+    fn counts_entered_and_skipped_loops() {
+        let loops = coverage("++[>[-]+<-]").loops();
+        assert_eq!((loops[0].entered(), loops[0].skipped()), (1, 0));
+        assert_eq!((loops[1].entered(), loops[1].skipped()), (1, 1));
+    }
+
+    #[test]
+    fn does_not_cover_skipped_loop_bodies() {
+        let coverage = coverage("[>+<]+");
+        assert_eq!(coverage.counts(), [1, 0, 0, 0, 0, 1]);
+        assert!(!coverage.is_covered(2));
+    }
+}
@@ -1,9 +1,13 @@
-use std::io::{stdin, stdout, Read, Stdin, Stdout, Write};
+use std::{io::Write, time::Instant};
 
+#[cfg(feature = "jit")]
+use crate::jit::NativeParts;
 use crate::{
-    backend::common::{Instruction, Program, Settings},
-    interpreter::RuntimeError,
-    util::{read_byte, write_byte},
+    backend::common::{Instruction, Program, Settings, TapeMode},
+    interpreter::{
+        history::History, tape::Tape, BrainIo, ByteIo, Coverage, EngineObserver, EngineState,
+        EngineView, Profile, RuntimeError, StateError, StdIo, TraceFormat, Tracer,
+    },
 };
 
 /// A generic representation of the interpreter's engine state.
@@ -19,23 +23,26 @@ use crate::{
 /// ```
 #[must_use]
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Engine<In: Read, Out: Write> {
+pub struct Engine<Io: BrainIo, Obs: EngineObserver = ()> {
     program: Program,
     pc: usize,
     pointer: u32,
-    memory: Box<[u8]>,
+    origin: u32,
+    memory: Vec<u32>,
+    input_position: u64,
+    history: Option<History>,
+    observer: Obs,
     settings: Settings,
-    read: In,
-    write: Out,
+    io: Io,
 }
 
-/// An engine which operates on [`Stdin`] and [`Stdout`].
-pub type StdEngine = Engine<Stdin, Stdout>;
+/// An engine which operates on [`Stdin`](std::io::Stdin) and [`Stdout`](std::io::Stdout), see [`StdIo`].
+pub type StdEngine = Engine<StdIo>;
 
 impl StdEngine {
     /// Creates a new [`StdEngine`] with the provided [`Program`] and [`Settings`].
     pub fn new_std(program: Program, settings: Settings) -> Self {
-        Self::new(program, settings, stdin(), stdout())
+        Self::new(program, settings, StdIo::new())
     }
 
     /// Same as [`StdEngine::new_std`], but uses [`Settings::default`].
@@ -45,7 +52,7 @@ impl StdEngine {
 }
 
 /// An engine which reads from `&[u8]` and writes to `&mut Vec<u8>`.
-pub type ByteEngine<'io> = Engine<&'io [u8], &'io mut Vec<u8>>;
+pub type ByteEngine<'io> = Engine<ByteIo<'io>>;
 
 impl<'io> ByteEngine<'io> {
     /// Creates a new [`ByteEngine`] with the provided [`Program`] and [`Settings`].
@@ -55,7 +62,7 @@ impl<'io> ByteEngine<'io> {
         input: &'io [u8],
         output: &'io mut Vec<u8>,
     ) -> Self {
-        Self::new(program, settings, input, output)
+        Self::new(program, settings, ByteIo::new(input, output))
     }
 
     /// Same as [`ByteEngine::new_byte`], but uses [`Settings::default`].
@@ -64,20 +71,166 @@ impl<'io> ByteEngine<'io> {
     }
 }
 
-impl<In: Read, Out: Write> Engine<In, Out> {
-    /// Creates a new [`Engine`] with the provided [`Program`], [`Settings`], input and output.
-    pub fn new(program: Program, settings: Settings, read: In, write: Out) -> Self {
+impl<Io: BrainIo> Engine<Io> {
+    /// Creates a new [`Engine`] with the provided [`Program`], [`Settings`] and [`BrainIo`].
+    pub fn new(program: Program, settings: Settings, io: Io) -> Self {
         Self {
             program,
             pc: 0,
             pointer: 0,
-            memory: vec![0; settings.tape_length() as usize].into_boxed_slice(),
+            origin: 0,
+            memory: vec![0; settings.tape_length() as usize],
+            input_position: 0,
+            history: None,
+            observer: (),
             settings,
-            read,
-            write,
+            io,
         }
     }
 
+    /// Splits the [`Engine`] into its parts, dropping the undo log, see [`Engine::from_parts`].
+    pub(crate) fn into_parts(self) -> (Program, Settings, Io, EngineState) {
+        let state = EngineState {
+            pc: self.pc,
+            pointer: self.pointer,
+            origin: self.origin,
+            memory: self.memory,
+            input_position: self.input_position,
+        };
+        (self.program, self.settings, self.io, state)
+    }
+
+    /// Reassembles an [`Engine`] split with [`Engine::into_parts`].
+    pub(crate) fn from_parts(
+        program: Program,
+        settings: Settings,
+        io: Io,
+        state: EngineState,
+    ) -> Self {
+        Self {
+            program,
+            pc: state.pc,
+            pointer: state.pointer,
+            origin: state.origin,
+            memory: state.memory,
+            input_position: state.input_position,
+            history: None,
+            observer: (),
+            settings,
+            io,
+        }
+    }
+}
+
+#[cfg(feature = "jit")]
+impl<Io: BrainIo> Engine<Io> {
+    /// Borrows the fields which are read and written by the native code of a [`JitEngine`](crate::jit::JitEngine).
+    pub(crate) fn native_parts(&mut self) -> NativeParts<'_, Io> {
+        NativeParts {
+            pc: &mut self.pc,
+            pointer: &mut self.pointer,
+            memory: &mut self.memory,
+            input_position: &mut self.input_position,
+            settings: &self.settings,
+            io: &mut self.io,
+        }
+    }
+}
+
+/// Builds an [`EngineView`] from the fields, so that the observer can be borrowed mutably at the same time.
+macro_rules! view {
+    ($self:ident) => {
+        EngineView {
+            pc: $self.pc,
+            pointer: $self.pointer,
+            origin: $self.origin,
+            memory: &$self.memory,
+            settings: &$self.settings,
+        }
+    };
+}
+
+impl<Io: BrainIo, Obs: EngineObserver> Engine<Io, Obs> {
+    /// How many instructions [`Engine::run_until`] executes between checks of the clock.
+    const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
+    /// Returns the [`Engine`] with an undo log enabled, which allows stepping backwards with [`Engine::step_back`].
+    ///
+    /// The log uses at most `memory_cap` bytes, after which the oldest entries are dropped.
+    ///
+    /// # Examples
+    /// ```
+    /// # use brainlib::{interpreter::Engine, Program};
+    /// let program = Program::compile("+>+", &Default::default())?;
+    /// let mut engine = Engine::new_std_default(program).with_history(1024 * 1024);
+    /// engine.run()?;
+    ///
+    /// assert_eq!(engine.memory()[..2], [1, 1]);
+    ///
+    /// while engine.step_back().is_some() {}
+    /// assert_eq!(engine.memory()[..2], [0, 0]);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn with_history(self, memory_cap: usize) -> Self {
+        Self {
+            history: Some(History::new(memory_cap)),
+            ..self
+        }
+    }
+
+    /// Returns the [`Engine`] with the given [`EngineObserver`], replacing the current one.
+    pub fn with_observer<O: EngineObserver>(self, observer: O) -> Engine<Io, O> {
+        Engine {
+            program: self.program,
+            pc: self.pc,
+            pointer: self.pointer,
+            origin: self.origin,
+            memory: self.memory,
+            input_position: self.input_position,
+            history: self.history,
+            observer,
+            settings: self.settings,
+            io: self.io,
+        }
+    }
+
+    /// Returns the [`Engine`] with a [`Profile`] observer, which counts the executions of each instruction.
+    pub fn with_profiling(self) -> Engine<Io, Profile> {
+        let profile = Profile::new(&self.program);
+        self.with_observer(profile)
+    }
+
+    /// Returns the [`Engine`] with a [`Coverage`] observer, which records the executed instructions.
+    pub fn with_coverage(self) -> Engine<Io, Coverage> {
+        let coverage = Coverage::new(&self.program);
+        self.with_observer(coverage)
+    }
+
+    /// Returns the [`Engine`] with a [`Tracer`] observer, which writes a record of every executed instruction.
+    pub fn with_tracing<W: Write>(self, write: W, format: TraceFormat) -> Engine<Io, Tracer<W>> {
+        self.with_observer(Tracer::new(write, format))
+    }
+
+    /// Returns the used [`EngineObserver`].
+    pub const fn observer(&self) -> &Obs {
+        &self.observer
+    }
+
+    /// Returns the used [`EngineObserver`] mutably.
+    pub fn observer_mut(&mut self) -> &mut Obs {
+        &mut self.observer
+    }
+
+    /// Returns the used [`BrainIo`].
+    pub const fn io(&self) -> &Io {
+        &self.io
+    }
+
+    /// Returns the used [`BrainIo`] mutably.
+    pub fn io_mut(&mut self) -> &mut Io {
+        &mut self.io
+    }
+
     /// Returns the [`Program`] which the [`Engine`] is executing.
     pub const fn program(&self) -> &Program {
         &self.program
@@ -89,51 +242,148 @@ impl<In: Read, Out: Write> Engine<In, Out> {
         self.pc
     }
 
-    /// Returns the current cell pointer.
+    /// Returns the current cell pointer, as an index into [`Engine::memory`].
     #[must_use]
     pub const fn pointer(&self) -> u32 {
         self.pointer
     }
 
+    /// Returns the index into [`Engine::memory`] of the initial first cell.
+    ///
+    /// This is always zero, unless a [`TapeMode::GrowBoth`] tape grew to the left.
+    #[must_use]
+    pub const fn origin(&self) -> u32 {
+        self.origin
+    }
+
+    /// Returns the current cell pointer, relative to the initial first cell.
+    #[must_use]
+    pub const fn position(&self) -> i32 {
+        self.pointer as i32 - self.origin as i32
+    }
+
     /// Returns a view into the memory (cell vector).
+    ///
+    /// Cells are always stored as `u32`, but their values never exceed the configured [`CellWidth`](crate::CellWidth).
+    /// A growable tape only contains the cells allocated so far, all other cells are zero.
     #[must_use]
-    pub const fn memory(&self) -> &[u8] {
+    pub fn memory(&self) -> &[u32] {
         &self.memory
     }
 
+    /// Returns the value of the cell at the given position, relative to the initial first cell.
+    ///
+    /// Returns [`None`] if the tape can never contain such a cell.
+    /// Cells of a growable tape which were not allocated yet are zero.
+    ///
+    /// # Examples
+    /// ```
+    /// # use brainlib::{interpreter::Engine, Program, Settings, TapeMode};
+    /// let settings = Settings::new().with_tape_mode(TapeMode::GrowBoth);
+    /// let program = Program::compile("<++", &settings)?;
+    /// let mut engine = Engine::new_std(program, settings);
+    /// engine.run()?;
+    ///
+    /// assert_eq!(engine.cell(-1), Some(2));
+    /// assert_eq!(engine.cell(-100), Some(0));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[must_use]
+    pub fn cell(&self, position: i32) -> Option<u32> {
+        view!(self).cell(position)
+    }
+
+    /// Returns the number of bytes consumed from the input.
+    #[must_use]
+    pub const fn input_position(&self) -> u64 {
+        self.input_position
+    }
+
     /// Returns the used [`Settings`].
     pub const fn settings(&self) -> &Settings {
         &self.settings
     }
 
     #[must_use]
-    fn c(&mut self) -> &mut u8 {
+    fn c(&mut self) -> &mut u32 {
         &mut self.memory[self.pointer as usize]
     }
 
+    /// Stores the value in the cell at the given index, recording the previous value in the undo log.
+    fn write_cell(&mut self, at: u32, value: u32) {
+        let cell = &mut self.memory[at as usize];
+        if let Some(history) = &mut self.history {
+            history.record_write(at, *cell);
+        }
+        self.observer
+            .on_write(at as i32 - self.origin as i32, *cell, value);
+        *cell = value;
+    }
+
+    /// Borrows the memory together with the pointer, see [`Tape`].
+    fn tape(&mut self) -> Tape<'_> {
+        Tape {
+            memory: &mut self.memory,
+            pointer: &mut self.pointer,
+            origin: &mut self.origin,
+            settings: &self.settings,
+        }
+    }
+
+    /// Returns the index of the cell `offset` cells away from the pointer, growing the tape if needed.
+    ///
+    /// Cells inside of the tape are found without going through the [`Tape`].
+    #[inline(always)]
+    fn cell_at(&mut self, offset: i32) -> Result<u32, RuntimeError> {
+        match u32::try_from(i64::from(self.pointer) + i64::from(offset)) {
+            Ok(index) if (index as usize) < self.memory.len() => Ok(index),
+            _ => self.tape().cell_at(offset),
+        }
+    }
+
+    fn scan(&mut self, by: i32) -> Result<(), RuntimeError> {
+        if !self.tape().scan(by)? {
+            // No zero cell can be reached, so the loop never ends, run it again on the next step.
+            self.pc -= 1;
+        }
+        Ok(())
+    }
+
     fn exec(&mut self, instruction: Instruction) -> Result<(), RuntimeError> {
         use Instruction as I;
         match instruction {
             I::MutPointer(change) => {
-                self.pointer = self.settings.mut_pointer(self.pointer, change).ok_or(
-                    RuntimeError::TapeOverflow {
-                        from: self.pointer,
-                        by: change,
-                    },
-                )?;
+                self.pointer = self.cell_at(change)?;
             },
-            I::MutCell(change) => {
-                let previous = *self.c();
-                *self.c() =
+            I::MutCell { offset, delta } => {
+                let at = self.cell_at(offset)?;
+                let previous = self.memory[at as usize];
+                let value =
                     self.settings
-                        .mut_cell(previous, change)
+                        .mut_cell(previous, delta)
                         .ok_or(RuntimeError::CellOverflow {
-                            at: self.pointer,
-                            from: *self.c(),
-                            by: change,
+                            at: at as i32 - self.origin as i32,
+                            from: previous,
+                            by: delta,
                         })?;
+                self.write_cell(at, value);
             },
-            I::SetCell(value) => *self.c() = value,
+            I::SetCell { offset, value } => {
+                let at = self.cell_at(offset)?;
+                self.write_cell(at, value);
+            },
+            I::MulAdd { offset, factor } => {
+                let value = *self.c();
+                let target = self.cell_at(offset)?;
+                let previous = self.memory[target as usize];
+                self.write_cell(
+                    target,
+                    previous.wrapping_add(value.wrapping_mul(factor as u32))
+                        & self.settings.cell_width().max(),
+                );
+            },
+            I::ScanRight(stride) => self.scan(stride as i32)?,
+            I::ScanLeft(stride) => self.scan(-(stride as i32))?,
             I::JumpRightZ(offset) =>
                 if *self.c() == 0 {
                     self.pc += offset as usize;
@@ -142,36 +392,240 @@ impl<In: Read, Out: Write> Engine<In, Out> {
                 if *self.c() != 0 {
                     self.pc -= offset as usize;
                 },
-            I::Input => *self.c() = read_byte(&mut self.read).ok_or(RuntimeError::InputError)?,
+            I::Input => {
+                let byte = match self.history.as_mut().and_then(History::replay) {
+                    Some(byte) => byte,
+                    None => self.io.read_byte().map_err(RuntimeError::input)?,
+                };
+                if let Some(history) = &mut self.history {
+                    history.record_read(byte);
+                }
+                self.observer.on_input(byte);
+                let previous = *self.c();
+                let value = match byte {
+                    Some(byte) => {
+                        self.input_position += 1;
+                        byte.into()
+                    },
+                    None => self.settings.eof_value(previous),
+                };
+                self.write_cell(self.pointer, value);
+            },
             I::Output => {
-                let value = *self.c();
-                write_byte(&mut self.write, value).ok_or(RuntimeError::OutputError)?;
+                // Only the least significant byte of wider cells is written.
+                let value = *self.c() as u8;
+                self.io.write_byte(value).map_err(RuntimeError::output)?;
+                self.observer.on_output(value);
             },
             I::Breakpoint(_) => (), // NOOP
         }
         Ok(())
     }
 
+    /// Captures the current execution state, which can be later passed to [`Engine::restore`].
+    pub fn snapshot(&self) -> EngineState {
+        EngineState {
+            pc: self.pc,
+            pointer: self.pointer,
+            origin: self.origin,
+            memory: self.memory.clone(),
+            input_position: self.input_position,
+        }
+    }
+
+    /// Restores the execution state captured by [`Engine::snapshot`].
+    ///
+    /// The state must come from an engine with the same [`Program`] and [`Settings`],
+    /// obviously mismatched states are rejected with a [`StateError`].
+    ///
+    /// The input is neither rewound nor skipped, it should continue after
+    /// the first [`EngineState::input_position`] bytes of the original input.
+    pub fn restore(&mut self, state: EngineState) -> Result<(), StateError> {
+        if state.pc > self.program.len() {
+            return Err(StateError::ProgramMismatch);
+        }
+
+        let length = state.memory.len();
+        let fits_tape = match self.settings.tape_mode() {
+            TapeMode::Fixed => length == self.settings.tape_length() as usize,
+            TapeMode::GrowRight => length >= self.settings.tape_length() as usize,
+            TapeMode::GrowBoth =>
+                length >= self.settings.tape_length() as usize + state.origin as usize,
+        };
+        if !fits_tape
+            || length > Settings::MAX_LENGTH as usize
+            || (self.settings.tape_mode() != TapeMode::GrowBoth && state.origin != 0)
+            || state.pointer as usize >= length
+            || (state.memory.iter()).any(|&cell| cell > self.settings.cell_width().max())
+        {
+            return Err(StateError::SettingsMismatch);
+        }
+
+        self.pc = state.pc;
+        self.pointer = state.pointer;
+        self.origin = state.origin;
+        self.memory = state.memory;
+        self.input_position = state.input_position;
+        if let Some(history) = &mut self.history {
+            history.clear();
+        }
+        Ok(())
+    }
+
     /// Executes a single [`Instruction`] and returns it or a [`RuntimeError`].
     #[must_use]
     pub fn step(&mut self) -> Option<Result<Instruction, RuntimeError>> {
-        let instruction = *self.program.0.get(self.pc)?;
+        let instruction = *self.program.code.get(self.pc)?;
+        if let Some(history) = &mut self.history {
+            history.push(self.pc, self.pointer, self.origin, self.memory.len() as u32);
+        }
+        let pc = self.pc;
+        self.observer
+            .before_instruction(pc, instruction, view!(self));
         self.pc += 1;
-        Some(self.exec(instruction).map(|_| instruction))
+        let result = self.exec(instruction);
+        match &result {
+            Ok(()) => {
+                self.observer
+                    .after_instruction(pc, instruction, view!(self));
+                if let Instruction::Breakpoint(position) = instruction {
+                    self.observer.on_breakpoint(pc, position);
+                }
+            },
+            Err(err) => self.observer.on_error(pc, err),
+        }
+        Some(result.map(|_| instruction))
+    }
+
+    /// Writes out the output buffered by the [`BrainIo`].
+    ///
+    /// This happens automatically once a run method stops, but not between steps.
+    pub fn flush(&mut self) -> Result<(), RuntimeError> {
+        self.io.flush().map_err(RuntimeError::output)
+    }
+
+    /// Flushes the output once a run method stops with the given result.
+    ///
+    /// An output error takes precedence over the resumable errors, but not over the errors of the program.
+    pub(crate) fn stop(&mut self, result: Result<(), RuntimeError>) -> Result<(), RuntimeError> {
+        let flushed = self.flush();
+        match result {
+            Err(
+                RuntimeError::OutOfFuel | RuntimeError::DeadlineExceeded | RuntimeError::Paused,
+            ) => flushed.and(result),
+            _ => result.and(flushed),
+        }
+    }
+
+    /// Undoes the last executed [`Instruction`] and returns it.
+    ///
+    /// Returns `None` if the undo log is disabled (see [`Engine::with_history`]) or empty.
+    /// Undone input is read again when executing forwards, but output can't be taken back.
+    #[must_use]
+    pub fn step_back(&mut self) -> Option<Instruction> {
+        let entry = self.history.as_mut()?.pop()?;
+        if let Some((at, previous)) = entry.write {
+            self.memory[at as usize] = previous;
+        }
+        if let Some(Some(_)) = entry.read {
+            self.input_position -= 1;
+        }
+        // Cells added by growing the tape are zero again, so they can be dropped.
+        self.memory.drain(..(self.origin - entry.origin) as usize);
+        self.memory.truncate(entry.length as usize);
+        self.pc = entry.pc;
+        self.pointer = entry.pointer;
+        self.origin = entry.origin;
+        Some(self.program.code[entry.pc])
+    }
+
+    /// Returns the number of instructions which can be undone with [`Engine::step_back`].
+    #[must_use]
+    pub fn history_len(&self) -> usize {
+        self.history.as_ref().map_or(0, History::len)
+    }
+
+    /// Returns [`RuntimeError::Paused`] if the observer requests a pause.
+    pub(super) fn check_pause(&mut self) -> Result<(), RuntimeError> {
+        match self.observer.should_pause(view!(self)) {
+            true => Err(RuntimeError::Paused),
+            false => Ok(()),
+        }
     }
 
     /// Runs the [`Engine`] until it halts or a [`RuntimeError`] occurs.
     pub fn run(&mut self) -> Result<(), RuntimeError> {
-        while let Some(result) = self.step() {
-            let _: Instruction = result?;
+        // Even at a billion instructions per second, this much fuel lasts for centuries.
+        self.run_with_fuel(u64::MAX)
+    }
+
+    /// Same as [`Engine::run`], but executes at most `fuel` instructions.
+    ///
+    /// If the program doesn't halt in time, [`RuntimeError::OutOfFuel`] is returned.
+    /// The execution can then be resumed by calling any of the run methods again.
+    ///
+    /// Note that the instructions are counted after optimization, see [`Program`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use brainlib::{interpreter::{Engine, RuntimeError}, Program};
+    /// let program = Program::compile("+[]", &Default::default())?;
+    /// let mut engine = Engine::new_std_default(program);
+    /// assert_eq!(engine.run_with_fuel(1000), Err(RuntimeError::OutOfFuel));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn run_with_fuel(&mut self, fuel: u64) -> Result<(), RuntimeError> {
+        let result = self.run_steps(fuel);
+        self.stop(result)
+    }
+
+    /// Same as [`Engine::run_with_fuel`], but leaves the output buffered.
+    fn run_steps(&mut self, fuel: u64) -> Result<(), RuntimeError> {
+        for _ in 0..fuel {
+            self.check_pause()?;
+            match self.step() {
+                Some(result) => {
+                    let _: Instruction = result?;
+                },
+                None => return Ok(()),
+            }
+        }
+        match self.pc < self.program.len() {
+            true => Err(RuntimeError::OutOfFuel),
+            false => Ok(()),
+        }
+    }
+
+    /// Same as [`Engine::run`], but stops once the `deadline` passes.
+    ///
+    /// If the program doesn't halt in time, [`RuntimeError::DeadlineExceeded`] is returned.
+    /// The execution can then be resumed by calling any of the run methods again.
+    ///
+    /// The clock is only checked every few instructions, so the deadline may be slightly overrun.
+    pub fn run_until(&mut self, deadline: Instant) -> Result<(), RuntimeError> {
+        loop {
+            match self.run_steps(Self::DEADLINE_CHECK_INTERVAL) {
+                Err(RuntimeError::OutOfFuel) if Instant::now() >= deadline =>
+                    return self.stop(Err(RuntimeError::DeadlineExceeded)),
+                Err(RuntimeError::OutOfFuel) => (),
+                result => return self.stop(result),
+            }
         }
-        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Engine, Instruction as I, Program, RuntimeError, Settings};
+    use std::{
+        io::{self, ErrorKind},
+        time::{Duration, Instant},
+    };
+
+    use super::{Engine, Instruction as I, Program, RuntimeError, Settings, StateError};
+    use crate::{
+        interpreter::{BrainIo, FnIo},
+        CellWidth, EofBehavior, TapeMode,
+    };
 
     fn assert_interpret(program: Program, input: &str, output: &str) {
         let mut buffer = Vec::new();
@@ -183,13 +637,13 @@ mod tests {
 
     #[test]
     fn starts_with_zero_at_cell_zero() {
-        assert_interpret(Program(vec![I::Output]), "", "\0")
+        assert_interpret(Program::from_code(vec![I::Output]), "", "\0")
     }
 
     #[test]
     fn copies_input_with_cat() {
         assert_interpret(
-            Program(vec![
+            Program::from_code(vec![
                 I::Input,
                 I::JumpRightZ(3),
                 I::Output,
@@ -201,15 +655,52 @@ mod tests {
         )
     }
 
+    #[test]
+    fn reads_eof_according_to_settings() {
+        for (eof_behavior, value) in [
+            (EofBehavior::Zero, 0),
+            (EofBehavior::Unchanged, 7),
+            (EofBehavior::Max, 255),
+        ] {
+            let mut output = Vec::new();
+            let mut eng = Engine::new_byte(
+                Program::from_code(vec![
+                    I::SetCell {
+                        offset: 0,
+                        value: 7,
+                    },
+                    I::Input,
+                ]),
+                Settings::new().with_eof_behavior(eof_behavior),
+                &[],
+                &mut output,
+            );
+            eng.run().unwrap();
+            assert_eq!(*eng.c(), value);
+        }
+    }
+
     #[test]
     fn reverses_increment_with_decrement() {
         assert_interpret(
-            Program(vec![
+            Program::from_code(vec![
                 I::Input,
-                I::MutCell(1),
-                I::MutCell(-1),
-                I::MutCell(-1),
-                I::MutCell(1),
+                I::MutCell {
+                    offset: 0,
+                    delta: 1,
+                },
+                I::MutCell {
+                    offset: 0,
+                    delta: -1,
+                },
+                I::MutCell {
+                    offset: 0,
+                    delta: -1,
+                },
+                I::MutCell {
+                    offset: 0,
+                    delta: 1,
+                },
                 I::Output,
             ]),
             "x",
@@ -220,7 +711,7 @@ mod tests {
     #[test]
     fn reverses_right_with_left() {
         assert_interpret(
-            Program(vec![
+            Program::from_code(vec![
                 I::Input,
                 I::MutPointer(1),
                 I::MutPointer(-1),
@@ -236,10 +727,13 @@ mod tests {
     #[test]
     fn zeroes_cell_with_loop() {
         assert_interpret(
-            Program(vec![
+            Program::from_code(vec![
                 I::Input,
                 I::JumpRightZ(2),
-                I::MutCell(-1),
+                I::MutCell {
+                    offset: 0,
+                    delta: -1,
+                },
                 I::JumpLeftNz(2),
                 I::Output,
             ]),
@@ -250,28 +744,103 @@ mod tests {
 
     #[test]
     fn wraps_around_mut_pointer_without_strict() {
-        let mut eng = Engine::new_std_default(Program(vec![I::MutPointer(-1)]));
+        let mut eng = Engine::new_std_default(Program::from_code(vec![I::MutPointer(-1)]));
         eng.run().unwrap();
         assert_eq!(eng.pointer, Settings::DEFAULT_LENGTH - 1);
     }
 
     #[test]
     fn reaches_all_values_with_mut_cell() {
-        let mut eng = Engine::new_std_default(Program(vec![
-            I::MutCell(127),
-            I::SetCell(0),
-            I::MutCell(-128),
+        let mut eng = Engine::new_std_default(Program::from_code(vec![
+            I::MutCell {
+                offset: 0,
+                delta: 127,
+            },
+            I::SetCell {
+                offset: 0,
+                value: 0,
+            },
+            I::MutCell {
+                offset: 0,
+                delta: -128,
+            },
         ]));
-        assert_eq!(eng.step(), Some(Ok(I::MutCell(127))));
+        assert_eq!(
+            eng.step(),
+            Some(Ok(I::MutCell {
+                offset: 0,
+                delta: 127
+            }))
+        );
         assert_eq!(*eng.c(), 127);
-        assert_eq!(eng.step(), Some(Ok(I::SetCell(0))));
-        assert_eq!(eng.step(), Some(Ok(I::MutCell(-128))));
+        assert_eq!(
+            eng.step(),
+            Some(Ok(I::SetCell {
+                offset: 0,
+                value: 0
+            }))
+        );
+        assert_eq!(
+            eng.step(),
+            Some(Ok(I::MutCell {
+                offset: 0,
+                delta: -128
+            }))
+        );
         assert_eq!(*eng.c(), 128);
     }
 
+    #[test]
+    fn wraps_around_wider_cells_without_strict() {
+        for (cell_width, max) in [(CellWidth::U16, 65_535), (CellWidth::U32, u32::MAX)] {
+            let mut eng = Engine::new_std(
+                Program::from_code(vec![I::MutCell {
+                    offset: 0,
+                    delta: -1,
+                }]),
+                Settings::new().with_cell_width(cell_width),
+            );
+            eng.run().unwrap();
+            assert_eq!(*eng.c(), max);
+        }
+    }
+
+    #[test]
+    fn returns_error_on_wider_cell_overflow_with_strict() {
+        let mut eng = Engine::new_std(
+            Program::from_code(vec![
+                I::SetCell {
+                    offset: 0,
+                    value: 65_535,
+                },
+                I::MutCell {
+                    offset: 0,
+                    delta: 1,
+                },
+            ]),
+            Settings::new()
+                .with_strict()
+                .with_cell_width(CellWidth::U16),
+        );
+        assert_eq!(
+            eng.run(),
+            Err(RuntimeError::CellOverflow {
+                at: 0,
+                from: 65_535,
+                by: 1
+            })
+        );
+    }
+
     #[test]
     fn returns_error_on_cell_overflow_with_strict() {
-        let mut eng = Engine::new_std(Program(vec![I::MutCell(-1)]), Settings::new().with_strict());
+        let mut eng = Engine::new_std(
+            Program::from_code(vec![I::MutCell {
+                offset: 0,
+                delta: -1,
+            }]),
+            Settings::new().with_strict(),
+        );
         assert_eq!(
             eng.run(),
             Err(RuntimeError::CellOverflow {
@@ -285,7 +854,7 @@ mod tests {
     #[test]
     fn returns_error_on_pointer_overflow_with_strict() {
         let mut eng = Engine::new_std(
-            Program(vec![I::MutPointer(3), I::MutPointer(-5)]),
+            Program::from_code(vec![I::MutPointer(3), I::MutPointer(-5)]),
             Settings::new().with_strict(),
         );
         assert_eq!(
@@ -294,13 +863,390 @@ mod tests {
         )
     }
 
+    #[test]
+    fn changes_cells_at_offset() {
+        let mut eng = Engine::new_std_default(Program::from_code(vec![
+            I::MutPointer(2),
+            I::MutCell {
+                offset: -1,
+                delta: 3,
+            },
+            I::SetCell {
+                offset: 1,
+                value: 7,
+            },
+        ]));
+        eng.run().unwrap();
+        assert_eq!(eng.pointer, 2);
+        assert_eq!(eng.memory()[..4], [0, 3, 0, 7]);
+    }
+
+    #[test]
+    fn returns_error_on_offset_overflow_with_strict() {
+        let mut eng = Engine::new_std(
+            Program::from_code(vec![I::SetCell {
+                offset: -2,
+                value: 1,
+            }]),
+            Settings::new().with_strict(),
+        );
+        assert_eq!(
+            eng.run(),
+            Err(RuntimeError::TapeOverflow { from: 0, by: -2 })
+        )
+    }
+
+    #[test]
+    fn scans_with_stride() {
+        let mut eng = Engine::new_std_default(Program::from_code(vec![
+            I::MutCell {
+                offset: 0,
+                delta: 1,
+            },
+            I::MutPointer(2),
+            I::MutCell {
+                offset: 0,
+                delta: 1,
+            },
+            I::MutPointer(-2),
+            I::ScanRight(2),
+        ]));
+        eng.run().unwrap();
+        assert_eq!(eng.pointer, 4);
+    }
+
+    #[test]
+    fn wraps_around_scan_without_strict() {
+        let mut eng = Engine::new_std(
+            Program::from_code(vec![
+                I::MutCell {
+                    offset: 0,
+                    delta: 1,
+                },
+                I::MutPointer(-1),
+                I::MutCell {
+                    offset: 0,
+                    delta: 1,
+                },
+                I::ScanRight(1),
+            ]),
+            Settings::try_new(5, false, false).unwrap(),
+        );
+        eng.run().unwrap();
+        assert_eq!(eng.pointer, 1);
+    }
+
+    #[test]
+    fn returns_error_on_scan_overflow_with_strict() {
+        let mut eng = Engine::new_std(
+            Program::from_code(vec![
+                I::MutCell {
+                    offset: 0,
+                    delta: 1,
+                },
+                I::ScanLeft(1),
+            ]),
+            Settings::new().with_strict(),
+        );
+        assert_eq!(
+            eng.run(),
+            Err(RuntimeError::TapeOverflow { from: 0, by: -1 })
+        )
+    }
+
     #[test]
     fn wraps_around_custom_tape_length_without_strict() {
         let mut eng = Engine::new_std(
-            Program(vec![I::MutCell(13), I::MutPointer(21)]),
+            Program::from_code(vec![
+                I::MutCell {
+                    offset: 0,
+                    delta: 13,
+                },
+                I::MutPointer(21),
+            ]),
             Settings::try_new(21, false, false).unwrap(),
         );
         eng.run().unwrap();
         assert_eq!(*eng.c(), 13);
     }
+
+    #[test]
+    fn grows_tape_to_the_right() {
+        let mut eng = Engine::new_std(
+            Program::from_code(vec![
+                I::MutPointer(5),
+                I::MutCell {
+                    offset: 0,
+                    delta: 1,
+                },
+                I::ScanRight(1),
+            ]),
+            Settings::try_new(3, true, false)
+                .unwrap()
+                .with_tape_mode(TapeMode::GrowRight),
+        );
+        eng.run().unwrap();
+        assert_eq!(eng.pointer, 6);
+        assert_eq!(eng.memory()[5], 1);
+        assert!(eng.memory().len() > 6);
+    }
+
+    #[test]
+    fn returns_error_on_left_edge_of_growing_right_tape() {
+        let mut eng = Engine::new_std(
+            Program::from_code(vec![I::MutPointer(-1)]),
+            Settings::new().with_tape_mode(TapeMode::GrowRight),
+        );
+        assert_eq!(
+            eng.run(),
+            Err(RuntimeError::TapeOverflow { from: 0, by: -1 })
+        )
+    }
+
+    #[test]
+    fn grows_tape_in_both_directions() {
+        let mut eng = Engine::new_std(
+            Program::from_code(vec![
+                I::MutCell {
+                    offset: 0,
+                    delta: 1,
+                },
+                I::MutPointer(-4),
+                I::SetCell {
+                    offset: 0,
+                    value: 2,
+                },
+                I::ScanLeft(1),
+                I::MutCell {
+                    offset: 0,
+                    delta: -1,
+                },
+            ]),
+            Settings::try_new(3, true, false)
+                .unwrap()
+                .with_tape_mode(TapeMode::GrowBoth),
+        );
+        assert_eq!(
+            eng.run(),
+            Err(RuntimeError::CellOverflow {
+                at: -5,
+                from: 0,
+                by: -1
+            })
+        );
+        assert_eq!(eng.position(), -5);
+        assert_eq!(eng.memory()[eng.origin() as usize], 1);
+        assert_eq!(eng.memory()[eng.origin() as usize - 4], 2);
+    }
+
+    #[test]
+    fn returns_error_when_out_of_fuel() {
+        let mut eng = Engine::new_std_default(Program::from_code(vec![
+            I::MutCell {
+                offset: 0,
+                delta: 1,
+            },
+            I::JumpRightZ(1),
+            I::JumpLeftNz(1),
+        ]));
+        assert_eq!(eng.run_with_fuel(100), Err(RuntimeError::OutOfFuel));
+        assert_eq!(eng.pc(), 2);
+    }
+
+    #[test]
+    fn resumes_after_running_out_of_fuel() {
+        let mut buffer = Vec::new();
+        let mut eng = Engine::new_byte_default(
+            Program::from_code(vec![I::Input, I::Output, I::Input, I::Output]),
+            b"ab",
+            &mut buffer,
+        );
+        assert_eq!(eng.run_with_fuel(3), Err(RuntimeError::OutOfFuel));
+        assert_eq!(eng.run_with_fuel(1), Ok(()));
+        assert_eq!(buffer, b"ab");
+    }
+
+    #[test]
+    fn returns_error_when_deadline_is_exceeded() {
+        let mut eng = Engine::new_std_default(Program::from_code(vec![
+            I::MutCell {
+                offset: 0,
+                delta: 1,
+            },
+            I::JumpRightZ(1),
+            I::JumpLeftNz(1),
+        ]));
+        assert_eq!(
+            eng.run_until(Instant::now() + Duration::from_millis(10)),
+            Err(RuntimeError::DeadlineExceeded)
+        );
+    }
+
+    #[test]
+    fn preserves_io_errors() {
+        let io = FnIo::new(
+            || Err(io::Error::new(ErrorKind::PermissionDenied, "denied")),
+            |_| Ok(()),
+        );
+        let mut eng = Engine::new(Program::from_code(vec![I::Input]), Settings::default(), io);
+        let Err(RuntimeError::InputError(err)) = eng.run() else {
+            panic!("expected an input error");
+        };
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+        assert_eq!(err.get_ref().to_string(), "denied");
+    }
+
+    #[test]
+    fn flushes_buffered_output_when_stopping() {
+        #[derive(Debug, Default)]
+        struct Buffered {
+            buffer: Vec<u8>,
+            flushed: Vec<u8>,
+        }
+
+        impl BrainIo for Buffered {
+            fn read_byte(&mut self) -> io::Result<Option<u8>> {
+                Ok(None)
+            }
+
+            fn write_byte(&mut self, byte: u8) -> io::Result<()> {
+                self.buffer.push(byte);
+                Ok(())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                self.flushed.append(&mut self.buffer);
+                Ok(())
+            }
+        }
+
+        let program = Program::from_code(vec![I::Output, I::Output, I::JumpLeftNz(0)]);
+        let mut eng = Engine::new(program, Settings::default(), Buffered::default());
+        let _ = eng.step();
+        assert_eq!(eng.io().flushed, []);
+        assert_eq!(eng.run_with_fuel(1), Err(RuntimeError::OutOfFuel));
+        assert_eq!(eng.io().flushed, [0, 0]);
+        eng.run().unwrap();
+        assert_eq!(eng.io().flushed, [0, 0]);
+    }
+
+    #[test]
+    fn resumes_from_snapshot() {
+        let program = Program::compile(",>,[<+>-]<.", &Settings::new()).unwrap();
+
+        let mut buffer = Vec::new();
+        let mut eng = Engine::new_byte_default(program.clone(), b"\x02\x03", &mut buffer);
+        assert_eq!(eng.run_with_fuel(2), Err(RuntimeError::OutOfFuel));
+        let state = eng.snapshot();
+        assert_eq!(state.input_position(), 1);
+
+        let mut buffer = Vec::new();
+        let mut eng = Engine::new_byte_default(program, b"\x03", &mut buffer);
+        eng.restore(state).unwrap();
+        eng.run().unwrap();
+        assert_eq!(buffer, [5]);
+    }
+
+    #[test]
+    fn rejects_mismatched_snapshot() {
+        let state =
+            Engine::new_std_default(Program::compile("+", &Settings::new()).unwrap()).snapshot();
+
+        let mut eng = Engine::new_std(Program::from_code(Vec::new()), Settings::new());
+        assert_eq!(eng.restore(state.clone()), Ok(()));
+
+        let mut eng = Engine::new_std(
+            Program::from_code(Vec::new()),
+            Settings::try_new(100, false, false).unwrap(),
+        );
+        assert_eq!(eng.restore(state), Err(StateError::SettingsMismatch));
+    }
+
+    #[test]
+    fn steps_back_to_previous_states() {
+        let program = Program::compile("+>++[->+<]<<", &Settings::new()).unwrap();
+        let mut eng = Engine::new_std(program, Settings::new()).with_history(1024);
+
+        let mut states = Vec::new();
+        loop {
+            states.push(eng.snapshot());
+            if eng.step().is_none() {
+                break;
+            }
+        }
+        states.pop();
+
+        while let Some(state) = states.pop() {
+            assert!(eng.step_back().is_some());
+            assert_eq!(eng.snapshot(), state);
+        }
+        assert_eq!(eng.step_back(), None);
+    }
+
+    #[test]
+    fn steps_back_over_tape_growth() {
+        let mut eng = Engine::new_std(
+            Program::from_code(vec![I::SetCell {
+                offset: -5,
+                value: 1,
+            }]),
+            Settings::try_new(3, false, false)
+                .unwrap()
+                .with_tape_mode(TapeMode::GrowBoth),
+        )
+        .with_history(1024);
+        eng.run().unwrap();
+        assert!(eng.step_back().is_some());
+        assert_eq!(eng.memory(), [0, 0, 0]);
+        assert_eq!((eng.pointer(), eng.origin()), (0, 0));
+    }
+
+    #[test]
+    fn replays_input_after_stepping_back() {
+        let mut buffer = Vec::new();
+        let mut eng = Engine::new_byte_default(
+            Program::from_code(vec![I::Input, I::Output]),
+            b"ab",
+            &mut buffer,
+        )
+        .with_history(1024);
+        eng.run().unwrap();
+        assert!(eng.step_back().is_some());
+        assert!(eng.step_back().is_some());
+        assert_eq!(eng.input_position(), 0);
+        eng.run().unwrap();
+        assert_eq!(buffer, b"aa");
+    }
+
+    #[test]
+    fn limits_history_to_memory_cap() {
+        let program = Program::from_code(vec![I::MutPointer(1); 100]);
+        let mut eng = Engine::new_std_default(program).with_history(256);
+        eng.run().unwrap();
+        assert!(eng.history_len() > 0 && eng.history_len() < 100);
+        assert_eq!(eng.pc(), 100);
+    }
+
+    #[test]
+    fn reads_cells_by_position() {
+        let settings = Settings::try_new(3, false, false).unwrap();
+        let mut eng = Engine::new_std(
+            Program::from_code(vec![
+                I::MutPointer(2),
+                I::MutCell {
+                    offset: 0,
+                    delta: 5,
+                },
+            ]),
+            settings,
+        );
+        eng.run().unwrap();
+        assert_eq!(eng.cell(2), Some(5));
+        assert_eq!(eng.cell(3), None);
+        assert_eq!(eng.cell(-1), None);
+
+        let eng = Engine::new_std(Program::new(), settings.with_tape_mode(TapeMode::GrowRight));
+        assert_eq!(eng.cell(10), Some(0));
+        assert_eq!(eng.cell(-1), None);
+    }
 }
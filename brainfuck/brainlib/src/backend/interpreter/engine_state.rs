@@ -0,0 +1,288 @@
+use std::io::{self, Read, Write};
+
+use thiserror::Error;
+
+use crate::Settings;
+
+/// A snapshot of the execution state of an [`Engine`](crate::interpreter::Engine).
+///
+/// It is created with [`Engine::snapshot`](crate::interpreter::Engine::snapshot) and
+/// loaded back with [`Engine::restore`](crate::interpreter::Engine::restore).
+///
+/// The state can be saved using [`EngineState::write_to`] and read using [`EngineState::read_from`].
+/// The format is compact: numbers are stored as LEB128[^1], and runs of zero cells are collapsed.
+/// It starts with the `bfst` magic bytes and a version byte, so that future versions can be detected.
+///
+/// # Examples
+/// ```
+/// # use brainlib::{interpreter::{Engine, EngineState}, Program};
+/// let program = Program::compile("+++>++>+", &Default::default())?;
+/// let mut engine = Engine::new_std_default(program);
+/// engine.run()?;
+///
+/// let mut bytes = Vec::new();
+/// engine.snapshot().write_to(&mut bytes)?;
+/// assert!(bytes.starts_with(b"bfst"));
+///
+/// let state = EngineState::read_from(bytes.as_slice())?;
+/// assert_eq!(state.memory()[..3], [3, 2, 1]);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+///
+/// [^1]: [LEB128 - Wikipedia](https://en.wikipedia.org/wiki/LEB128)
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EngineState {
+    pub(crate) pc: usize,
+    pub(crate) pointer: u32,
+    pub(crate) origin: u32,
+    pub(crate) memory: Vec<u32>,
+    pub(crate) input_position: u64,
+}
+
+/// Error which occurs when restoring an [`EngineState`] which doesn't match the engine.
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Error)]
+pub enum StateError {
+    /// The program counter is past the end of the engine's program.
+    #[error("program counter out of range")]
+    ProgramMismatch,
+
+    /// The tape doesn't match the engine's settings (length, mode or cell width).
+    #[error("tape does not match settings")]
+    SettingsMismatch,
+}
+
+impl EngineState {
+    const MAGIC: &'static [u8; 4] = b"bfst";
+    const VERSION: u8 = 1;
+
+    /// Returns the saved program counter.
+    #[must_use]
+    pub const fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// Returns the saved cell pointer, as an index into [`EngineState::memory`].
+    #[must_use]
+    pub const fn pointer(&self) -> u32 {
+        self.pointer
+    }
+
+    /// Returns the index into [`EngineState::memory`] of the initial first cell.
+    #[must_use]
+    pub const fn origin(&self) -> u32 {
+        self.origin
+    }
+
+    /// Returns the saved memory (cell vector).
+    #[must_use]
+    pub fn memory(&self) -> &[u32] {
+        &self.memory
+    }
+
+    /// Returns the number of bytes consumed from the input.
+    #[must_use]
+    pub const fn input_position(&self) -> u64 {
+        self.input_position
+    }
+
+    /// Writes the state in the binary format to the given [`Write`].
+    pub fn write_to(&self, mut write: impl Write) -> io::Result<()> {
+        let mut bytes = Self::MAGIC.to_vec();
+        bytes.push(Self::VERSION);
+        for value in [
+            self.pc as u64,
+            self.pointer.into(),
+            self.origin.into(),
+            self.input_position,
+            self.memory.len() as u64,
+        ] {
+            write_leb128(&mut bytes, value);
+        }
+
+        let mut cells = self.memory.iter().peekable();
+        while let Some(&cell) = cells.next() {
+            write_leb128(&mut bytes, cell.into());
+            if cell == 0 {
+                // A zero is followed by the count of zeros directly after it.
+                let mut run = 0;
+                while cells.next_if_eq(&&0).is_some() {
+                    run += 1;
+                }
+                write_leb128(&mut bytes, run);
+            }
+        }
+
+        write.write_all(&bytes)
+    }
+
+    /// Reads a state in the binary format from the given [`Read`].
+    ///
+    /// Returns an [`io::Error`] of kind [`io::ErrorKind::InvalidData`] if the data is malformed
+    /// or was written by an unsupported version. The memory can't be longer than [`Settings::MAX_LENGTH`].
+    pub fn read_from(mut read: impl Read) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        read.read_to_end(&mut bytes)?;
+
+        let Some(rest) = bytes.strip_prefix(Self::MAGIC) else {
+            return Err(invalid_data("not an engine state"));
+        };
+        let Some((&version, mut rest)) = rest.split_first() else {
+            return Err(invalid_data("missing version"));
+        };
+        if version != Self::VERSION {
+            return Err(invalid_data("unsupported version"));
+        }
+
+        let mut next = |max: u64| match read_leb128(&mut rest) {
+            Some(value) if value <= max => Ok(value),
+            _ => Err(invalid_data("malformed engine state")),
+        };
+
+        let pc = next(usize::MAX as u64)? as usize;
+        let pointer = next(u32::MAX.into())? as u32;
+        let origin = next(u32::MAX.into())? as u32;
+        let input_position = next(u64::MAX)?;
+        let length = next(Settings::MAX_LENGTH.into())? as usize;
+
+        // The memory grows only as cells are read, so that a short input can't claim a huge tape.
+        let mut memory = Vec::new();
+        while memory.len() < length {
+            let cell = next(u32::MAX.into())? as u32;
+            let count = match cell {
+                0 => next((length - memory.len() - 1) as u64)? as usize + 1,
+                _ => 1,
+            };
+            memory
+                .try_reserve(count)
+                .map_err(|_| io::Error::from(io::ErrorKind::OutOfMemory))?;
+            memory.resize(memory.len() + count, cell);
+        }
+
+        if !rest.is_empty() {
+            return Err(invalid_data("trailing data after engine state"));
+        }
+
+        Ok(Self {
+            pc,
+            pointer,
+            origin,
+            memory,
+            input_position,
+        })
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+pub(super) fn write_leb128(bytes: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        match value {
+            0 => return bytes.push(byte),
+            _ => bytes.push(byte | 0x80),
+        }
+    }
+}
+
+#[must_use]
+fn read_leb128(bytes: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+        let bits = u64::from(byte & 0x7F);
+        // The tenth byte holds only the highest bit, anything more would be silently dropped.
+        if shift == 63 && bits > 1 {
+            return None;
+        }
+        value |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::EngineState;
+
+    fn state(memory: Vec<u32>) -> EngineState {
+        EngineState {
+            pc: 1234,
+            pointer: 3,
+            origin: 1,
+            memory,
+            input_position: 56,
+        }
+    }
+
+    fn roundtrip(state: &EngineState) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        state.write_to(&mut bytes).unwrap();
+        assert_eq!(&EngineState::read_from(bytes.as_slice()).unwrap(), state);
+        bytes
+    }
+
+    #[test]
+    fn reads_written_state() {
+        roundtrip(&state(vec![0, 1, 0, 0, 300, u32::MAX, 0]));
+    }
+
+    #[test]
+    fn collapses_zero_cells() {
+        let mut memory = vec![0; 30_000];
+        memory[100] = 7;
+        assert!(roundtrip(&state(memory)).len() < 24);
+    }
+
+    #[test]
+    fn rejects_unknown_format() {
+        let error = EngineState::read_from(b"bfs".as_slice()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_unsupported_version() {
+        let mut bytes = roundtrip(&state(vec![0; 3]));
+        bytes[4] = 2;
+        let error = EngineState::read_from(bytes.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_truncated_state() {
+        let bytes = roundtrip(&state(vec![5; 10]));
+        let error = EngineState::read_from(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn reads_largest_input_position() {
+        roundtrip(&EngineState {
+            input_position: u64::MAX,
+            ..state(vec![1])
+        });
+    }
+
+    #[test]
+    fn rejects_overlong_number() {
+        let bytes = b"bfst\x01\0\0\0\xff\xff\xff\xff\xff\xff\xff\xff\xff\x02\0";
+        let error = EngineState::read_from(bytes.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_memory_longer_than_max_length() {
+        let bytes = b"bfst\x01\0\0\0\0\xff\xff\xff\xff\x0f";
+        let error = EngineState::read_from(bytes.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
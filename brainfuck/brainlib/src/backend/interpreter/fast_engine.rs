@@ -0,0 +1,613 @@
+use std::{
+    fmt::{self, Debug},
+    sync::Arc,
+};
+
+use crate::{
+    backend::common::{Instruction, Program, Settings},
+    interpreter::{tape::Tape, BrainIo, Engine, EngineState, EngineView, RuntimeError},
+};
+
+/// An engine which executes programs faster than [`Engine`], at the cost of being less inspectable.
+///
+/// The [`Program`] is compiled up front into closures specialized for the [`Settings`],
+/// with the jump targets resolved to absolute positions. They handle the common cases,
+/// while the rest (such as I/O or growing the tape) goes through a slower interpreter loop.
+/// There are no observers, no undo log and no single stepping, use [`Engine`] when these are needed.
+///
+/// The results are the same as those of [`Engine`], including the [`RuntimeError`]s and the number of
+/// instructions counted by [`FastEngine::run_with_fuel`]. An [`Engine`] can be converted into a
+/// [`FastEngine`] and back at any time, keeping the execution state.
+///
+/// # Examples
+/// ```
+/// # use brainlib::{interpreter::{Engine, FastEngine}, Program, Settings};
+/// let program = Program::compile("++++++++[>++++++++<-]>+.", &Settings::default())?;
+/// let mut output = Vec::new();
+/// let mut engine = FastEngine::from(Engine::new_byte_default(program, &[], &mut output));
+/// engine.run()?;
+///
+/// let engine = Engine::from(engine);
+/// assert_eq!(engine.memory()[..2], [0, 65]);
+/// # drop(engine);
+/// assert_eq!(output, b"A");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FastEngine<Io: BrainIo> {
+    core: Core,
+    io: Io,
+}
+
+/// The state of a [`FastEngine`] apart from its [`BrainIo`].
+///
+/// It isn't generic, so that the interpreter loop is compiled as a part of this crate,
+/// instead of every crate which uses the [`FastEngine`], and is optimized even when they aren't.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Core {
+    program: Program,
+    ops: Vec<Op>,
+    closures: Closures,
+    pc: usize,
+    pointer: u32,
+    origin: u32,
+    memory: Vec<u32>,
+    input_position: u64,
+    settings: Settings,
+}
+
+/// An [`Instruction`] translated for the [`FastEngine`], there is exactly one per instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    MovePointer(i32),
+    /// Add `delta` to a cell, wrapping around to the cell width.
+    AddWrapping {
+        offset: i32,
+        delta: u32,
+    },
+    /// Add `delta` to a cell, failing if it doesn't fit in the cell width.
+    AddChecked {
+        offset: i32,
+        delta: i32,
+    },
+    SetCell {
+        offset: i32,
+        value: u32,
+    },
+    MulAdd {
+        offset: i32,
+        factor: u32,
+    },
+    Scan(i32),
+    /// Jump to the given position if the current cell is zero.
+    JumpZ(usize),
+    /// Jump to the given position if the current cell is not zero.
+    JumpNz(usize),
+    Input,
+    Output,
+    Nop,
+}
+
+/// The [`Op`]s compiled into closures, which run until one of them returns `false`.
+///
+/// A closure returns `false` without changing anything if its instruction needs the slow path,
+/// which is then executed by [`Core::exec`]. They are stateless, so they can be shared between clones.
+#[derive(Clone)]
+struct Closures(Arc<[Closure]>);
+
+type Closure = Box<dyn Fn(&mut Regs<'_>) -> bool + Send + Sync>;
+
+/// The state which the [`Closures`] operate on, borrowed from the [`Core`] while they run.
+struct Regs<'m> {
+    memory: &'m mut [u32],
+    pointer: usize,
+    pc: usize,
+}
+
+impl Op {
+    fn translate(pc: usize, instruction: Instruction, settings: &Settings) -> Self {
+        use Instruction as I;
+        match instruction {
+            I::MutPointer(change) => Self::MovePointer(change),
+            I::MutCell { offset, delta } if settings.strict() => Self::AddChecked { offset, delta },
+            I::MutCell { offset, delta } => Self::AddWrapping {
+                offset,
+                delta: delta as u32,
+            },
+            I::SetCell { offset, value } => Self::SetCell { offset, value },
+            I::MulAdd { offset, factor } => Self::MulAdd {
+                offset,
+                factor: factor as u32,
+            },
+            I::ScanRight(stride) => Self::Scan(stride as i32),
+            I::ScanLeft(stride) => Self::Scan(-(stride as i32)),
+            I::JumpRightZ(offset) => Self::JumpZ(pc + 1 + offset as usize),
+            I::JumpLeftNz(offset) => Self::JumpNz(pc + 1 - offset as usize),
+            I::Input => Self::Input,
+            I::Output => Self::Output,
+            I::Breakpoint(_) => Self::Nop,
+        }
+    }
+}
+
+impl Closures {
+    fn compile(ops: &[Op], settings: &Settings) -> Self {
+        let max = settings.cell_width().max();
+        let closures = (ops.iter().enumerate())
+            .map(|(pc, &op)| Self::compile_op(pc + 1, op, max))
+            .collect();
+        Self(closures)
+    }
+
+    /// Compiles the [`Op`] followed by the instruction at `next`, on a tape of cells up to `max`.
+    ///
+    /// Negative offsets wrap around to huge indices, so a single comparison finds the cells outside of the tape.
+    fn compile_op(next: usize, op: Op, max: u32) -> Closure {
+        match op {
+            Op::MovePointer(change) => {
+                let change = change as isize as usize;
+                Box::new(move |r| {
+                    let pointer = r.pointer.wrapping_add(change);
+                    if pointer >= r.memory.len() {
+                        return false;
+                    }
+                    r.pointer = pointer;
+                    r.pc = next;
+                    true
+                })
+            },
+            Op::AddWrapping { offset, delta } => {
+                let offset = offset as isize as usize;
+                Box::new(move |r| {
+                    let at = r.pointer.wrapping_add(offset);
+                    if at >= r.memory.len() {
+                        return false;
+                    }
+                    r.memory[at] = r.memory[at].wrapping_add(delta) & max;
+                    r.pc = next;
+                    true
+                })
+            },
+            Op::AddChecked { offset, delta } => {
+                let offset = offset as isize as usize;
+                Box::new(move |r| {
+                    let at = r.pointer.wrapping_add(offset);
+                    if at >= r.memory.len() {
+                        return false;
+                    }
+                    let value = r.memory[at] as i64 + delta as i64;
+                    if value < 0 || value > max as i64 {
+                        return false;
+                    }
+                    r.memory[at] = value as u32;
+                    r.pc = next;
+                    true
+                })
+            },
+            Op::SetCell { offset, value } => {
+                let offset = offset as isize as usize;
+                Box::new(move |r| {
+                    let at = r.pointer.wrapping_add(offset);
+                    if at >= r.memory.len() {
+                        return false;
+                    }
+                    r.memory[at] = value;
+                    r.pc = next;
+                    true
+                })
+            },
+            Op::MulAdd { offset, factor } => {
+                let offset = offset as isize as usize;
+                Box::new(move |r| {
+                    let at = r.pointer.wrapping_add(offset);
+                    if at >= r.memory.len() {
+                        return false;
+                    }
+                    let value = r.memory[r.pointer];
+                    r.memory[at] = r.memory[at].wrapping_add(value.wrapping_mul(factor)) & max;
+                    r.pc = next;
+                    true
+                })
+            },
+            Op::Scan(by) => {
+                let by = by as isize as usize;
+                Box::new(move |r| {
+                    let mut at = r.pointer;
+                    while r.memory[at] != 0 {
+                        at = at.wrapping_add(by);
+                        if at >= r.memory.len() {
+                            return false;
+                        }
+                    }
+                    r.pointer = at;
+                    r.pc = next;
+                    true
+                })
+            },
+            Op::JumpZ(target) => Box::new(move |r| {
+                r.pc = if r.memory[r.pointer] == 0 {
+                    target
+                } else {
+                    next
+                };
+                true
+            }),
+            Op::JumpNz(target) => Box::new(move |r| {
+                r.pc = if r.memory[r.pointer] != 0 {
+                    target
+                } else {
+                    next
+                };
+                true
+            }),
+            Op::Input | Op::Output => Box::new(|_| false),
+            Op::Nop => Box::new(move |r| {
+                r.pc = next;
+                true
+            }),
+        }
+    }
+}
+
+impl Debug for Closures {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Closures({})", self.0.len())
+    }
+}
+
+/// The closures are compiled from the [`Op`]s, which are compared instead.
+impl PartialEq for Closures {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Closures {}
+
+impl<Io: BrainIo> FastEngine<Io> {
+    /// Creates a new [`FastEngine`] with the provided [`Program`], [`Settings`] and [`BrainIo`].
+    pub fn new(program: Program, settings: Settings, io: Io) -> Self {
+        Self::from(Engine::new(program, settings, io))
+    }
+
+    /// Returns the used [`BrainIo`].
+    pub const fn io(&self) -> &Io {
+        &self.io
+    }
+
+    /// Returns the used [`BrainIo`] mutably.
+    pub fn io_mut(&mut self) -> &mut Io {
+        &mut self.io
+    }
+
+    /// Returns the [`Program`] which the [`FastEngine`] is executing.
+    pub const fn program(&self) -> &Program {
+        &self.core.program
+    }
+
+    /// Returns the current program counter, see [`Engine::pc`].
+    #[must_use]
+    pub const fn pc(&self) -> usize {
+        self.core.pc
+    }
+
+    /// Returns the current cell pointer, relative to the initial first cell, see [`Engine::position`].
+    #[must_use]
+    pub const fn position(&self) -> i32 {
+        self.core.pointer as i32 - self.core.origin as i32
+    }
+
+    /// Returns a view into the memory (cell vector), see [`Engine::memory`].
+    #[must_use]
+    pub fn memory(&self) -> &[u32] {
+        &self.core.memory
+    }
+
+    /// Returns the value of the cell at the given position, see [`Engine::cell`].
+    #[must_use]
+    pub fn cell(&self, position: i32) -> Option<u32> {
+        let view = EngineView {
+            pc: self.core.pc,
+            pointer: self.core.pointer,
+            origin: self.core.origin,
+            memory: &self.core.memory,
+            settings: &self.core.settings,
+        };
+        view.cell(position)
+    }
+
+    /// Returns the number of bytes consumed from the input.
+    #[must_use]
+    pub const fn input_position(&self) -> u64 {
+        self.core.input_position
+    }
+
+    /// Returns the used [`Settings`].
+    pub const fn settings(&self) -> &Settings {
+        &self.core.settings
+    }
+
+    /// Writes out the output buffered by the [`BrainIo`], see [`Engine::flush`].
+    pub fn flush(&mut self) -> Result<(), RuntimeError> {
+        self.io.flush().map_err(RuntimeError::output)
+    }
+
+    /// Runs the [`FastEngine`] until it halts or a [`RuntimeError`] occurs.
+    pub fn run(&mut self) -> Result<(), RuntimeError> {
+        // Even at a billion instructions per second, this much fuel lasts for centuries.
+        self.run_with_fuel(u64::MAX)
+    }
+
+    /// Same as [`FastEngine::run`], but executes at most `fuel` instructions, see [`Engine::run_with_fuel`].
+    pub fn run_with_fuel(&mut self, fuel: u64) -> Result<(), RuntimeError> {
+        match self.core.run_steps(fuel, &mut self.io) {
+            Err(RuntimeError::OutOfFuel) => self.flush().and(Err(RuntimeError::OutOfFuel)),
+            result => result.and(self.flush()),
+        }
+    }
+}
+
+impl Core {
+    fn run_steps(&mut self, mut fuel: u64, io: &mut dyn BrainIo) -> Result<(), RuntimeError> {
+        loop {
+            self.run_closures(&mut fuel);
+            let Some(&op) = self.ops.get(self.pc) else {
+                return Ok(());
+            };
+            if fuel == 0 {
+                return Err(RuntimeError::OutOfFuel);
+            }
+            fuel -= 1;
+            self.pc += 1;
+            self.exec(op, io)?;
+        }
+    }
+
+    /// Runs the [`Closures`] until the program halts, the fuel runs out or an instruction needs the slow path.
+    fn run_closures(&mut self, fuel: &mut u64) {
+        let closures = &*self.closures.0;
+        let mut regs = Regs {
+            memory: &mut self.memory,
+            pointer: self.pointer as usize,
+            pc: self.pc,
+        };
+        while regs.pc < closures.len() && *fuel > 0 && closures[regs.pc](&mut regs) {
+            *fuel -= 1;
+        }
+        self.pointer = regs.pointer as u32;
+        self.pc = regs.pc;
+    }
+
+    fn exec(&mut self, op: Op, io: &mut dyn BrainIo) -> Result<(), RuntimeError> {
+        match op {
+            Op::MovePointer(change) => self.pointer = self.cell_at(change)? as u32,
+            Op::AddWrapping { offset, delta } => {
+                let at = self.cell_at(offset)?;
+                let max = self.settings.cell_width().max();
+                self.memory[at] = self.memory[at].wrapping_add(delta) & max;
+            },
+            Op::AddChecked { offset, delta } => {
+                let at = self.cell_at(offset)?;
+                let previous = self.memory[at];
+                self.memory[at] =
+                    self.settings
+                        .mut_cell(previous, delta)
+                        .ok_or(RuntimeError::CellOverflow {
+                            at: at as i32 - self.origin as i32,
+                            from: previous,
+                            by: delta,
+                        })?;
+            },
+            Op::SetCell { offset, value } => {
+                let at = self.cell_at(offset)?;
+                self.memory[at] = value;
+            },
+            Op::MulAdd { offset, factor } => {
+                let value = self.memory[self.pointer as usize];
+                let at = self.cell_at(offset)?;
+                let max = self.settings.cell_width().max();
+                self.memory[at] = self.memory[at].wrapping_add(value.wrapping_mul(factor)) & max;
+            },
+            Op::Scan(by) => self.scan(by)?,
+            Op::JumpZ(target) =>
+                if self.memory[self.pointer as usize] == 0 {
+                    self.pc = target;
+                },
+            Op::JumpNz(target) =>
+                if self.memory[self.pointer as usize] != 0 {
+                    self.pc = target;
+                },
+            Op::Input => {
+                let byte = io.read_byte().map_err(RuntimeError::input)?;
+                let cell = &mut self.memory[self.pointer as usize];
+                *cell = match byte {
+                    Some(byte) => {
+                        self.input_position += 1;
+                        byte.into()
+                    },
+                    None => self.settings.eof_value(*cell),
+                };
+            },
+            Op::Output => {
+                // Only the least significant byte of wider cells is written.
+                let value = self.memory[self.pointer as usize] as u8;
+                io.write_byte(value).map_err(RuntimeError::output)?;
+            },
+            Op::Nop => (),
+        }
+        Ok(())
+    }
+
+    /// Returns the index of the cell `offset` cells away from the pointer, growing the tape if needed.
+    ///
+    /// Cells inside of the tape are found without going through the [`Tape`].
+    #[inline(always)]
+    fn cell_at(&mut self, offset: i32) -> Result<usize, RuntimeError> {
+        match usize::try_from(i64::from(self.pointer) + i64::from(offset)) {
+            Ok(index) if index < self.memory.len() => Ok(index),
+            _ => self.tape().cell_at(offset).map(|index| index as usize),
+        }
+    }
+
+    /// Moves the pointer by `by` cells at a time until it points to a zero cell.
+    ///
+    /// Scans which stay inside of the tape are done without going through the [`Tape`],
+    /// the others are started over by it.
+    fn scan(&mut self, by: i32) -> Result<(), RuntimeError> {
+        let mut at = self.pointer as usize;
+        while self.memory[at] != 0 {
+            match usize::try_from(at as i64 + i64::from(by)) {
+                Ok(next) if next < self.memory.len() => at = next,
+                _ => {
+                    if !self.tape().scan(by)? {
+                        // No zero cell can be reached, so the loop never ends, run it again on the next step.
+                        self.pc -= 1;
+                    }
+                    return Ok(());
+                },
+            }
+        }
+        self.pointer = at as u32;
+        Ok(())
+    }
+
+    /// Borrows the memory together with the pointer, see [`Tape`].
+    fn tape(&mut self) -> Tape<'_> {
+        Tape {
+            memory: &mut self.memory,
+            pointer: &mut self.pointer,
+            origin: &mut self.origin,
+            settings: &self.settings,
+        }
+    }
+}
+
+impl<Io: BrainIo> From<Engine<Io>> for FastEngine<Io> {
+    /// Translates the program of the [`Engine`], keeping its execution state but dropping its undo log.
+    fn from(engine: Engine<Io>) -> Self {
+        let (program, settings, io, state) = engine.into_parts();
+        let ops = (program.code().iter().enumerate())
+            .map(|(pc, &instruction)| Op::translate(pc, instruction, &settings))
+            .collect::<Vec<_>>();
+        let closures = Closures::compile(&ops, &settings);
+        let core = Core {
+            program,
+            ops,
+            closures,
+            pc: state.pc,
+            pointer: state.pointer,
+            origin: state.origin,
+            memory: state.memory,
+            input_position: state.input_position,
+            settings,
+        };
+        Self { core, io }
+    }
+}
+
+impl<Io: BrainIo> From<FastEngine<Io>> for Engine<Io> {
+    /// Returns an [`Engine`] which continues the execution from where the [`FastEngine`] stopped.
+    fn from(engine: FastEngine<Io>) -> Self {
+        let core = engine.core;
+        let state = EngineState {
+            pc: core.pc,
+            pointer: core.pointer,
+            origin: core.origin,
+            memory: core.memory,
+            input_position: core.input_position,
+        };
+        Self::from_parts(core.program, core.settings, engine.io, state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::FastEngine;
+    use crate::{
+        interpreter::{ByteIo, Engine, EngineState, RuntimeError},
+        EofBehavior, Program, Settings, TapeMode,
+    };
+
+    type Outcome = (Result<(), RuntimeError>, EngineState, Vec<u8>);
+
+    fn run_engine(source: &str, input: &str, settings: Settings) -> Outcome {
+        let program = Program::compile(source, &settings).unwrap();
+        let mut output = Vec::new();
+        let mut eng = Engine::new_byte(program, settings, input.as_bytes(), &mut output);
+        let result = eng.run_with_fuel(10_000);
+        let state = eng.snapshot();
+        drop(eng);
+        (result, state, output)
+    }
+
+    fn run_fast_engine(source: &str, input: &str, settings: Settings) -> Outcome {
+        let program = Program::compile(source, &settings).unwrap();
+        let mut output = Vec::new();
+        let mut eng = FastEngine::from(Engine::new_byte(
+            program,
+            settings,
+            input.as_bytes(),
+            &mut output,
+        ));
+        let result = eng.run_with_fuel(10_000);
+        let state = Engine::from(eng).snapshot();
+        (result, state, output)
+    }
+
+    #[test_case("++++++++[>++++++++<-]>+.", ""; "hello")]
+    #[test_case(",[.,]", "Hello, world!"; "cat")]
+    #[test_case("<+>>-<[-<+>]", ""; "wrapping pointer")]
+    #[test_case("+[-<+>]<.", ""; "wrapping mul add")]
+    #[test_case("+>+>+>>+<<<<[>]", ""; "scan right")]
+    #[test_case(">+>+<+[<<]", ""; "scan left with stride")]
+    #[test_case("+>+>+[>]", ""; "endless scan")]
+    #[test_case("-+[+]>,,,", "A"; "reads past end of input")]
+    #[test_case("+[]", ""; "out of fuel")]
+    fn matches_engine(source: &str, input: &str) {
+        for settings in [
+            Settings::try_new(3, false, false).unwrap(),
+            Settings::new().with_strict(),
+            Settings::new().with_tape_mode(TapeMode::GrowRight),
+            Settings::new().with_tape_mode(TapeMode::GrowBoth),
+            Settings::new().with_eof_behavior(EofBehavior::Max),
+        ] {
+            assert_eq!(
+                run_fast_engine(source, input, settings),
+                run_engine(source, input, settings),
+            );
+        }
+    }
+
+    #[test]
+    fn stops_at_failed_instruction() {
+        let (result, state, _) = run_fast_engine("+>-", "", Settings::new().with_strict());
+        assert_eq!(
+            result,
+            Err(RuntimeError::CellOverflow {
+                at: 1,
+                from: 0,
+                by: -1
+            })
+        );
+        assert_eq!(state.pc(), 3);
+    }
+
+    #[test]
+    fn resumes_after_running_out_of_fuel() {
+        let program = Program::compile(",[.,]", &Settings::default()).unwrap();
+        let mut output = Vec::new();
+        let mut eng = FastEngine::new(
+            program,
+            Settings::default(),
+            ByteIo::new(b"abc", &mut output),
+        );
+        assert_eq!(eng.run_with_fuel(5), Err(RuntimeError::OutOfFuel));
+        assert_eq!((eng.pc(), eng.input_position()), (2, 2));
+        assert_eq!(eng.run(), Ok(()));
+        drop(eng);
+        assert_eq!(output, b"abc");
+    }
+}
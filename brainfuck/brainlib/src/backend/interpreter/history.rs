@@ -0,0 +1,95 @@
+use std::{collections::VecDeque, mem};
+
+/// The undo log of an [`Engine`](crate::interpreter::Engine), used to step backwards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct History {
+    entries: VecDeque<UndoEntry>,
+    capacity: usize,
+    /// Input which was taken back, and should be read again before the actual input.
+    replay: Vec<Option<u8>>,
+}
+
+/// The state changed by a single executed instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct UndoEntry {
+    pub(crate) pc: usize,
+    pub(crate) pointer: u32,
+    pub(crate) origin: u32,
+    pub(crate) length: u32,
+    /// The index and the previous value of the written cell.
+    pub(crate) write: Option<(u32, u32)>,
+    /// The byte read from the input, or `None` at the end of input.
+    pub(crate) read: Option<Option<u8>>,
+}
+
+impl History {
+    /// Creates an empty log, holding as many entries as fit in `memory_cap` bytes.
+    pub(crate) fn new(memory_cap: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity: memory_cap / mem::size_of::<UndoEntry>(),
+            replay: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Starts a new entry, dropping the oldest one if the log is full.
+    pub(crate) fn push(&mut self, pc: usize, pointer: u32, origin: u32, length: u32) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(UndoEntry {
+            pc,
+            pointer,
+            origin,
+            length,
+            write: None,
+            read: None,
+        });
+    }
+
+    /// Records a cell write in the latest entry.
+    pub(crate) fn record_write(&mut self, at: u32, previous: u32) {
+        if let Some(entry) = self.entries.back_mut() {
+            entry.write = Some((at, previous));
+        }
+    }
+
+    /// Records an input read in the latest entry.
+    pub(crate) fn record_read(&mut self, byte: Option<u8>) {
+        if let Some(entry) = self.entries.back_mut() {
+            entry.read = Some(byte);
+        }
+    }
+
+    /// Removes the latest entry, so that it can be undone.
+    ///
+    /// Its input is kept, so that it can be replayed with [`History::replay`].
+    #[must_use]
+    pub(crate) fn pop(&mut self) -> Option<UndoEntry> {
+        let entry = self.entries.pop_back()?;
+        if let Some(byte) = entry.read {
+            self.replay.push(byte);
+        }
+        Some(entry)
+    }
+
+    /// Returns the next input which was taken back, if any.
+    #[must_use]
+    pub(crate) fn replay(&mut self) -> Option<Option<u8>> {
+        self.replay.pop()
+    }
+
+    /// Forgets all entries and input to replay, for when the state changes outside of normal execution.
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.replay.clear();
+    }
+}
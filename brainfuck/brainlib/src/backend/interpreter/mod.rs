@@ -3,9 +3,11 @@
 //! You should use the generic [`Engine`] type, or the specialized [`StdEngine`] and [`ByteEngine`] types.
 //!
 //! Available engines:
-//! - [`Engine`] - reads inputs from any [`Read`](std::io::Read) type and outputs to any [`Write`](std::io::Write) type
+//! - [`Engine`] - reads inputs and writes outputs using any [`BrainIo`] type
 //! - [`StdEngine`] - reads inputs from [`Stdin`](std::io::Stdin) and outputs to [`Stdout`](std::io::Stdout)
 //! - [`ByteEngine`] - reads inputs from `&[u8]` and outputs to `&mut Vec<u8>`
+//! - [`FastEngine`] - runs the same programs faster, but can't be observed or stepped through
+//! - [`AsyncEngine`] - awaits inputs and outputs using any [`AsyncBrainIo`] type, yielding every few instructions
 //!
 //! # Examples
 //! ```
@@ -24,10 +26,28 @@
 //! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
 
+mod async_engine;
+mod brain_io;
+mod coverage;
 mod engine;
+mod engine_state;
+mod fast_engine;
+mod history;
+mod observer;
+mod profile;
 mod runtime_error;
+mod tape;
+mod trace;
 
 pub use self::{
+    async_engine::{AsyncBrainIo, AsyncEngine, SyncIo},
+    brain_io::{BrainIo, ByteIo, ChannelIo, FnIo, ReadWriteIo, StdIo},
+    coverage::{Coverage, LoopCoverage},
     engine::{ByteEngine, Engine, StdEngine},
-    runtime_error::RuntimeError,
+    engine_state::{EngineState, StateError},
+    fast_engine::FastEngine,
+    observer::{EngineObserver, EngineView},
+    profile::{LoopProfile, Profile},
+    runtime_error::{IoError, RuntimeError},
+    trace::{TraceFormat, TraceRecord, Tracer},
 };
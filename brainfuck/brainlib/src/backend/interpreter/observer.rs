@@ -0,0 +1,262 @@
+use crate::{interpreter::RuntimeError, Instruction, Settings, TapeMode};
+
+/// Callbacks invoked by an [`Engine`](crate::interpreter::Engine) while it executes a program.
+///
+/// All methods do nothing by default, so only the interesting ones have to be implemented.
+/// The engine is generic over its observer, so the default `()` observer has no runtime cost.
+///
+/// It is set with [`Engine::with_observer`](crate::interpreter::Engine::with_observer)
+/// and read back with [`Engine::observer`](crate::interpreter::Engine::observer).
+/// A pair of observers can be used to combine them, the first one is notified first.
+///
+/// # Examples
+/// ```
+/// # use brainlib::{interpreter::{Engine, EngineObserver}, Program};
+/// #[derive(Default)]
+/// struct Printed(Vec<u8>);
+///
+/// impl EngineObserver for Printed {
+///     fn on_output(&mut self, byte: u8) {
+///         self.0.push(byte);
+///     }
+/// }
+///
+/// let program = Program::compile("++++++++[>++++++++<-]>+.+.", &Default::default())?;
+/// let mut output = Vec::new();
+/// let mut engine =
+///     Engine::new_byte_default(program, &[], &mut output).with_observer(Printed::default());
+/// engine.run()?;
+///
+/// assert_eq!(engine.observer().0, b"AB");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[allow(unused_variables)]
+pub trait EngineObserver {
+    /// Called before the instruction at `pc` is executed.
+    fn before_instruction(&mut self, pc: usize, instruction: Instruction, view: EngineView<'_>) {}
+
+    /// Called after the instruction at `pc` was executed successfully, `view` contains the next program counter.
+    fn after_instruction(&mut self, pc: usize, instruction: Instruction, view: EngineView<'_>) {}
+
+    /// Called when a cell at the given position changes its value.
+    fn on_write(&mut self, position: i32, from: u32, to: u32) {}
+
+    /// Called when a byte is read from the input, `None` at the end of input.
+    fn on_input(&mut self, byte: Option<u8>) {}
+
+    /// Called when a byte is written to the output.
+    fn on_output(&mut self, byte: u8) {}
+
+    /// Called when a [`Breakpoint`](Instruction::Breakpoint) at the given source position is reached.
+    fn on_breakpoint(&mut self, pc: usize, position: u32) {}
+
+    /// Called instead of [`EngineObserver::after_instruction`] when the instruction at `pc` fails.
+    fn on_error(&mut self, pc: usize, error: &RuntimeError) {}
+
+    /// Called by the run methods before each instruction and before halting,
+    /// `true` stops them with [`RuntimeError::Paused`].
+    ///
+    /// This lets observers such as debuggers interrupt the execution, which can be resumed afterwards.
+    fn should_pause(&mut self, view: EngineView<'_>) -> bool {
+        false
+    }
+}
+
+impl EngineObserver for () {}
+
+impl<A: EngineObserver, B: EngineObserver> EngineObserver for (A, B) {
+    fn before_instruction(&mut self, pc: usize, instruction: Instruction, view: EngineView<'_>) {
+        self.0.before_instruction(pc, instruction, view);
+        self.1.before_instruction(pc, instruction, view);
+    }
+
+    fn after_instruction(&mut self, pc: usize, instruction: Instruction, view: EngineView<'_>) {
+        self.0.after_instruction(pc, instruction, view);
+        self.1.after_instruction(pc, instruction, view);
+    }
+
+    fn on_write(&mut self, position: i32, from: u32, to: u32) {
+        self.0.on_write(position, from, to);
+        self.1.on_write(position, from, to);
+    }
+
+    fn on_input(&mut self, byte: Option<u8>) {
+        self.0.on_input(byte);
+        self.1.on_input(byte);
+    }
+
+    fn on_output(&mut self, byte: u8) {
+        self.0.on_output(byte);
+        self.1.on_output(byte);
+    }
+
+    fn on_breakpoint(&mut self, pc: usize, position: u32) {
+        self.0.on_breakpoint(pc, position);
+        self.1.on_breakpoint(pc, position);
+    }
+
+    fn on_error(&mut self, pc: usize, error: &RuntimeError) {
+        self.0.on_error(pc, error);
+        self.1.on_error(pc, error);
+    }
+
+    fn should_pause(&mut self, view: EngineView<'_>) -> bool {
+        // Both observers are asked, so that neither misses the instruction.
+        self.0.should_pause(view) | self.1.should_pause(view)
+    }
+}
+
+/// A read-only view of the execution state, passed to an [`EngineObserver`].
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EngineView<'e> {
+    pub(crate) pc: usize,
+    pub(crate) pointer: u32,
+    pub(crate) origin: u32,
+    pub(crate) memory: &'e [u32],
+    pub(crate) settings: &'e Settings,
+}
+
+impl EngineView<'_> {
+    /// Returns the program counter, see [`Engine::pc`](crate::interpreter::Engine::pc).
+    #[must_use]
+    pub const fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// Returns the pointer position, see [`Engine::position`](crate::interpreter::Engine::position).
+    #[must_use]
+    pub const fn position(&self) -> i32 {
+        self.pointer as i32 - self.origin as i32
+    }
+
+    /// Returns the value of a cell, see [`Engine::cell`](crate::interpreter::Engine::cell).
+    #[must_use]
+    pub fn cell(&self, position: i32) -> Option<u32> {
+        let index = i64::from(position) + i64::from(self.origin);
+        let value = usize::try_from(index)
+            .ok()
+            .and_then(|i| self.memory.get(i).copied());
+        match self.settings.tape_mode() {
+            TapeMode::Fixed => value,
+            TapeMode::GrowRight if position < 0 => None,
+            TapeMode::GrowRight | TapeMode::GrowBoth => Some(value.unwrap_or(0)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EngineObserver, EngineView};
+    use crate::{
+        interpreter::{Engine, RuntimeError},
+        Instruction, Program, Settings,
+    };
+
+    #[derive(Default)]
+    struct Events(Vec<String>);
+
+    impl EngineObserver for Events {
+        fn before_instruction(&mut self, pc: usize, _: Instruction, view: EngineView<'_>) {
+            self.0.push(format!("before {pc} at {}", view.position()));
+        }
+
+        fn after_instruction(&mut self, pc: usize, _: Instruction, view: EngineView<'_>) {
+            self.0.push(format!("after {pc} to {}", view.pc()));
+        }
+
+        fn on_write(&mut self, position: i32, from: u32, to: u32) {
+            self.0.push(format!("write {position} {from}->{to}"));
+        }
+
+        fn on_input(&mut self, byte: Option<u8>) {
+            self.0.push(format!("input {byte:?}"));
+        }
+
+        fn on_output(&mut self, byte: u8) {
+            self.0.push(format!("output {byte}"));
+        }
+
+        fn on_breakpoint(&mut self, pc: usize, position: u32) {
+            self.0.push(format!("breakpoint {pc} at {position}"));
+        }
+
+        fn on_error(&mut self, pc: usize, error: &RuntimeError) {
+            self.0.push(format!("error {pc} {error}"));
+        }
+    }
+
+    fn events(source: &str, settings: Settings) -> Vec<String> {
+        let program = Program::compile(source, &settings).unwrap();
+        let mut output = Vec::new();
+        let mut eng =
+            Engine::new_byte(program, settings, b"A", &mut output).with_observer(Events::default());
+        let _ = eng.run();
+        eng.observer().0.clone()
+    }
+
+    #[test]
+    fn notifies_about_each_event() {
+        assert_eq!(
+            events(",.#>", Settings::new().with_debug()),
+            [
+                "before 0 at 0",
+                "input Some(65)",
+                "write 0 0->65",
+                "after 0 to 1",
+                "before 1 at 0",
+                "output 65",
+                "after 1 to 2",
+                "before 2 at 0",
+                "after 2 to 3",
+                "breakpoint 2 at 2",
+                "before 3 at 0",
+                "after 3 to 4",
+            ]
+        );
+    }
+
+    #[test]
+    fn notifies_about_errors() {
+        assert_eq!(
+            events("<", Settings::new().with_strict()),
+            [
+                "before 0 at 0",
+                "error 0 tape overflow when changing 0 by -1"
+            ]
+        );
+    }
+
+    #[test]
+    fn notifies_both_observers_of_a_pair() {
+        let program = Program::compile("+.", &Settings::default()).unwrap();
+        let mut output = Vec::new();
+        let mut eng = Engine::new_byte_default(program, &[], &mut output)
+            .with_observer((Events::default(), Events::default()));
+        eng.run().unwrap();
+        let (first, second) = eng.observer();
+        assert_eq!(first.0, second.0);
+        assert_eq!(first.0.len(), 6);
+    }
+
+    struct PauseAt(usize);
+
+    impl EngineObserver for PauseAt {
+        fn should_pause(&mut self, view: EngineView<'_>) -> bool {
+            view.pc() == self.0
+        }
+    }
+
+    #[test]
+    fn pauses_and_resumes_when_requested() {
+        let program = Program::compile("+.+.", &Settings::default()).unwrap();
+        let mut output = Vec::new();
+        let mut eng = Engine::new_byte_default(program, &[], &mut output).with_observer(PauseAt(2));
+        assert!(matches!(eng.run(), Err(RuntimeError::Paused)));
+        assert_eq!(eng.pc(), 2);
+        eng.observer_mut().0 = usize::MAX;
+        eng.run().unwrap();
+        drop(eng);
+        assert_eq!(output, [1, 2]);
+    }
+}
@@ -0,0 +1,202 @@
+use std::io::{self, Write};
+
+use crate::{
+    interpreter::{EngineObserver, EngineView},
+    Instruction, Program,
+};
+
+/// An [`EngineObserver`] which counts the executions of each instruction.
+///
+/// It is enabled with [`Engine::with_profiling`](crate::interpreter::Engine::with_profiling)
+/// and read with [`Engine::observer`](crate::interpreter::Engine::observer).
+///
+/// # Examples
+/// ```
+/// # use brainlib::{interpreter::Engine, Program, Settings};
+/// let program = Program::compile("++[>+++[-]<-]", &Settings::new().with_debug())?;
+/// let mut engine = Engine::new_std_default(program).with_profiling();
+/// engine.run()?;
+///
+/// let profile = engine.observer();
+/// let loops = profile.loops();
+/// assert_eq!(loops[0].iterations(), 2);
+/// assert!(loops[0].steps() > loops[1].steps());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    counts: Vec<u64>,
+    /// Indices of matching [`JumpRightZ`](crate::Instruction::JumpRightZ) and
+    /// [`JumpLeftNz`](crate::Instruction::JumpLeftNz) pairs, in program order.
+    loops: Vec<(usize, usize)>,
+}
+
+/// Execution counts of a single loop, see [`Profile::loops`].
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LoopProfile {
+    start: usize,
+    end: usize,
+    entries: u64,
+    iterations: u64,
+    steps: u64,
+}
+
+impl Profile {
+    /// Creates an empty profile of the [`Program`].
+    pub fn new(program: &Program) -> Self {
+        Self {
+            counts: vec![0; program.len()],
+            loops: program.loops().collect(),
+        }
+    }
+
+    /// Returns how many times each instruction was executed, indexed by the program counter.
+    #[must_use]
+    pub fn counts(&self) -> &[u64] {
+        &self.counts
+    }
+
+    /// Returns the total number of executed instructions.
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Returns the execution counts of every loop, in program order.
+    #[must_use]
+    pub fn loops(&self) -> Vec<LoopProfile> {
+        (self.loops.iter())
+            .map(|&(start, end)| LoopProfile {
+                start,
+                end,
+                entries: self.counts[start],
+                iterations: self.counts[end],
+                steps: self.counts[start..=end].iter().sum(),
+            })
+            .collect()
+    }
+
+    /// Writes the profile in the folded stacks format, used by flamegraph tools[^1].
+    ///
+    /// Each line contains a stack of nested loops, with their names separated by semicolons,
+    /// followed by the number of instructions executed directly in the innermost loop.
+    /// The outermost frame is called `main`, loops are named using the `frame` function.
+    ///
+    /// [^1]: [FlameGraph](https://github.com/brendangregg/FlameGraph)
+    pub fn write_folded(
+        &self,
+        mut write: impl Write,
+        frame: impl Fn(&LoopProfile) -> String,
+    ) -> io::Result<()> {
+        let loops = self.loops();
+        let mut stack: Vec<&LoopProfile> = Vec::new();
+        let mut next_loop = loops.iter().peekable();
+        let mut lines: Vec<(String, u64)> = Vec::new();
+
+        for (pc, &count) in self.counts.iter().enumerate() {
+            while stack.last().is_some_and(|l| l.end < pc) {
+                stack.pop();
+            }
+            if let Some(entered) = next_loop.next_if(|l| l.start == pc) {
+                stack.push(entered);
+            }
+            if count == 0 {
+                continue;
+            }
+
+            let name = (["main".to_owned()].into_iter())
+                .chain(stack.iter().map(|l| frame(l)))
+                .collect::<Vec<_>>()
+                .join(";");
+            match lines.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, total)) => *total += count,
+                None => lines.push((name, count)),
+            }
+        }
+
+        for (name, count) in lines {
+            writeln!(write, "{name} {count}")?;
+        }
+        Ok(())
+    }
+}
+
+impl EngineObserver for Profile {
+    fn before_instruction(&mut self, pc: usize, _: Instruction, _: EngineView<'_>) {
+        self.counts[pc] += 1;
+    }
+}
+
+impl LoopProfile {
+    /// Returns the index of the loop's [`JumpRightZ`](crate::Instruction::JumpRightZ).
+    #[must_use]
+    pub const fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Returns the index of the loop's [`JumpLeftNz`](crate::Instruction::JumpLeftNz).
+    #[must_use]
+    pub const fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Returns how many times the loop was reached, including when it was skipped.
+    #[must_use]
+    pub const fn entries(&self) -> u64 {
+        self.entries
+    }
+
+    /// Returns how many times the body of the loop was executed.
+    #[must_use]
+    pub const fn iterations(&self) -> u64 {
+        self.iterations
+    }
+
+    /// Returns the number of executed instructions of the loop, including nested loops.
+    #[must_use]
+    pub const fn steps(&self) -> u64 {
+        self.steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Profile;
+    use crate::{interpreter::Engine, Program, Settings};
+
+    fn profile(source: &str) -> Profile {
+        let program = Program::compile(source, &Settings::new().with_debug()).unwrap();
+        let mut eng = Engine::new_std_default(program).with_profiling();
+        eng.run().unwrap();
+        eng.observer().clone()
+    }
+
+    #[test]
+    fn counts_loop_iterations() {
+        let loops = profile("+++[-]").loops();
+        assert_eq!(loops.len(), 1);
+        assert_eq!((loops[0].entries(), loops[0].iterations()), (1, 3));
+        assert_eq!(loops[0].steps(), 1 + 3 + 3);
+    }
+
+    #[test]
+    fn counts_skipped_loops() {
+        let loops = profile("[+]").loops();
+        assert_eq!((loops[0].entries(), loops[0].iterations()), (1, 0));
+        assert_eq!(loops[0].steps(), 1);
+    }
+
+    #[test]
+    fn writes_folded_stacks() {
+        let mut folded = Vec::new();
+        profile("++[>+[-]<-]>.")
+            .write_folded(&mut folded, |l| format!("loop{}", l.start()))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(folded).unwrap(),
+            "main 4\nmain;loop2 11\nmain;loop2;loop5 6\n"
+        );
+    }
+}
@@ -1,34 +1,136 @@
+use std::{
+    error::Error as StdError,
+    fmt::{self, Display},
+    hash::{Hash, Hasher},
+    io,
+    sync::Arc,
+};
+
 use thiserror::Error;
 
 /// Error which occurs during the interpretation of the code.
 #[must_use]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Error)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Error)]
 pub enum RuntimeError {
-    /// The input could not be read from for an unknown reason.
+    /// The input could not be read from, see [`BrainIo::read_byte`](crate::interpreter::BrainIo::read_byte).
     #[error("input error")]
-    InputError,
+    InputError(#[source] IoError),
 
-    /// The output could not be written to for an unknown reason.
+    /// The output could not be written to, see [`BrainIo::write_byte`](crate::interpreter::BrainIo::write_byte).
     #[error("output error")]
-    OutputError,
+    OutputError(#[source] IoError),
 
-    /// The tape pointer overflowed in `strict` mode.
+    /// The tape pointer overflowed in `strict` mode, moved left of a
+    /// [`TapeMode::GrowRight`](crate::TapeMode::GrowRight) tape,
+    /// or a growable tape could not grow any further.
     #[error("tape overflow when changing {from} by {by}")]
     TapeOverflow {
-        /// The position of the pointer before the overflow.
-        from: u32,
+        /// The position of the pointer before the overflow, negative if left of the initial first cell.
+        from: i32,
         /// The amount by which the pointer was supposed to be changed.
         by: i32,
     },
 
+    /// The instruction budget passed to [`Engine::run_with_fuel`](crate::interpreter::Engine::run_with_fuel)
+    /// ran out before the program halted.
+    ///
+    /// The engine is left in a consistent state, so the execution can be resumed.
+    #[error("out of fuel")]
+    OutOfFuel,
+
+    /// The deadline passed to [`Engine::run_until`](crate::interpreter::Engine::run_until)
+    /// passed before the program halted.
+    ///
+    /// The engine is left in a consistent state, so the execution can be resumed.
+    #[error("deadline exceeded")]
+    DeadlineExceeded,
+
+    /// The [`EngineObserver`](crate::interpreter::EngineObserver) requested a pause
+    /// before the next instruction, see [`EngineObserver::should_pause`](crate::interpreter::EngineObserver::should_pause).
+    ///
+    /// The engine is left in a consistent state, so the execution can be resumed.
+    #[error("paused")]
+    Paused,
+
     /// A cell overflowed in `strict` mode.
     #[error("cell overflow when changing {from} by {by} at {at}")]
     CellOverflow {
-        /// The index of the changed cell.
-        at: u32,
+        /// The index of the changed cell, negative if left of the initial first cell.
+        at: i32,
         /// The value of the cell before the overflow.
-        from: u8,
+        from: u32,
         /// The amount by which the cell was supposed to be changed.
-        by: i8,
+        by: i32,
     },
 }
+
+impl RuntimeError {
+    /// Wraps an error returned by [`BrainIo::read_byte`](crate::interpreter::BrainIo::read_byte).
+    ///
+    /// It is only built out of line, so that the interpreter loops stay free of it.
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn input(err: io::Error) -> Self {
+        Self::InputError(err.into())
+    }
+
+    /// Wraps an error returned while writing or flushing the output, see [`RuntimeError::input`].
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn output(err: io::Error) -> Self {
+        Self::OutputError(err.into())
+    }
+}
+
+/// An [`io::Error`] returned by a [`BrainIo`](crate::interpreter::BrainIo), stored inside a [`RuntimeError`].
+///
+/// It is shared, so that the [`RuntimeError`] can be cloned.
+/// Errors are compared and hashed by their [`io::ErrorKind`] only.
+#[derive(Debug, Clone)]
+pub struct IoError(Arc<io::Error>);
+
+impl IoError {
+    /// Returns the kind of the underlying error.
+    #[must_use]
+    pub fn kind(&self) -> io::ErrorKind {
+        self.0.kind()
+    }
+
+    /// Returns the underlying error.
+    #[must_use]
+    pub fn get_ref(&self) -> &io::Error {
+        &self.0
+    }
+}
+
+impl From<io::Error> for IoError {
+    fn from(err: io::Error) -> Self {
+        Self(Arc::new(err))
+    }
+}
+
+impl PartialEq for IoError {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind() == other.kind()
+    }
+}
+
+impl Eq for IoError {}
+
+impl Hash for IoError {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.kind().hash(state);
+    }
+}
+
+impl Display for IoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl StdError for IoError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.0.source()
+    }
+}
@@ -0,0 +1,98 @@
+use std::iter;
+
+use crate::{interpreter::RuntimeError, Settings, TapeMode};
+
+/// The memory of an engine borrowed together with its pointer, which moves according to the [`Settings`].
+///
+/// It is shared by [`Engine`](crate::interpreter::Engine) and [`FastEngine`](crate::interpreter::FastEngine),
+/// so that both handle the edges of the tape in the same way.
+pub(super) struct Tape<'t> {
+    pub(super) memory: &'t mut Vec<u32>,
+    pub(super) pointer: &'t mut u32,
+    pub(super) origin: &'t mut u32,
+    pub(super) settings: &'t Settings,
+}
+
+impl Tape<'_> {
+    /// Returns the index of the cell `offset` cells away from the pointer, growing the tape if needed.
+    pub(super) fn cell_at(&mut self, offset: i32) -> Result<u32, RuntimeError> {
+        let from = *self.pointer as i32 - *self.origin as i32;
+        let overflow = || RuntimeError::TapeOverflow { from, by: offset };
+        let index = i64::from(*self.pointer) + i64::from(offset);
+        match self.settings.tape_mode() {
+            TapeMode::Fixed => self
+                .settings
+                .mut_pointer(*self.pointer, offset)
+                .ok_or_else(overflow),
+            TapeMode::GrowRight if index < 0 => Err(overflow()),
+            TapeMode::GrowRight | TapeMode::GrowBoth => self.grow_to(index).ok_or_else(overflow),
+        }
+    }
+
+    /// Grows the tape so that it contains the cell at the given index, and returns its index after growing.
+    ///
+    /// The tape at least doubles on each growth, so that growing is amortized.
+    #[must_use]
+    fn grow_to(&mut self, index: i64) -> Option<u32> {
+        let length = self.memory.len() as i64;
+        let room = i64::from(Settings::MAX_LENGTH) - length;
+        if index < 0 {
+            if -index > room {
+                return None;
+            }
+            let added = (-index).max(length.min(room));
+            self.memory.splice(0..0, iter::repeat_n(0, added as usize));
+            *self.origin += added as u32;
+            *self.pointer += added as u32;
+            Some((index + added) as u32)
+        } else {
+            if index >= length {
+                if index >= length + room {
+                    return None;
+                }
+                let grown = (index + 1).max(length + length.min(room));
+                self.memory.resize(grown as usize, 0);
+            }
+            Some(index as u32)
+        }
+    }
+
+    /// Moves the pointer by `by` cells at a time until it points to a zero cell.
+    ///
+    /// Returns `false` if no zero cell can be reached, the pointer is then left where the scan started.
+    pub(super) fn scan(&mut self, by: i32) -> Result<bool, RuntimeError> {
+        let stride = by.unsigned_abs() as usize;
+        let mut from = *self.pointer as usize;
+        let mut visited = 0;
+
+        loop {
+            let is_zero = |cell: &u32| *cell == 0;
+            let (found, last) = if by > 0 {
+                let found = self.memory[from..].iter().step_by(stride).position(is_zero);
+                let last = from + (self.memory.len() - 1 - from) / stride * stride;
+                (found.map(|steps| from + steps * stride), last)
+            } else {
+                let found = self.memory[..=from]
+                    .iter()
+                    .rev()
+                    .step_by(stride)
+                    .position(is_zero);
+                (found.map(|steps| from - steps * stride), from % stride)
+            };
+
+            if let Some(found) = found {
+                *self.pointer = found as u32;
+                return Ok(true);
+            }
+
+            visited += from.abs_diff(last) / stride + 1;
+            *self.pointer = last as u32;
+            from = self.cell_at(by)? as usize;
+
+            if self.settings.tape_mode() == TapeMode::Fixed && visited >= self.memory.len() {
+                *self.pointer = from as u32;
+                return Ok(false);
+            }
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use std::io::{self, Write};
+
+use crate::{
+    interpreter::{engine_state::write_leb128, EngineObserver, EngineView, RuntimeError},
+    Instruction,
+};
+
+/// An [`EngineObserver`] which writes a [`TraceRecord`] for every executed instruction.
+///
+/// It is enabled with [`Engine::with_tracing`](crate::interpreter::Engine::with_tracing).
+/// Instructions which fail are traced as well, so that the trace shows the cause of an error.
+/// Writing stops at the first I/O error, which is returned by [`Tracer::flush`].
+///
+/// # Examples
+/// ```
+/// # use brainlib::{interpreter::{Engine, TraceFormat}, Program, Settings};
+/// let program = Program::compile(">+", &Settings::new().with_debug())?;
+/// let mut engine = Engine::new_std_default(program).with_tracing(Vec::new(), TraceFormat::JsonLines);
+/// engine.run()?;
+/// engine.observer_mut().flush()?;
+///
+/// let trace = String::from_utf8(engine.observer().get_ref().clone())?;
+/// assert_eq!(
+///     trace.lines().last(),
+///     Some(r#"{"pc":1,"instruction":"+","pointer":1,"cell":1,"before":0,"after":1,"input":null,"output":null}"#)
+/// );
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct Tracer<W: Write> {
+    write: W,
+    format: TraceFormat,
+    /// The record of the instruction being executed.
+    record: Option<TraceRecord>,
+    error: Option<io::Error>,
+}
+
+/// A record of a single instruction executed by an [`Engine`](crate::interpreter::Engine), see [`Tracer`].
+///
+/// Positions are logical cell numbers, as returned by [`Engine::position`](crate::interpreter::Engine::position).
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TraceRecord {
+    pub(crate) pc: usize,
+    pub(crate) instruction: Instruction,
+    pub(crate) pointer: i32,
+    pub(crate) cell: i32,
+    pub(crate) before: u32,
+    pub(crate) after: u32,
+    pub(crate) input: Option<u8>,
+    pub(crate) output: Option<u8>,
+}
+
+/// The format in which a [`TraceRecord`] is written, see [`TraceRecord::write_to`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TraceFormat {
+    /// One JSON object per line[^1], with the fields named after the [`TraceRecord`] getters.
+    ///
+    /// Fields without a value are `null`.
+    /// Instructions without a source representation are written using their [`Debug`] form.
+    ///
+    /// [^1]: [JSON Lines](https://jsonlines.org)
+    #[default]
+    JsonLines,
+
+    /// The same fields as [`TraceFormat::JsonLines`], stored as LEB128 numbers.
+    ///
+    /// The instruction is not stored, since it can be read from the program using the program counter.
+    /// The positions are zigzag encoded, and the input and output bytes are preceded
+    /// by a byte with a bit set for each present value (`1` for input, `2` for output).
+    Binary,
+}
+
+impl<W: Write> Tracer<W> {
+    /// Creates a [`Tracer`] which writes the records to `write` using the given [`TraceFormat`].
+    pub const fn new(write: W, format: TraceFormat) -> Self {
+        Self {
+            write,
+            format,
+            record: None,
+            error: None,
+        }
+    }
+
+    /// Returns the record of the last executed instruction.
+    #[must_use]
+    pub const fn last(&self) -> Option<&TraceRecord> {
+        self.record.as_ref()
+    }
+
+    /// Returns the underlying writer.
+    pub const fn get_ref(&self) -> &W {
+        &self.write
+    }
+
+    /// Flushes the underlying writer, or returns the error which stopped the writing.
+    pub fn flush(&mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => self.write.flush(),
+        }
+    }
+
+    fn write_record(&mut self) {
+        if let (Some(record), None) = (&self.record, &self.error) {
+            self.error = record.write_to(&mut self.write, self.format).err();
+        }
+    }
+}
+
+impl<W: Write> EngineObserver for Tracer<W> {
+    fn before_instruction(&mut self, pc: usize, instruction: Instruction, view: EngineView<'_>) {
+        let value = view.cell(view.position()).unwrap_or(0);
+        self.record = Some(TraceRecord {
+            pc,
+            instruction,
+            pointer: view.position(),
+            cell: view.position(),
+            before: value,
+            after: value,
+            input: None,
+            output: None,
+        });
+    }
+
+    fn after_instruction(&mut self, _: usize, _: Instruction, _: EngineView<'_>) {
+        self.write_record();
+    }
+
+    fn on_write(&mut self, position: i32, from: u32, to: u32) {
+        if let Some(record) = &mut self.record {
+            record.cell = position;
+            record.before = from;
+            record.after = to;
+        }
+    }
+
+    fn on_input(&mut self, byte: Option<u8>) {
+        if let Some(record) = &mut self.record {
+            record.input = byte;
+        }
+    }
+
+    fn on_output(&mut self, byte: u8) {
+        if let Some(record) = &mut self.record {
+            record.output = Some(byte);
+        }
+    }
+
+    fn on_error(&mut self, _: usize, _: &RuntimeError) {
+        self.write_record();
+    }
+}
+
+impl TraceRecord {
+    /// Returns the index of the executed instruction.
+    #[must_use]
+    pub const fn pc(&self) -> usize {
+        self.pc
+    }
+
+    /// Returns the executed instruction.
+    pub const fn instruction(&self) -> Instruction {
+        self.instruction
+    }
+
+    /// Returns the position of the pointer before the instruction was executed.
+    #[must_use]
+    pub const fn pointer(&self) -> i32 {
+        self.pointer
+    }
+
+    /// Returns the position of the cell written by the instruction, or of the pointer if no cell was written.
+    #[must_use]
+    pub const fn cell(&self) -> i32 {
+        self.cell
+    }
+
+    /// Returns the value of the [`TraceRecord::cell`] before the instruction was executed.
+    #[must_use]
+    pub const fn before(&self) -> u32 {
+        self.before
+    }
+
+    /// Returns the value of the [`TraceRecord::cell`] after the instruction was executed.
+    #[must_use]
+    pub const fn after(&self) -> u32 {
+        self.after
+    }
+
+    /// Returns the byte read by the instruction, `None` if nothing or the end of input was read.
+    #[must_use]
+    pub const fn input(&self) -> Option<u8> {
+        self.input
+    }
+
+    /// Returns the byte written by the instruction, if any.
+    #[must_use]
+    pub const fn output(&self) -> Option<u8> {
+        self.output
+    }
+
+    /// Writes the record using the given [`TraceFormat`].
+    pub fn write_to(&self, mut write: impl Write, format: TraceFormat) -> io::Result<()> {
+        match format {
+            TraceFormat::JsonLines => {
+                let instruction = match self.instruction.to_string() {
+                    text if text == "¿" => format!("{:?}", self.instruction),
+                    text => text,
+                };
+                let byte = |byte: Option<u8>| byte.map_or("null".to_owned(), |b| b.to_string());
+                writeln!(
+                    write,
+                    r#"{{"pc":{},"instruction":"{}","pointer":{},"cell":{},"before":{},"after":{},"input":{},"output":{}}}"#,
+                    self.pc,
+                    instruction,
+                    self.pointer,
+                    self.cell,
+                    self.before,
+                    self.after,
+                    byte(self.input),
+                    byte(self.output),
+                )
+            },
+            TraceFormat::Binary => {
+                let zigzag = |value: i32| ((value << 1) ^ (value >> 31)) as u32;
+                let mut bytes = Vec::new();
+                write_leb128(&mut bytes, self.pc as u64);
+                write_leb128(&mut bytes, zigzag(self.pointer).into());
+                write_leb128(&mut bytes, zigzag(self.cell).into());
+                write_leb128(&mut bytes, self.before.into());
+                write_leb128(&mut bytes, self.after.into());
+                bytes.push(u8::from(self.input.is_some()) | u8::from(self.output.is_some()) << 1);
+                bytes.extend(self.input);
+                bytes.extend(self.output);
+                write.write_all(&bytes)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TraceFormat;
+    use crate::{interpreter::Engine, Program, Settings};
+
+    fn trace(source: &str, input: &str, format: TraceFormat) -> Vec<u8> {
+        let program = Program::compile(source, &Settings::new().with_debug()).unwrap();
+        let mut output = Vec::new();
+        let mut eng = Engine::new_byte_default(program, input.as_bytes(), &mut output)
+            .with_tracing(Vec::new(), format);
+        eng.run().unwrap();
+        eng.observer_mut().flush().unwrap();
+        eng.observer().get_ref().clone()
+    }
+
+    #[test]
+    fn traces_input_and_output() {
+        let trace = String::from_utf8(trace(",.", "A", TraceFormat::JsonLines)).unwrap();
+        assert_eq!(
+            trace,
+            concat!(
+                r#"{"pc":0,"instruction":",","pointer":0,"cell":0,"before":0,"after":65,"input":65,"output":null}"#,
+                "\n",
+                r#"{"pc":1,"instruction":".","pointer":0,"cell":0,"before":65,"after":65,"input":null,"output":65}"#,
+                "\n",
+            )
+        );
+    }
+
+    #[test]
+    fn traces_cells_written_at_offset() {
+        let program = Program::compile("+[->++<]", &Settings::default()).unwrap();
+        let mut eng =
+            Engine::new_std_default(program).with_tracing(Vec::new(), TraceFormat::Binary);
+        let _ = eng.step();
+        let _ = eng.step();
+        let record = eng.observer().last().copied().unwrap();
+        assert_eq!((record.pointer(), record.cell()), (0, 1));
+        assert_eq!((record.before(), record.after()), (0, 2));
+        assert!(format!("{:?}", record.instruction()).contains("MulAdd"));
+    }
+
+    #[test]
+    fn writes_binary_records() {
+        assert_eq!(
+            trace("<.", "", TraceFormat::Binary),
+            // `<` wraps around to cell 29999, which is zigzag encoded as 59998.
+            [0, 0, 0, 0, 0, 0, 1, 0xDE, 0xD4, 0x03, 0xDE, 0xD4, 0x03, 0, 0, 2, 0]
+        );
+    }
+
+    #[test]
+    fn traces_failed_instruction() {
+        let settings = Settings::new().with_strict();
+        let program = Program::compile("+<", &settings).unwrap();
+        let mut eng =
+            Engine::new_std(program, settings).with_tracing(Vec::new(), TraceFormat::JsonLines);
+        assert!(eng.run().is_err());
+        let trace = String::from_utf8(eng.observer().get_ref().clone()).unwrap();
+        assert_eq!(trace.lines().count(), 2);
+    }
+}
@@ -0,0 +1,250 @@
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+use super::x86_64::NativeCode;
+use crate::{
+    interpreter::{BrainIo, Engine, RuntimeError},
+    Program, Settings,
+};
+
+/// An engine which runs a [`Program`] as native machine code, falling back to the interpreter when needed.
+///
+/// The code is compiled once, when the [`JitEngine`] is created.
+/// It is specialized for the [`Settings`] and runs directly on the state of the wrapped [`Engine`].
+///
+/// The native code only handles the common cases: cells inside of the allocated tape and values which
+/// don't overflow in `strict` mode. Instructions which wrap the pointer around, grow the tape or fail
+/// are executed by the interpreter, so the results are the same as those of [`Engine`].
+///
+/// If no native code can be generated, because of the platform or a failure to allocate executable memory,
+/// the whole program is interpreted, see [`JitEngine::is_native`].
+///
+/// Input and output go through callbacks into Rust.
+/// A panic inside of the [`BrainIo`] can't unwind through the native code, so it aborts the process.
+///
+/// # Examples
+/// ```
+/// # use brainlib::{interpreter::ByteIo, jit::JitEngine, Program, Settings};
+/// let program = Program::compile("++++++++[>++++++++<-]>+.", &Settings::default())?;
+/// let mut output = Vec::new();
+/// let mut engine = JitEngine::new(program, Settings::default(), ByteIo::new(&[], &mut output));
+/// engine.run()?;
+///
+/// assert_eq!(engine.engine().memory()[..2], [0, 65]);
+/// # drop(engine);
+/// assert_eq!(output, b"A");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[must_use]
+#[derive(Debug)]
+pub struct JitEngine<Io: BrainIo> {
+    engine: Engine<Io>,
+    code: Option<NativeCode>,
+}
+
+/// The fields of an [`Engine`] which are read and written by the native code.
+pub(crate) struct NativeParts<'e, Io: BrainIo> {
+    pub(crate) pc: &'e mut usize,
+    pub(crate) pointer: &'e mut u32,
+    pub(crate) memory: &'e mut Vec<u32>,
+    pub(crate) input_position: &'e mut u64,
+    pub(crate) settings: &'e Settings,
+    pub(crate) io: &'e mut Io,
+}
+
+/// The reason for which the native code returned.
+#[cfg_attr(
+    not(all(target_arch = "x86_64", target_os = "linux")),
+    allow(dead_code)
+)]
+pub(super) enum Exit {
+    /// The program halted.
+    Halted,
+    /// The instruction at the program counter has to be executed by the interpreter.
+    Interpret,
+}
+
+impl<Io: BrainIo> JitEngine<Io> {
+    /// Creates a new [`JitEngine`] with the provided [`Program`], [`Settings`] and [`BrainIo`].
+    pub fn new(program: Program, settings: Settings, io: Io) -> Self {
+        Self::from(Engine::new(program, settings, io))
+    }
+
+    /// Returns the wrapped [`Engine`], which holds the execution state.
+    pub const fn engine(&self) -> &Engine<Io> {
+        &self.engine
+    }
+
+    /// Returns `true` if the [`Program`] was compiled to native code, `false` if it is interpreted.
+    #[must_use]
+    pub const fn is_native(&self) -> bool {
+        self.code.is_some()
+    }
+
+    /// Runs the [`JitEngine`] until it halts or a [`RuntimeError`] occurs, see [`Engine::run`].
+    pub fn run(&mut self) -> Result<(), RuntimeError> {
+        let Some(code) = &self.code else {
+            return self.engine.run();
+        };
+        let result = loop {
+            match code.run(&mut self.engine) {
+                Ok(Exit::Halted) => break Ok(()),
+                Ok(Exit::Interpret) =>
+                    if let Some(Err(err)) = self.engine.step() {
+                        break Err(err);
+                    },
+                Err(err) => break Err(err),
+            }
+        };
+        self.engine.stop(result)
+    }
+}
+
+impl<Io: BrainIo> From<Engine<Io>> for JitEngine<Io> {
+    /// Compiles the program of the [`Engine`], keeping its execution state but dropping its undo log.
+    fn from(engine: Engine<Io>) -> Self {
+        let (program, settings, io, state) = engine.into_parts();
+        let code = NativeCode::compile(&program, &settings).ok();
+        Self {
+            engine: Engine::from_parts(program, settings, io, state),
+            code,
+        }
+    }
+}
+
+impl<Io: BrainIo> From<JitEngine<Io>> for Engine<Io> {
+    /// Returns the wrapped [`Engine`], which continues the execution from where the [`JitEngine`] stopped.
+    fn from(engine: JitEngine<Io>) -> Self {
+        engine.engine
+    }
+}
+
+/// Native code can't be generated on this platform, so a [`JitEngine`] is always interpreted.
+#[cfg(not(all(target_arch = "x86_64", target_os = "linux")))]
+#[derive(Debug)]
+enum NativeCode {}
+
+#[cfg(not(all(target_arch = "x86_64", target_os = "linux")))]
+impl NativeCode {
+    fn compile(_: &Program, _: &Settings) -> std::io::Result<Self> {
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+
+    fn run<Io: BrainIo>(&self, _: &mut Engine<Io>) -> Result<Exit, RuntimeError> {
+        match *self {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use test_case::test_case;
+
+    use super::JitEngine;
+    use crate::{
+        interpreter::{BrainIo, ByteIo, Engine, EngineState, FnIo, RuntimeError},
+        CellWidth, EofBehavior, Program, Settings, TapeMode,
+    };
+
+    type Outcome = (Result<(), RuntimeError>, EngineState, Vec<u8>);
+
+    fn run_engine(source: &str, input: &str, settings: Settings) -> Outcome {
+        let program = Program::compile(source, &settings).unwrap();
+        let mut output = Vec::new();
+        let mut eng = Engine::new_byte(program, settings, input.as_bytes(), &mut output);
+        let result = eng.run();
+        let state = eng.snapshot();
+        drop(eng);
+        (result, state, output)
+    }
+
+    fn run_jit_engine(source: &str, input: &str, settings: Settings) -> Outcome {
+        let program = Program::compile(source, &settings).unwrap();
+        let mut output = Vec::new();
+        let mut eng = JitEngine::new(
+            program,
+            settings,
+            ByteIo::new(input.as_bytes(), &mut output),
+        );
+        let result = eng.run();
+        let state = Engine::from(eng).snapshot();
+        (result, state, output)
+    }
+
+    #[test_case("++++++++[>++++++++<-]>+.", ""; "hello")]
+    #[test_case(",[.,]", "Hello, world!"; "cat")]
+    #[test_case("<+>>-<[-<+>]", ""; "wrapping pointer")]
+    #[test_case("+[-<+>]<.", ""; "wrapping mul add")]
+    #[test_case("+++[->>+++<<]>>[-<+++++>]<.", ""; "mul add")]
+    #[test_case("+>+>+>>+<<<<[>]", ""; "scan right")]
+    #[test_case(">+>+<+[<<]", ""; "scan left with stride")]
+    #[test_case("->>>>>>+[-<<<<<<+>>>>>>]<<<<<<+.", ""; "overflow")]
+    fn matches_engine(source: &str, input: &str) {
+        for settings in [
+            Settings::try_new(5, false, false).unwrap(),
+            Settings::new().with_strict(),
+            Settings::new().with_tape_mode(TapeMode::GrowRight),
+            Settings::new().with_tape_mode(TapeMode::GrowBoth),
+            Settings::new().with_cell_width(CellWidth::U32),
+        ] {
+            assert_eq!(
+                run_jit_engine(source, input, settings),
+                run_engine(source, input, settings),
+            );
+        }
+    }
+
+    #[test_case(EofBehavior::Zero)]
+    #[test_case(EofBehavior::Unchanged)]
+    #[test_case(EofBehavior::Max)]
+    fn matches_engine_at_end_of_input(eof_behavior: EofBehavior) {
+        let settings = Settings::new().with_eof_behavior(eof_behavior);
+        assert_eq!(
+            run_jit_engine("-+[+]>,.+,.>,.", "A", settings),
+            run_engine("-+[+]>,.+,.>,.", "A", settings),
+        );
+    }
+
+    #[test]
+    fn compiles_to_native_code() {
+        let program = Program::compile("+[-]", &Settings::default()).unwrap();
+        let mut output = Vec::new();
+        let eng = JitEngine::new(program, Settings::default(), ByteIo::new(&[], &mut output));
+        assert_eq!(
+            eng.is_native(),
+            cfg!(all(target_arch = "x86_64", target_os = "linux"))
+        );
+    }
+
+    #[test_case(",+.", true, false, 1; "input")]
+    #[test_case("+.,", false, true, 2; "output")]
+    fn stops_at_failed_io(source: &str, fail_read: bool, fail_write: bool, pc: usize) {
+        fn failing_io(fail_read: bool, fail_write: bool) -> impl BrainIo {
+            FnIo::new(
+                move || match fail_read {
+                    true => Err(io::Error::other("read")),
+                    false => Ok(Some(b'A')),
+                },
+                move |_| match fail_write {
+                    true => Err(io::Error::other("write")),
+                    false => Ok(()),
+                },
+            )
+        }
+
+        let program = Program::compile(source, &Settings::default()).unwrap();
+        let mut eng = JitEngine::new(
+            program,
+            Settings::default(),
+            failing_io(fail_read, fail_write),
+        );
+        let result = eng.run();
+        assert_eq!(eng.engine().pc(), pc);
+        match (result, fail_read) {
+            (Err(RuntimeError::InputError(err)), true) =>
+                assert_eq!(err.get_ref().to_string(), "read"),
+            (Err(RuntimeError::OutputError(err)), false) =>
+                assert_eq!(err.get_ref().to_string(), "write"),
+            (result, _) => panic!("unexpected result: {result:?}"),
+        }
+    }
+}
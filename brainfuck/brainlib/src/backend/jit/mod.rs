@@ -0,0 +1,40 @@
+//! Functionalities related to running Brainfuck code as native x86-64 machine code.
+//!
+//! This module is only available with the `jit` feature enabled.
+//!
+//! The features are exposed through the [`JitEngine`] type, which compiles a [`Program`](crate::Program)
+//! into machine code placed in an executable memory mapping, and runs it on the state of an [`Engine`].
+//! Input and output go through callbacks into Rust, which use the [`BrainIo`] of the engine.
+//!
+//! Native code is only generated on x86-64 Linux, on other platforms the [`JitEngine`] falls back to the interpreter.
+//!
+//! # Examples
+//! ```
+//! use brainlib::{interpreter::Engine, jit::JitEngine, Program, Settings};
+//!
+//! let source = ",[.,]";
+//! let program = Program::compile(source, &Settings::default())?;
+//!
+//! let input = "ABCD";
+//! let mut output = Vec::new();
+//! let mut eng = JitEngine::from(Engine::new_byte_default(
+//!     program,
+//!     input.as_bytes(),
+//!     &mut output,
+//! ));
+//! eng.run()?;
+//! drop(eng);
+//!
+//! assert_eq!(output, input.as_bytes());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [`Engine`]: crate::interpreter::Engine
+//! [`BrainIo`]: crate::interpreter::BrainIo
+
+mod jit_engine;
+#[cfg(all(target_arch = "x86_64", target_os = "linux"))]
+mod x86_64;
+
+pub use self::jit_engine::JitEngine;
+pub(crate) use self::jit_engine::NativeParts;
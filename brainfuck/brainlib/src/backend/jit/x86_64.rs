@@ -0,0 +1,644 @@
+use std::{
+    ffi::c_void,
+    fmt::{self, Debug, Formatter},
+    io,
+    mem::{self, offset_of},
+    ptr::{self, NonNull},
+};
+
+use super::jit_engine::Exit;
+use crate::{
+    interpreter::{BrainIo, Engine, RuntimeError},
+    Instruction, Program, Settings, TapeMode,
+};
+
+/// Native code compiled for a [`Program`], stored in an executable memory mapping.
+///
+/// The code is a single function, called with a [`Context`] and the address at which to start the execution.
+/// It keeps the context in `r15`, the address of the first cell in `r14`, the number of cells in `r12`
+/// and the index of the current cell in `rbx`. The returned value is one of the `EXIT_` constants.
+pub(super) struct NativeCode {
+    mapping: NonNull<c_void>,
+    length: usize,
+    /// The offset of the code of each instruction, followed by the offset of the code which halts.
+    offsets: Vec<usize>,
+}
+
+// SAFETY: The mapping is never written after it was made executable, so it can be shared freely.
+unsafe impl Send for NativeCode {}
+// SAFETY: As above, the mapping is read-only.
+unsafe impl Sync for NativeCode {}
+
+/// The state shared between the native code and Rust, `r15` points to it.
+#[repr(C)]
+struct Context {
+    memory: *mut u32,
+    length: u64,
+    pointer: u64,
+    pc: u64,
+    read: extern "sysv64" fn(*mut Context, u32) -> u64,
+    write: extern "sysv64" fn(*mut Context, u32) -> u64,
+    callbacks: *mut c_void,
+}
+
+/// The state used by the I/O callbacks, [`Context::callbacks`] points to it.
+struct Callbacks<'e, Io: BrainIo> {
+    io: &'e mut Io,
+    input_position: &'e mut u64,
+    settings: &'e Settings,
+    error: Option<RuntimeError>,
+}
+
+const EXIT_HALTED: u64 = 0;
+const EXIT_INTERPRET: u64 = 1;
+const EXIT_IO_ERROR: u64 = 2;
+
+/// Returned by [`read_cell`] instead of the value of the cell when the input fails.
+const READ_FAILED: u64 = u64::MAX;
+
+type EntryFn = extern "sysv64" fn(*mut Context, *const u8) -> u64;
+
+impl NativeCode {
+    /// Compiles the [`Program`] for the given [`Settings`].
+    ///
+    /// An error is only returned if the executable memory could not be allocated.
+    pub(super) fn compile(program: &Program, settings: &Settings) -> io::Result<Self> {
+        let (code, offsets) = Compiler::new(settings).compile(program);
+        let length = code.len();
+
+        // SAFETY: A new private mapping is created, it doesn't alias any existing memory.
+        let mapping = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                length,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if mapping == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        let native = Self {
+            mapping: NonNull::new(mapping).expect("mmap returned a null pointer"),
+            length,
+            offsets,
+        };
+
+        // SAFETY: The mapping is writable and has room for the whole code.
+        unsafe { ptr::copy_nonoverlapping(code.as_ptr(), mapping.cast(), length) };
+        // SAFETY: The mapping was created above, it is made executable but no longer writable.
+        if unsafe { libc::mprotect(mapping, length, libc::PROT_READ | libc::PROT_EXEC) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(native)
+    }
+
+    /// Runs the native code on the state of the [`Engine`], starting at its program counter.
+    ///
+    /// The [`Engine`] must be executing the same [`Program`] with the same [`Settings`] as the compiled one.
+    pub(super) fn run<Io: BrainIo>(&self, engine: &mut Engine<Io>) -> Result<Exit, RuntimeError> {
+        let parts = engine.native_parts();
+        let Some(&offset) = self.offsets.get(*parts.pc) else {
+            return Ok(Exit::Halted);
+        };
+
+        let mut callbacks = Callbacks {
+            io: parts.io,
+            input_position: parts.input_position,
+            settings: parts.settings,
+            error: None,
+        };
+        let mut context = Context {
+            memory: parts.memory.as_mut_ptr(),
+            length: parts.memory.len() as u64,
+            pointer: u64::from(*parts.pointer),
+            pc: 0,
+            read: read_cell::<Io>,
+            write: write_cell::<Io>,
+            callbacks: ptr::from_mut(&mut callbacks).cast(),
+        };
+
+        // SAFETY: The mapping starts with the entry function, which only jumps to the start of an instruction.
+        // The instructions only access the cells below `length`, and the pointer is always one of them.
+        let exit = unsafe {
+            let entry: EntryFn = mem::transmute(self.mapping.as_ptr());
+            entry(&mut context, self.mapping.as_ptr().cast::<u8>().add(offset))
+        };
+
+        *parts.pointer = context.pointer as u32;
+        *parts.pc = context.pc as usize;
+        match exit {
+            EXIT_HALTED => Ok(Exit::Halted),
+            EXIT_INTERPRET => Ok(Exit::Interpret),
+            _ => Err(callbacks
+                .error
+                .expect("native code exited without an error")),
+        }
+    }
+}
+
+impl Drop for NativeCode {
+    fn drop(&mut self) {
+        // SAFETY: The mapping was created in `NativeCode::compile` and is no longer used.
+        unsafe { libc::munmap(self.mapping.as_ptr(), self.length) };
+    }
+}
+
+impl Debug for NativeCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NativeCode")
+            .field("length", &self.length)
+            .field("offsets", &self.offsets)
+            .finish()
+    }
+}
+
+/// Reads a byte of input, returns the new value of the cell equal to `cell` or [`READ_FAILED`].
+extern "sysv64" fn read_cell<Io: BrainIo>(context: *mut Context, cell: u32) -> u64 {
+    // SAFETY: The native code passes the context created in `NativeCode::run`, which outlives the call.
+    let callbacks = unsafe { &mut *(*context).callbacks.cast::<Callbacks<'_, Io>>() };
+    match callbacks.io.read_byte() {
+        Ok(Some(byte)) => {
+            *callbacks.input_position += 1;
+            byte.into()
+        },
+        Ok(None) => callbacks.settings.eof_value(cell).into(),
+        Err(err) => {
+            callbacks.error = Some(RuntimeError::input(err));
+            READ_FAILED
+        },
+    }
+}
+
+/// Writes the least significant byte of the cell, returns zero on success.
+extern "sysv64" fn write_cell<Io: BrainIo>(context: *mut Context, cell: u32) -> u64 {
+    // SAFETY: As in `read_cell`.
+    let callbacks = unsafe { &mut *(*context).callbacks.cast::<Callbacks<'_, Io>>() };
+    match callbacks.io.write_byte(cell as u8) {
+        Ok(()) => 0,
+        Err(err) => {
+            callbacks.error = Some(RuntimeError::output(err));
+            1
+        },
+    }
+}
+
+/// General purpose registers, numbered as in their encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reg {
+    Rax = 0,
+    Rcx = 1,
+    Rdx = 2,
+    Rbx = 3,
+    Rsp = 4,
+    Rbp = 5,
+    Rsi = 6,
+    Rdi = 7,
+    R12 = 12,
+    R13 = 13,
+    R14 = 14,
+    R15 = 15,
+}
+
+const CONTEXT: Reg = Reg::R15;
+const MEMORY: Reg = Reg::R14;
+const LENGTH: Reg = Reg::R12;
+const POINTER: Reg = Reg::Rbx;
+
+/// Registers preserved by the entry function, as required by the System V ABI.
+const SAVED: [Reg; 6] = [Reg::Rbx, Reg::Rbp, Reg::R12, Reg::R13, Reg::R14, Reg::R15];
+
+/// Condition codes of the conditional jumps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cond {
+    Equal = 0x4,
+    NotEqual = 0x5,
+    Below = 0x2,
+    AboveOrEqual = 0x3,
+    Above = 0x7,
+}
+
+/// A position in the code, which may not be known yet when it is jumped to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Label(usize);
+
+/// An assembler of the few x86-64 instructions used by the [`Compiler`].
+#[derive(Debug, Default)]
+struct Assembler {
+    code: Vec<u8>,
+    labels: Vec<Option<usize>>,
+    /// The positions of the 32-bit displacements of jumps, with the labels they jump to.
+    fixups: Vec<(usize, Label)>,
+}
+
+impl Assembler {
+    fn label(&mut self) -> Label {
+        self.labels.push(None);
+        Label(self.labels.len() - 1)
+    }
+
+    fn bind(&mut self, label: Label) {
+        self.labels[label.0] = Some(self.code.len());
+    }
+
+    fn emit(&mut self, bytes: &[u8]) {
+        self.code.extend_from_slice(bytes);
+    }
+
+    fn emit_u32(&mut self, value: u32) {
+        self.emit(&value.to_le_bytes());
+    }
+
+    /// Emits a REX prefix if any of its bits are set.
+    fn rex(&mut self, wide: bool, reg: u8, index: u8, base: u8) {
+        let rex = 0x40 | u8::from(wide) << 3 | (reg >> 3) << 2 | (index >> 3) << 1 | base >> 3;
+        if rex != 0x40 {
+            self.emit(&[rex]);
+        }
+    }
+
+    /// Emits an instruction with a register operand and a register or opcode extension `reg`.
+    fn op_reg(&mut self, wide: bool, opcode: &[u8], reg: u8, rm: Reg) {
+        self.rex(wide, reg, 0, rm as u8);
+        self.emit(opcode);
+        self.emit(&[0xC0 | (reg & 7) << 3 | (rm as u8 & 7)]);
+    }
+
+    /// Emits an instruction with a `[base + disp]` memory operand and a register or opcode extension `reg`.
+    fn op_mem(&mut self, wide: bool, opcode: &[u8], reg: u8, base: Reg, disp: usize) {
+        self.rex(wide, reg, 0, base as u8);
+        self.emit(opcode);
+        self.emit(&[0x80 | (reg & 7) << 3 | (base as u8 & 7)]);
+        if base as u8 & 7 == Reg::Rsp as u8 {
+            self.emit(&[0x24]);
+        }
+        self.emit_u32(disp as u32);
+    }
+
+    /// Emits an instruction with the cell `[MEMORY + index * 4]` as the operand and a register or opcode extension `reg`.
+    fn op_cell(&mut self, opcode: &[u8], reg: u8, index: Reg) {
+        self.rex(false, reg, index as u8, MEMORY as u8);
+        self.emit(opcode);
+        self.emit(&[0x80 | (reg & 7) << 3 | 0b100]);
+        self.emit(&[0b10 << 6 | (index as u8 & 7) << 3 | (MEMORY as u8 & 7)]);
+        self.emit_u32(0);
+    }
+
+    fn push(&mut self, reg: Reg) {
+        self.rex(false, 0, 0, reg as u8);
+        self.emit(&[0x50 | (reg as u8 & 7)]);
+    }
+
+    fn pop(&mut self, reg: Reg) {
+        self.rex(false, 0, 0, reg as u8);
+        self.emit(&[0x58 | (reg as u8 & 7)]);
+    }
+
+    /// `mov dst, src`
+    fn mov(&mut self, dst: Reg, src: Reg) {
+        self.op_reg(true, &[0x89], src as u8, dst);
+    }
+
+    /// `mov dst32, imm32`, which clears the upper half of the register.
+    fn mov_imm(&mut self, dst: Reg, imm: u32) {
+        self.rex(false, 0, 0, dst as u8);
+        self.emit(&[0xB8 | (dst as u8 & 7)]);
+        self.emit_u32(imm);
+    }
+
+    /// `mov dst, [base + disp]`
+    fn load(&mut self, dst: Reg, base: Reg, disp: usize) {
+        self.op_mem(true, &[0x8B], dst as u8, base, disp);
+    }
+
+    /// `mov [base + disp], src`
+    fn store(&mut self, base: Reg, disp: usize, src: Reg) {
+        self.op_mem(true, &[0x89], src as u8, base, disp);
+    }
+
+    /// `mov qword [base + disp], imm32`
+    fn store_imm(&mut self, base: Reg, disp: usize, imm: u32) {
+        self.op_mem(true, &[0xC7], 0, base, disp);
+        self.emit_u32(imm);
+    }
+
+    /// `lea dst, [base + disp]`
+    fn lea(&mut self, dst: Reg, base: Reg, disp: i32) {
+        self.op_mem(true, &[0x8D], dst as u8, base, disp as usize);
+    }
+
+    /// `mov dst32, [cell]`
+    fn load_cell(&mut self, dst: Reg, index: Reg) {
+        self.op_cell(&[0x8B], dst as u8, index);
+    }
+
+    /// `mov [cell], src32`
+    fn store_cell(&mut self, index: Reg, src: Reg) {
+        self.op_cell(&[0x89], src as u8, index);
+    }
+
+    /// `mov dword [cell], imm32`
+    fn store_cell_imm(&mut self, index: Reg, imm: u32) {
+        self.op_cell(&[0xC7], 0, index);
+        self.emit_u32(imm);
+    }
+
+    /// `cmp dword [cell], 0`
+    fn cmp_cell_zero(&mut self, index: Reg) {
+        self.op_cell(&[0x83], 7, index);
+        self.emit(&[0]);
+    }
+
+    /// `add dst, imm32`, with the immediate sign extended if `wide`.
+    fn add_imm(&mut self, wide: bool, dst: Reg, imm: i32) {
+        self.op_reg(wide, &[0x81], 0, dst);
+        self.emit_u32(imm as u32);
+    }
+
+    /// `and dst32, imm32`
+    fn and_imm(&mut self, dst: Reg, imm: u32) {
+        self.op_reg(false, &[0x81], 4, dst);
+        self.emit_u32(imm);
+    }
+
+    /// `add dst, src`, using the lower halves of the registers unless `wide`.
+    fn add(&mut self, wide: bool, dst: Reg, src: Reg) {
+        self.op_reg(wide, &[0x01], src as u8, dst);
+    }
+
+    /// `sub dst, src`
+    fn sub(&mut self, dst: Reg, src: Reg) {
+        self.op_reg(true, &[0x29], src as u8, dst);
+    }
+
+    /// `imul dst32, src32, imm32`
+    fn imul_imm(&mut self, dst: Reg, src: Reg, imm: i32) {
+        self.op_reg(false, &[0x69], dst as u8, src);
+        self.emit_u32(imm as u32);
+    }
+
+    /// `cmp left, right`
+    fn cmp(&mut self, left: Reg, right: Reg) {
+        self.op_reg(true, &[0x39], right as u8, left);
+    }
+
+    /// `test reg, reg`
+    fn test(&mut self, reg: Reg) {
+        self.op_reg(true, &[0x85], reg as u8, reg);
+    }
+
+    /// `sub rsp, imm8` or `add rsp, imm8`
+    fn adjust_stack(&mut self, by: i8) {
+        let (extension, amount) = if by < 0 { (5, -by) } else { (0, by) };
+        self.op_reg(true, &[0x83], extension, Reg::Rsp);
+        self.emit(&[amount as u8]);
+    }
+
+    /// `call [base + disp]`
+    fn call_mem(&mut self, base: Reg, disp: usize) {
+        self.op_mem(false, &[0xFF], 2, base, disp);
+    }
+
+    /// `jmp reg`
+    fn jmp_reg(&mut self, reg: Reg) {
+        self.op_reg(false, &[0xFF], 4, reg);
+    }
+
+    fn jmp(&mut self, label: Label) {
+        self.emit(&[0xE9]);
+        self.fixup(label);
+    }
+
+    fn jcc(&mut self, cond: Cond, label: Label) {
+        self.emit(&[0x0F, 0x80 | cond as u8]);
+        self.fixup(label);
+    }
+
+    fn fixup(&mut self, label: Label) {
+        self.fixups.push((self.code.len(), label));
+        self.emit_u32(0);
+    }
+
+    fn ret(&mut self) {
+        self.emit(&[0xC3]);
+    }
+
+    /// Resolves the jumps and returns the code, with the positions of the given labels.
+    fn finish(mut self, labels: &[Label]) -> (Vec<u8>, Vec<usize>) {
+        for &(at, label) in &self.fixups {
+            let target = self.labels[label.0].expect("jump to an unbound label");
+            let displacement = target as i64 - (at as i64 + 4);
+            let displacement = i32::try_from(displacement).expect("code too large to jump across");
+            self.code[at..at + 4].copy_from_slice(&displacement.to_le_bytes());
+        }
+        let positions = (labels.iter())
+            .map(|label| self.labels[label.0].expect("unbound label"))
+            .collect();
+        (self.code, positions)
+    }
+}
+
+/// Translates the instructions of a [`Program`] into x86-64 code.
+struct Compiler<'s> {
+    asm: Assembler,
+    settings: &'s Settings,
+    epilogue: Label,
+    /// Code which returns to Rust, placed after the instructions, as it is rarely executed.
+    exits: Vec<(Label, usize, u64)>,
+}
+
+impl<'s> Compiler<'s> {
+    fn new(settings: &'s Settings) -> Self {
+        let mut asm = Assembler::default();
+        let epilogue = asm.label();
+        Self {
+            asm,
+            settings,
+            epilogue,
+            exits: Vec::new(),
+        }
+    }
+
+    /// Returns the code and the offsets of the instructions, see [`NativeCode::offsets`].
+    fn compile(mut self, program: &Program) -> (Vec<u8>, Vec<usize>) {
+        let labels = (0..=program.len())
+            .map(|_| self.asm.label())
+            .collect::<Vec<_>>();
+
+        self.prologue();
+        for (pc, &instruction) in program.code().iter().enumerate() {
+            self.asm.bind(labels[pc]);
+            self.instruction(pc, instruction, &labels);
+        }
+        self.asm.bind(labels[program.len()]);
+        self.asm
+            .store_imm(CONTEXT, offset_of!(Context, pc), program.len() as u32);
+        self.asm.mov_imm(Reg::Rax, EXIT_HALTED as u32);
+        self.asm.jmp(self.epilogue);
+
+        for (label, pc, exit) in mem::take(&mut self.exits) {
+            self.asm.bind(label);
+            self.asm
+                .store_imm(CONTEXT, offset_of!(Context, pc), pc as u32);
+            self.asm.mov_imm(Reg::Rax, exit as u32);
+            self.asm.jmp(self.epilogue);
+        }
+        self.epilogue();
+        self.asm.finish(&labels)
+    }
+
+    /// Saves the registers, loads the [`Context`] and jumps to the address passed in `rsi`.
+    fn prologue(&mut self) {
+        for reg in SAVED {
+            self.asm.push(reg);
+        }
+        // Keeps the stack aligned to 16 bytes for the calls.
+        self.asm.adjust_stack(-8);
+        self.asm.mov(CONTEXT, Reg::Rdi);
+        self.asm.load(MEMORY, CONTEXT, offset_of!(Context, memory));
+        self.asm.load(LENGTH, CONTEXT, offset_of!(Context, length));
+        self.asm
+            .load(POINTER, CONTEXT, offset_of!(Context, pointer));
+        self.asm.jmp_reg(Reg::Rsi);
+    }
+
+    /// Stores the pointer, restores the registers and returns the exit code from `rax`.
+    fn epilogue(&mut self) {
+        self.asm.bind(self.epilogue);
+        self.asm
+            .store(CONTEXT, offset_of!(Context, pointer), POINTER);
+        self.asm.adjust_stack(8);
+        for reg in SAVED.into_iter().rev() {
+            self.asm.pop(reg);
+        }
+        self.asm.ret();
+    }
+
+    /// Returns a label which exits with the program counter set to `pc`.
+    fn exit(&mut self, pc: usize, exit: u64) -> Label {
+        let label = self.asm.label();
+        self.exits.push((label, pc, exit));
+        label
+    }
+
+    /// Loads the index of the cell `offset` cells away from the pointer into `rcx`.
+    ///
+    /// On a fixed tape the index wraps around the edges, like in [`Settings::mut_pointer`].
+    /// Otherwise the instruction is left to the interpreter if the cell is outside of the allocated tape.
+    fn cell_at(&mut self, pc: usize, offset: i32) -> Reg {
+        if offset == 0 {
+            return POINTER;
+        }
+        if self.settings.tape_mode() == TapeMode::Fixed && !self.settings.strict() {
+            // The offset is shorter than the tape, so a single correction brings the index back onto it.
+            let offset =
+                (self.settings).normalize_pointer_change(self.settings.wrap_pointer_change(offset));
+            let inside = self.asm.label();
+            self.asm.lea(Reg::Rcx, POINTER, offset);
+            self.asm.cmp(Reg::Rcx, LENGTH);
+            self.asm.jcc(Cond::Below, inside);
+            if offset > 0 {
+                self.asm.sub(Reg::Rcx, LENGTH);
+            } else {
+                self.asm.add(true, Reg::Rcx, LENGTH);
+            }
+            self.asm.bind(inside);
+        } else {
+            let interpret = self.exit(pc, EXIT_INTERPRET);
+            self.asm.lea(Reg::Rcx, POINTER, offset);
+            self.asm.cmp(Reg::Rcx, LENGTH);
+            self.asm.jcc(Cond::AboveOrEqual, interpret);
+        }
+        Reg::Rcx
+    }
+
+    fn instruction(&mut self, pc: usize, instruction: Instruction, labels: &[Label]) {
+        use Instruction as I;
+        let max = self.settings.cell_width().max();
+        match instruction {
+            I::MutPointer(change) => {
+                let cell = self.cell_at(pc, change);
+                self.asm.mov(POINTER, cell);
+            },
+            I::MutCell { offset, delta } => {
+                let cell = self.cell_at(pc, offset);
+                self.asm.load_cell(Reg::Rax, cell);
+                if self.settings.strict() {
+                    // Cells are unsigned, so values out of range are above the maximum when compared as unsigned.
+                    let interpret = self.exit(pc, EXIT_INTERPRET);
+                    self.asm.add_imm(true, Reg::Rax, delta);
+                    self.asm.mov_imm(Reg::Rdx, max);
+                    self.asm.cmp(Reg::Rax, Reg::Rdx);
+                    self.asm.jcc(Cond::Above, interpret);
+                } else {
+                    self.asm.add_imm(false, Reg::Rax, delta);
+                    self.asm.and_imm(Reg::Rax, max);
+                }
+                self.asm.store_cell(cell, Reg::Rax);
+            },
+            I::SetCell { offset, value } => {
+                let cell = self.cell_at(pc, offset);
+                self.asm.store_cell_imm(cell, value);
+            },
+            I::MulAdd { offset, factor } => {
+                self.asm.load_cell(Reg::Rdx, POINTER);
+                let cell = self.cell_at(pc, offset);
+                self.asm.imul_imm(Reg::Rdx, Reg::Rdx, factor);
+                self.asm.load_cell(Reg::Rax, cell);
+                self.asm.add(false, Reg::Rax, Reg::Rdx);
+                self.asm.and_imm(Reg::Rax, max);
+                self.asm.store_cell(cell, Reg::Rax);
+            },
+            I::ScanRight(stride) => self.scan(pc, stride as i32),
+            I::ScanLeft(stride) => self.scan(pc, -(stride as i32)),
+            I::JumpRightZ(offset) => {
+                self.asm.cmp_cell_zero(POINTER);
+                self.asm.jcc(Cond::Equal, labels[pc + 1 + offset as usize]);
+            },
+            I::JumpLeftNz(offset) => {
+                self.asm.cmp_cell_zero(POINTER);
+                self.asm
+                    .jcc(Cond::NotEqual, labels[pc + 1 - offset as usize]);
+            },
+            I::Input => {
+                let failed = self.exit(pc + 1, EXIT_IO_ERROR);
+                self.asm.mov(Reg::Rdi, CONTEXT);
+                self.asm.load_cell(Reg::Rsi, POINTER);
+                self.asm.call_mem(CONTEXT, offset_of!(Context, read));
+                self.asm.mov_imm(Reg::Rdx, u32::MAX);
+                self.asm.cmp(Reg::Rax, Reg::Rdx);
+                self.asm.jcc(Cond::Above, failed);
+                self.asm.store_cell(POINTER, Reg::Rax);
+            },
+            I::Output => {
+                let failed = self.exit(pc + 1, EXIT_IO_ERROR);
+                self.asm.mov(Reg::Rdi, CONTEXT);
+                self.asm.load_cell(Reg::Rsi, POINTER);
+                self.asm.call_mem(CONTEXT, offset_of!(Context, write));
+                self.asm.test(Reg::Rax);
+                self.asm.jcc(Cond::NotEqual, failed);
+            },
+            I::Breakpoint(_) => (),
+        }
+    }
+
+    /// Moves the pointer by `by` cells at a time until it points to a zero cell.
+    ///
+    /// The scan is left to the interpreter, starting over, if it reaches the edge of the tape.
+    fn scan(&mut self, pc: usize, by: i32) {
+        let interpret = self.exit(pc, EXIT_INTERPRET);
+        let (step, found) = (self.asm.label(), self.asm.label());
+        self.asm.mov(Reg::Rcx, POINTER);
+        self.asm.bind(step);
+        self.asm.cmp_cell_zero(Reg::Rcx);
+        self.asm.jcc(Cond::Equal, found);
+        self.asm.add_imm(true, Reg::Rcx, by);
+        self.asm.cmp(Reg::Rcx, LENGTH);
+        self.asm.jcc(Cond::AboveOrEqual, interpret);
+        self.asm.jmp(step);
+        self.asm.bind(found);
+        self.asm.mov(POINTER, Reg::Rcx);
+    }
+}
@@ -0,0 +1,355 @@
+use std::io::{self, Write};
+
+use crate::backend::common::{
+    Addressing, CellWidth, EofBehavior, Instruction as JI, Program, Settings, SourceWriter,
+    TapeMode,
+};
+
+/// Represents the JavaScript source code of a given [`Program`].
+///
+/// The source can be created from a [`Program`] using [`JsSource::compile_from`],
+/// and emitted using [`JsSource::emit`].
+///
+/// The produced code is an ES module without any imports, which can be loaded by any modern
+/// JavaScript runtime, e.g. `import { run } from "./program.js";`.
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsSource(String);
+
+impl JsSource {
+    /// Creates a new [`JsSource`] from a [`Program`].
+    ///
+    /// The program code is placed in the exported `run` function, which takes the whole input
+    /// as a `Uint8Array` and returns the whole output as a `Uint8Array`. Every loop is placed in its own
+    /// `loopN` function nested in `run`, where `N` is the position of its [`Instruction::JumpRightZ`](JI::JumpRightZ),
+    /// since JavaScript engines optimize large functions poorly. The cells are stored in the `tape`
+    /// typed array of the configured [`CellWidth`], so the arithmetic wraps around like in the interpreter,
+    /// and `ptr` holds the index of the current cell. A growable tape is replaced by a longer array when needed.
+    ///
+    /// The code behaves like the interpreter for the given [`Settings`]. The runtime errors (overflows in
+    /// `strict` mode) throw the exported `RuntimeError`, which holds the output written before the error.
+    ///
+    /// All programs produce valid code, panics can only occur due to internal errors.
+    pub fn compile_from(program: &Program, settings: &Settings) -> Self {
+        let addressing = Addressing::from(settings);
+        let mut out = SourceWriter::new("    ");
+
+        let helpers = Helpers::of(program, settings, addressing);
+        prelude(&mut out, settings, addressing, helpers);
+
+        // The code of `run` and of the loops which are currently open, alongside the finished loops.
+        let mut stack = vec![SourceWriter::new("    ")];
+        let mut loops = Vec::new();
+
+        for (position, instr) in program.code.iter().enumerate() {
+            let out = stack.last_mut().expect("unexpected stack underflow");
+            match *instr {
+                JI::MutPointer(change) => {
+                    out.line(format!("ptr = {};", index(settings, addressing, change)));
+                },
+                JI::MutCell { offset, delta } => {
+                    let cell = cell(out, settings, addressing, offset);
+                    match (settings.strict(), delta < 0) {
+                        (true, _) => out.line(format!("add({cell}, {delta});")),
+                        (false, false) => out.line(format!("tape[{cell}] += {delta};")),
+                        (false, true) =>
+                            out.line(format!("tape[{cell}] -= {};", delta.unsigned_abs())),
+                    }
+                },
+                JI::SetCell { offset, value } => {
+                    let cell = cell(out, settings, addressing, offset);
+                    out.line(format!("tape[{cell}] = {value};"));
+                },
+                JI::MulAdd { offset, factor } => {
+                    let cell = cell(out, settings, addressing, offset);
+                    // The product is computed on 32 bits, so that it isn't rounded for wide cells.
+                    out.line(format!("tape[{cell}] += Math.imul(tape[ptr], {factor});"));
+                },
+                JI::ScanRight(stride) => scan(out, settings, addressing, stride as i32),
+                JI::ScanLeft(stride) => scan(out, settings, addressing, -(stride as i32)),
+                JI::JumpRightZ(_) => {
+                    out.line(format!("loop{position}();"));
+                    let mut body = SourceWriter::new("    ");
+                    body.open(format!("function loop{position}() {{"));
+                    body.open("while (tape[ptr]) {");
+                    stack.push(body);
+                },
+                JI::JumpLeftNz(jump) => {
+                    let mut body = stack.pop().expect("unexpected stack underflow");
+                    body.close("}");
+                    body.close("}");
+                    loops.push((position - jump as usize, body.finish()));
+                },
+                JI::Input => read(out, settings.eof_behavior()),
+                JI::Output => out.line(match settings.cell_width() {
+                    CellWidth::U8 => "output.push(tape[ptr]);",
+                    CellWidth::U16 | CellWidth::U32 => "output.push(tape[ptr] & 0xFF);",
+                }),
+                JI::Breakpoint(_) => (),
+            }
+        }
+
+        let body = stack.pop().expect("unexpected stack underflow");
+        assert!(stack.is_empty(), "unexpected stack overflow");
+        loops.sort_unstable_by_key(|(start, _)| *start);
+        for (_, function) in loops {
+            out.lines(&function);
+            out.line("");
+        }
+        out.lines(&body.finish());
+        out.line("");
+        out.line("return Uint8Array.from(output);");
+        out.close("}");
+
+        Self(out.finish())
+    }
+
+    /// Emits the JavaScript source code to the given [`Write`].
+    pub fn emit(&self, mut write: impl Write) -> io::Result<()> {
+        write.write_all(self.0.as_bytes())
+    }
+}
+
+/// The parts of the prelude which are used by a program.
+#[derive(Debug, Clone, Copy)]
+struct Helpers {
+    /// The `i` variable, which holds the index of a cell other than the current one.
+    index: bool,
+    wrap: bool,
+    at: bool,
+    add: bool,
+    /// The `position` variable, which holds the index of the next input byte.
+    read: bool,
+}
+
+impl Helpers {
+    fn of(program: &Program, settings: &Settings, addressing: Addressing) -> Self {
+        let any = |predicate: fn(&JI) -> bool| program.code.iter().any(predicate);
+        let index = any(|instr| {
+            matches!(instr,
+                JI::MutCell { offset, .. } | JI::SetCell { offset, .. } | JI::MulAdd { offset, .. }
+                    if *offset != 0
+            )
+        });
+        let moves = any(|instr| {
+            matches!(
+                instr,
+                JI::MutPointer(_) | JI::ScanRight(_) | JI::ScanLeft(_)
+            )
+        });
+        Self {
+            index,
+            wrap: addressing == Addressing::Wrapping && (index || moves),
+            at: addressing != Addressing::Wrapping && (index || moves),
+            add: settings.strict() && any(|instr| matches!(instr, JI::MutCell { .. })),
+            read: any(|instr| matches!(instr, JI::Input)),
+        }
+    }
+}
+
+/// Writes the constants, the error class and the beginning of `run`, including the helper functions.
+fn prelude(out: &mut SourceWriter, settings: &Settings, addressing: Addressing, helpers: Helpers) {
+    out.line(format!("const TAPE_LENGTH = {};", settings.tape_length()));
+    if addressing == Addressing::Growable {
+        out.line(format!("const MAX_LENGTH = {};", Settings::MAX_LENGTH));
+    }
+    if helpers.add || (helpers.read && settings.eof_behavior() == EofBehavior::Max) {
+        out.line(format!("const CELL_MAX = {};", settings.cell_width().max()));
+    }
+    out.line("");
+    out.lines(RUNTIME_ERROR);
+    out.line("");
+    out.lines(RUN_DOCS);
+    out.open("export function run(input) {");
+
+    let array = match settings.cell_width() {
+        CellWidth::U8 => "Uint8Array",
+        CellWidth::U16 => "Uint16Array",
+        CellWidth::U32 => "Uint32Array",
+    };
+    out.line(format!("let tape = new {array}(TAPE_LENGTH);"));
+    out.line("let ptr = 0;");
+    if helpers.index {
+        out.line("let i;");
+    }
+    if helpers.at || helpers.add {
+        // The origin is the index of the initial first cell, it only moves when the tape grows to the left.
+        out.line("let origin = 0;");
+    }
+    if helpers.read {
+        out.line("let position = 0;");
+    }
+    out.line("const output = [];");
+
+    if helpers.at || helpers.add {
+        out.line("");
+        out.lines(FAIL);
+    }
+    if helpers.at {
+        out.line("");
+        out.lines(TAPE_OVERFLOW);
+    }
+    match addressing {
+        Addressing::Wrapping if helpers.wrap => {
+            out.line("");
+            out.lines(WRAP);
+        },
+        Addressing::Bounded if helpers.at => {
+            out.line("");
+            out.lines(BOUNDED_AT);
+        },
+        Addressing::Growable if helpers.at => {
+            out.line("");
+            out.lines(&growable_at(settings.tape_mode(), array));
+        },
+        Addressing::Wrapping | Addressing::Bounded | Addressing::Growable => (),
+    }
+    if helpers.add {
+        out.line("");
+        out.lines(ADD);
+    }
+    out.line("");
+}
+
+/// Returns the expression of the index of the cell `offset` cells away from the pointer.
+fn index(settings: &Settings, addressing: Addressing, offset: i32) -> String {
+    match addressing {
+        Addressing::Wrapping => format!(
+            "wrap(ptr + {})",
+            offset.rem_euclid(settings.tape_length() as i32)
+        ),
+        Addressing::Bounded | Addressing::Growable => format!("at({offset})"),
+    }
+}
+
+/// Returns the expression of the index of the cell `offset` cells away from the pointer,
+/// storing it in `i` first if it has to be computed.
+///
+/// Growing the tape replaces the array, so the index is always computed in a separate statement.
+fn cell(
+    out: &mut SourceWriter,
+    settings: &Settings,
+    addressing: Addressing,
+    offset: i32,
+) -> &'static str {
+    if offset == 0 {
+        return "ptr";
+    }
+    out.line(format!("i = {};", index(settings, addressing, offset)));
+    "i"
+}
+
+fn scan(out: &mut SourceWriter, settings: &Settings, addressing: Addressing, stride: i32) {
+    out.open("while (tape[ptr]) {");
+    out.line(format!("ptr = {};", index(settings, addressing, stride)));
+    out.close("}");
+}
+
+/// Writes the statement which stores the next input byte, or handles the end of input.
+fn read(out: &mut SourceWriter, eof_behavior: EofBehavior) {
+    match eof_behavior {
+        EofBehavior::Zero =>
+            out.line("tape[ptr] = position < input.length ? input[position++] : 0;"),
+        EofBehavior::Unchanged => {
+            out.open("if (position < input.length) {");
+            out.line("tape[ptr] = input[position++];");
+            out.close("}");
+        },
+        EofBehavior::Max =>
+            out.line("tape[ptr] = position < input.length ? input[position++] : CELL_MAX;"),
+    }
+}
+
+const RUNTIME_ERROR: &str = r#"/** An error which stopped the execution, holding the output written before it. */
+export class RuntimeError extends Error {
+    constructor(message, output) {
+        super(message);
+        this.name = "RuntimeError";
+        this.output = output;
+    }
+}"#;
+
+const RUN_DOCS: &str = "\
+/**
+ * Runs the program with the given input, and returns its output.
+ * @param {Uint8Array} input
+ * @returns {Uint8Array}
+ */";
+
+const FAIL: &str = "\
+function fail(message) {
+    throw new RuntimeError(message, Uint8Array.from(output));
+}";
+
+const TAPE_OVERFLOW: &str = "\
+function tapeOverflow(by) {
+    fail(`tape overflow when changing ${ptr - origin} by ${by}`);
+}";
+
+const ADD: &str = "\
+function add(index, delta) {
+    const value = tape[index] + delta;
+    if (value < 0 || value > CELL_MAX) {
+        fail(`cell overflow when changing ${tape[index]} by ${delta} at ${index - origin}`);
+    }
+    tape[index] = value;
+}";
+
+/// Wraps an index which is at most one tape length past the end, which is faster than the remainder.
+const WRAP: &str = "\
+function wrap(index) {
+    return index < TAPE_LENGTH ? index : index - TAPE_LENGTH;
+}";
+
+const BOUNDED_AT: &str = "\
+function at(offset) {
+    const index = ptr + offset;
+    if (index < 0 || index >= TAPE_LENGTH) {
+        tapeOverflow(offset);
+    }
+    return index;
+}";
+
+/// Returns the `at` function of a growable tape, which grows it so that it contains the cell
+/// `offset` cells away from the pointer, and returns the index of that cell.
+fn growable_at(tape_mode: TapeMode, array: &str) -> String {
+    let grow_left = match tape_mode {
+        TapeMode::GrowBoth => grow_left(array),
+        TapeMode::Fixed | TapeMode::GrowRight => String::from("        tapeOverflow(offset);"),
+    };
+    format!(
+        "\
+function at(offset) {{
+    let index = ptr + offset;
+    const room = MAX_LENGTH - tape.length;
+    const least = Math.min(tape.length, room);
+    if (index < 0) {{
+{grow_left}
+    }} else if (index >= tape.length) {{
+        if (index >= tape.length + room) {{
+            tapeOverflow(offset);
+        }}
+        const grown = new {array}(Math.max(index + 1, tape.length + least));
+        grown.set(tape);
+        tape = grown;
+    }}
+    return index;
+}}"
+    )
+}
+
+/// Returns the part of `at` which copies the tape into a larger typed array, after the cells added on the left.
+fn grow_left(array: &str) -> String {
+    format!(
+        "        if (-index > room) {{
+            tapeOverflow(offset);
+        }}
+        const added = Math.max(-index, least);
+        const grown = new {array}(tape.length + added);
+        grown.set(tape, added);
+        tape = grown;
+        origin += added;
+        ptr += added;
+        index += added;"
+    )
+}
@@ -0,0 +1,31 @@
+//! Functionalities related to transpilation from Brainfuck to JavaScript.
+//!
+//! The features are exposed through the [`JsSource`] type, which holds a self-contained ES module.
+//! It exports the `run(input: Uint8Array): Uint8Array` function, so it can be used in the browser
+//! or in Node.js without WebAssembly, and without providing any I/O functions.
+//!
+//! If you prefer WebAssembly, [`WasmModule::emit_js_loader`](crate::wasm::WasmModule::emit_js_loader)
+//! emits a loader with a similar, asynchronous interface.
+//!
+//! # Examples
+//! ```
+//! # use std::io::stdout;
+//! use brainlib::{js::JsSource, Program, Settings};
+//!
+//! let settings = Settings::default();
+//! let source = ",[.,]";
+//! let program = Program::compile(source, &settings)?;
+//!
+//! let js_source = JsSource::compile_from(&program, &settings);
+//! js_source.emit(stdout())?;
+//!
+//! let mut text = Vec::new();
+//! js_source.emit(&mut text)?;
+//! assert!(String::from_utf8(text)?.contains("export function run(input) {"));
+//!
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+mod js_source;
+
+pub use self::js_source::JsSource;
@@ -0,0 +1,471 @@
+use std::io::{self, Write};
+
+use crate::backend::common::{
+    Addressing, CellWidth, EofBehavior, Instruction as CI, Program, Settings, TapeMode,
+};
+
+/// Represents an LLVM IR module for a given [`Program`].
+///
+/// A module can be created from a [`Program`] using [`LlvmModule::compile_from`],
+/// and emitted in the textual format using [`LlvmModule::emit_ll`].
+///
+/// The module defines the `main` function and only depends on the C standard library,
+/// so it can be built into an executable with e.g. `clang -O2 program.ll`.
+///
+/// # Specification
+/// - [LLVM Language Reference Manual](https://llvm.org/docs/LangRef.html)
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LlvmModule(String);
+
+impl LlvmModule {
+    /// Creates a new [`LlvmModule`] from a [`Program`].
+    ///
+    /// The entire program code is placed in the `@main` function. Every loop consists of the `loop.N`
+    /// and `loop.N.end` blocks, where `N` is the position of its [`Instruction::JumpRightZ`](CI::JumpRightZ).
+    /// The cells are stored in the `@tape` global, using the integer type of the configured [`CellWidth`],
+    /// and the `@ptr` global holds the index of the current cell. A fixed tape is a global array,
+    /// while a growable one is allocated with `realloc`.
+    ///
+    /// Input is read with `getchar`, which handles the configured [`EofBehavior`], and output is written with `putchar`.
+    ///
+    /// With a growable [`TapeMode`], every cell index is passed through the `@reserve` function,
+    /// which grows the tape when needed and returns the index to use, like the `$reserve` function of WASM.
+    ///
+    /// The runtime errors (overflows in `strict` mode, failed allocations) flush the output and call `llvm.trap`.
+    /// I/O errors are not reported, a failed read behaves like the end of input.
+    ///
+    /// All programs produce a valid module, panics can only occur due to internal errors.
+    pub fn compile_from(program: &Program, settings: &Settings) -> Self {
+        let addressing = Addressing::from(settings);
+        let mut main = Function::new(settings, addressing);
+
+        if addressing == Addressing::Growable {
+            main.line(format!("call void @resize(i64 {})", settings.tape_length()));
+        }
+
+        for (position, instr) in program.code.iter().enumerate() {
+            match *instr {
+                CI::MutPointer(change) => main.mut_pointer(change),
+                CI::MutCell { offset, delta } => main.mut_cell(offset, delta),
+                CI::SetCell { offset, value } => main.set_cell(offset, value),
+                CI::MulAdd { offset, factor } => main.mul_add(offset, factor),
+                CI::ScanRight(stride) => main.scan(position, stride as i32),
+                CI::ScanLeft(stride) => main.scan(position, -(stride as i32)),
+                CI::JumpRightZ(_) => main.loop_header(position),
+                CI::JumpLeftNz(jump) => main.loop_trailer(position - jump as usize),
+                CI::Input => main.input(),
+                CI::Output => main.output(),
+                CI::Breakpoint(_) => (),
+            }
+        }
+
+        main.line("ret i32 0");
+
+        let mut text = prelude(settings, addressing);
+        text.push_str(&main.finish());
+        if addressing == Addressing::Growable {
+            text.push('\n');
+            text.push_str(&resize(settings));
+            text.push('\n');
+            text.push_str(&reserve(settings));
+        }
+
+        Self(text)
+    }
+
+    /// Emits the module in the textual LLVM IR format to the given [`Write`].
+    pub fn emit_ll(&self, mut write: impl Write) -> io::Result<()> {
+        write.write_all(self.0.as_bytes())
+    }
+}
+
+/// Returns the integer type of a cell, e.g. `i8`.
+fn cell_type(settings: &Settings) -> String {
+    format!("i{}", settings.cell_width().bits())
+}
+
+/// Writes the globals and the declarations of the external functions.
+fn prelude(settings: &Settings, addressing: Addressing) -> String {
+    let cell = cell_type(settings);
+    let mut text = match addressing {
+        Addressing::Wrapping | Addressing::Bounded => format!(
+            "@tape = internal global [{} x {cell}] zeroinitializer\n",
+            settings.tape_length()
+        ),
+        Addressing::Growable =>
+            String::from("@tape = internal global ptr null\n@length = internal global i64 0\n"),
+    };
+    text.push_str("@ptr = internal global i64 0\n\n");
+    text.push_str(DECLARATIONS);
+    if addressing == Addressing::Growable {
+        text.push_str(GROWABLE_DECLARATIONS);
+    }
+    text.push('\n');
+    text
+}
+
+/// The body of the `@main` function, in which the values are numbered in the order of their definition.
+struct Function<'a> {
+    settings: &'a Settings,
+    addressing: Addressing,
+    /// The integer type of a cell.
+    cell: String,
+    body: String,
+    /// The number of the next value.
+    values: usize,
+    /// The number of the next block reached after passing a check.
+    checks: usize,
+    /// Whether any check branches to the `trap` block.
+    traps: bool,
+}
+
+impl<'a> Function<'a> {
+    fn new(settings: &'a Settings, addressing: Addressing) -> Self {
+        Self {
+            settings,
+            addressing,
+            cell: cell_type(settings),
+            body: String::from("define i32 @main() {\nentry:\n"),
+            values: 0,
+            checks: 0,
+            traps: false,
+        }
+    }
+
+    fn line(&mut self, instr: impl AsRef<str>) {
+        self.body.push_str("  ");
+        self.body.push_str(instr.as_ref());
+        self.body.push('\n');
+    }
+
+    /// Writes an instruction producing a value, and returns the name of that value.
+    fn value(&mut self, instr: impl AsRef<str>) -> String {
+        let name = format!("%{}", self.values);
+        self.values += 1;
+        self.line(format!("{name} = {}", instr.as_ref()));
+        name
+    }
+
+    fn block(&mut self, label: &str) {
+        self.body.push('\n');
+        self.body.push_str(label);
+        self.body.push_str(":\n");
+    }
+
+    /// Continues in a new block if the `condition` holds, or traps otherwise.
+    fn check(&mut self, condition: &str) {
+        let label = format!("ok.{}", self.checks);
+        self.checks += 1;
+        self.traps = true;
+        self.line(format!("br i1 {condition}, label %{label}, label %trap"));
+        self.block(&label);
+    }
+
+    /// Returns the constant of the cell type, with the same bits as the lowest bits of `value`.
+    fn constant(&self, value: u32) -> i32 {
+        self.settings.wrap_change(value as i32)
+    }
+
+    /// Returns the index of the cell `offset` cells away from the pointer.
+    fn index(&mut self, offset: i32) -> String {
+        let ptr = self.value("load i64, ptr @ptr");
+        if offset == 0 {
+            return ptr;
+        }
+
+        let length = self.settings.tape_length();
+        match self.addressing {
+            Addressing::Wrapping => {
+                let change = offset.rem_euclid(length as i32);
+                let index = self.value(format!("add nuw i64 {ptr}, {change}"));
+                let inside = self.value(format!("icmp ult i64 {index}, {length}"));
+                let wrapped = self.value(format!("sub nuw i64 {index}, {length}"));
+                self.value(format!("select i1 {inside}, i64 {index}, i64 {wrapped}"))
+            },
+            Addressing::Bounded => {
+                // A negative index is greater than the length when compared as unsigned.
+                let index = self.value(format!("add i64 {ptr}, {offset}"));
+                let inside = self.value(format!("icmp ult i64 {index}, {length}"));
+                self.check(&inside);
+                index
+            },
+            Addressing::Growable => {
+                let index = self.value(format!("add i64 {ptr}, {offset}"));
+                self.value(format!("call i64 @reserve(i64 {index})"))
+            },
+        }
+    }
+
+    /// Returns the pointer to the cell `offset` cells away from the pointer.
+    ///
+    /// Growing the tape moves it in memory, so the pointer is invalidated by the next growth.
+    fn cell(&mut self, offset: i32) -> String {
+        let index = self.index(offset);
+        let cell = self.cell.clone();
+        match self.addressing {
+            Addressing::Wrapping | Addressing::Bounded => self.value(format!(
+                "getelementptr inbounds [{} x {cell}], ptr @tape, i64 0, i64 {index}",
+                self.settings.tape_length()
+            )),
+            Addressing::Growable => {
+                let tape = self.value("load ptr, ptr @tape");
+                self.value(format!(
+                    "getelementptr inbounds {cell}, ptr {tape}, i64 {index}"
+                ))
+            },
+        }
+    }
+
+    /// Returns the pointer to the current cell, alongside its value.
+    fn current(&mut self) -> (String, String) {
+        let cell = self.cell(0);
+        let value = self.value(format!("load {}, ptr {cell}", self.cell));
+        (cell, value)
+    }
+
+    fn mut_pointer(&mut self, change: i32) {
+        let index = self.index(change);
+        self.line(format!("store i64 {index}, ptr @ptr"));
+    }
+
+    fn mut_cell(&mut self, offset: i32, delta: i32) {
+        let ty = self.cell.clone();
+        let cell = self.cell(offset);
+        let value = self.value(format!("load {ty}, ptr {cell}"));
+
+        if self.settings.strict() {
+            let fits = match delta > 0 {
+                true => {
+                    let limit = self.constant(self.settings.cell_width().max() - delta as u32);
+                    self.value(format!("icmp ule {ty} {value}, {limit}"))
+                },
+                false => {
+                    let limit = self.constant(delta.unsigned_abs());
+                    self.value(format!("icmp uge {ty} {value}, {limit}"))
+                },
+            };
+            self.check(&fits);
+        }
+
+        let delta = self.constant(delta as u32);
+        let result = self.value(format!("add {ty} {value}, {delta}"));
+        self.line(format!("store {ty} {result}, ptr {cell}"));
+    }
+
+    fn set_cell(&mut self, offset: i32, value: u32) {
+        let cell = self.cell(offset);
+        let value = self.constant(value);
+        self.line(format!("store {} {value}, ptr {cell}", self.cell));
+    }
+
+    fn mul_add(&mut self, offset: i32, factor: i32) {
+        let ty = self.cell.clone();
+        // The target is reserved first, since growing the tape to the left moves the current cell.
+        let target = self.cell(offset);
+        let value = self.value(format!("load {ty}, ptr {target}"));
+        let (_, current) = self.current();
+        let factor = self.constant(factor as u32);
+        let product = self.value(format!("mul {ty} {current}, {factor}"));
+        let result = self.value(format!("add {ty} {value}, {product}"));
+        self.line(format!("store {ty} {result}, ptr {target}"));
+    }
+
+    fn scan(&mut self, position: usize, stride: i32) {
+        let label = format!("scan.{position}");
+        self.line(format!("br label %{label}"));
+        self.block(&label);
+        let (_, value) = self.current();
+        let zero = self.value(format!("icmp eq {} {value}, 0", self.cell));
+        self.line(format!(
+            "br i1 {zero}, label %{label}.end, label %{label}.step"
+        ));
+        self.block(&format!("{label}.step"));
+        self.mut_pointer(stride);
+        self.line(format!("br label %{label}"));
+        self.block(&format!("{label}.end"));
+    }
+
+    fn loop_header(&mut self, position: usize) {
+        let (_, value) = self.current();
+        let zero = self.value(format!("icmp eq {} {value}, 0", self.cell));
+        self.line(format!(
+            "br i1 {zero}, label %loop.{position}.end, label %loop.{position}"
+        ));
+        self.block(&format!("loop.{position}"));
+    }
+
+    fn loop_trailer(&mut self, start: usize) {
+        let (_, value) = self.current();
+        let nonzero = self.value(format!("icmp ne {} {value}, 0", self.cell));
+        self.line(format!(
+            "br i1 {nonzero}, label %loop.{start}, label %loop.{start}.end"
+        ));
+        self.block(&format!("loop.{start}.end"));
+    }
+
+    fn input(&mut self) {
+        let ty = self.cell.clone();
+        let (cell, current) = self.current();
+        let byte = self.value("call i32 @getchar()");
+        let value = match self.settings.cell_width() {
+            CellWidth::U8 | CellWidth::U16 => self.value(format!("trunc i32 {byte} to {ty}")),
+            CellWidth::U32 => byte.clone(),
+        };
+        // The end of input is signalled by a negative value.
+        let eof = self.value(format!("icmp slt i32 {byte}, 0"));
+        let fallback = match self.settings.eof_behavior() {
+            EofBehavior::Zero => String::from("0"),
+            EofBehavior::Unchanged => current,
+            EofBehavior::Max => self.constant(self.settings.cell_width().max()).to_string(),
+        };
+        let result = self.value(format!("select i1 {eof}, {ty} {fallback}, {ty} {value}"));
+        self.line(format!("store {ty} {result}, ptr {cell}"));
+    }
+
+    fn output(&mut self) {
+        let (_, value) = self.current();
+        // putchar converts its argument to an unsigned char, so only the least significant byte is written.
+        let value = match self.settings.cell_width() {
+            CellWidth::U8 | CellWidth::U16 =>
+                self.value(format!("zext {} {value} to i32", self.cell)),
+            CellWidth::U32 => value,
+        };
+        self.value(format!("call i32 @putchar(i32 {value})"));
+    }
+
+    /// Returns the whole function, ending with the `trap` block if any check uses it.
+    fn finish(mut self) -> String {
+        if self.traps {
+            self.body.push('\n');
+            self.body.push_str(TRAP);
+        }
+        self.body.push_str("}\n");
+        self.body
+    }
+}
+
+const DECLARATIONS: &str = "\
+declare i32 @getchar()
+declare i32 @putchar(i32)
+declare i32 @fflush(ptr)
+declare void @llvm.trap()
+";
+
+/// The block which all failed checks of a function branch to.
+///
+/// The output is flushed first, so that it isn't lost when trapping.
+const TRAP: &str = "\
+trap:
+  %flushed = call i32 @fflush(ptr null)
+  call void @llvm.trap()
+  unreachable
+";
+
+const GROWABLE_DECLARATIONS: &str = "\
+declare ptr @realloc(ptr, i64)
+declare void @llvm.memset.p0.i64(ptr, i8, i64, i1)
+declare void @llvm.memmove.p0.p0.i64(ptr, ptr, i64, i1)
+declare i64 @llvm.umin.i64(i64, i64)
+declare i64 @llvm.umax.i64(i64, i64)
+";
+
+/// Returns the `@resize` function, which reallocates a growable tape to the given length,
+/// clearing the added cells.
+fn resize(settings: &Settings) -> String {
+    let (cell, bytes) = (cell_type(settings), settings.cell_width().bytes());
+    format!(
+        "\
+define internal void @resize(i64 %new_length) {{
+entry:
+  %tape = load ptr, ptr @tape
+  %length = load i64, ptr @length
+  %size = mul i64 %new_length, {bytes}
+  %resized = call ptr @realloc(ptr %tape, i64 %size)
+  %failed = icmp eq ptr %resized, null
+  br i1 %failed, label %trap, label %clear
+
+clear:
+  %end = getelementptr inbounds {cell}, ptr %resized, i64 %length
+  %added = sub i64 %new_length, %length
+  %cleared = mul i64 %added, {bytes}
+  call void @llvm.memset.p0.i64(ptr %end, i8 0, i64 %cleared, i1 false)
+  store ptr %resized, ptr @tape
+  store i64 %new_length, ptr @length
+  ret void
+
+{TRAP}}}
+"
+    )
+}
+
+/// Returns the `@reserve` function, which takes a cell index, grows the tape to contain it,
+/// and returns the index of the same cell after growing.
+///
+/// Growing to the left moves the whole tape (and `@ptr`) to the right.
+/// Moving left of a [`TapeMode::GrowRight`] tape traps.
+fn reserve(settings: &Settings) -> String {
+    let (cell, bytes) = (cell_type(settings), settings.cell_width().bytes());
+    let (left, grow_left) = match settings.tape_mode() {
+        TapeMode::GrowBoth => (
+            "left",
+            format!(
+                "
+left:
+  %needed = sub i64 0, %index
+  %too_far = icmp ugt i64 %needed, %room
+  br i1 %too_far, label %trap, label %grow_left
+
+grow_left:
+  %added = call i64 @llvm.umax.i64(i64 %needed, i64 %least)
+  %new_length = add i64 %length, %added
+  call void @resize(i64 %new_length)
+  %tape = load ptr, ptr @tape
+  %moved = getelementptr inbounds {cell}, ptr %tape, i64 %added
+  %size = mul i64 %length, {bytes}
+  call void @llvm.memmove.p0.p0.i64(ptr %moved, ptr %tape, i64 %size, i1 false)
+  %cleared = mul i64 %added, {bytes}
+  call void @llvm.memset.p0.i64(ptr %tape, i8 0, i64 %cleared, i1 false)
+  %ptr = load i64, ptr @ptr
+  %moved_ptr = add i64 %ptr, %added
+  store i64 %moved_ptr, ptr @ptr
+  %moved_index = add i64 %index, %added
+  ret i64 %moved_index
+"
+            ),
+        ),
+        TapeMode::Fixed | TapeMode::GrowRight => ("trap", String::new()),
+    };
+    let max_length = Settings::MAX_LENGTH;
+    format!(
+        "\
+define internal i64 @reserve(i64 %index) {{
+entry:
+  %length = load i64, ptr @length
+  %room = sub i64 {max_length}, %length
+  %least = call i64 @llvm.umin.i64(i64 %length, i64 %room)
+  %before = icmp slt i64 %index, 0
+  br i1 %before, label %{left}, label %inside
+{grow_left}
+inside:
+  %after = icmp sge i64 %index, %length
+  br i1 %after, label %right, label %done
+
+right:
+  %beyond = icmp sge i64 %index, {max_length}
+  br i1 %beyond, label %trap, label %grow_right
+
+grow_right:
+  %next = add i64 %index, 1
+  %doubled = add i64 %length, %least
+  %grown = call i64 @llvm.umax.i64(i64 %next, i64 %doubled)
+  call void @resize(i64 %grown)
+  br label %done
+
+done:
+  ret i64 %index
+
+{TRAP}}}
+"
+    )
+}
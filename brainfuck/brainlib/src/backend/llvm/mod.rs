@@ -0,0 +1,32 @@
+//! Functionalities related to transpilation from Brainfuck to LLVM IR.
+//!
+//! > The LLVM Project is a collection of modular and reusable compiler and toolchain technologies. [^1]
+//!
+//! The features are exposed through the [`LlvmModule`] type, which holds a module in the textual
+//! LLVM assembly format (`.ll`). It can be optimized with `opt`, compiled with `llc` or `clang`,
+//! or run directly with `lli`. The module uses opaque pointers, so LLVM 15 or newer is expected.
+//!
+//! # Examples
+//! ```
+//! # use std::io::stdout;
+//! use brainlib::{llvm::LlvmModule, Program, Settings};
+//!
+//! let settings = Settings::default();
+//! let source = ",[.,]";
+//! let program = Program::compile(source, &settings)?;
+//!
+//! let module = LlvmModule::compile_from(&program, &settings);
+//! module.emit_ll(stdout())?;
+//!
+//! let mut text = Vec::new();
+//! module.emit_ll(&mut text)?;
+//! assert!(text.starts_with(b"@tape"));
+//!
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! [^1]: [The LLVM Compiler Infrastructure](https://llvm.org)
+
+mod llvm_module;
+
+pub use self::llvm_module::LlvmModule;
@@ -1,3 +1,10 @@
+pub mod c;
 pub(crate) mod common;
 pub mod interpreter;
+#[cfg(feature = "jit")]
+pub mod jit;
+pub mod js;
+pub mod llvm;
+pub mod native;
+pub mod rust;
 pub mod wasm;
@@ -0,0 +1,497 @@
+use super::instruction::{AluOp, Cond, Instr as I, Label, Mem, Reg, Size};
+use crate::backend::common::{Addressing, EofBehavior, Instruction, Program, Settings, TapeMode};
+
+/// The address of the cell at position zero, the initial first cell.
+const TAPE: Reg = Reg::Rbx;
+/// The position of the current cell.
+const POINTER: Reg = Reg::R12;
+/// The position of the first cell of a growable tape.
+const LOW: Reg = Reg::R13;
+/// The position right after the last cell of a growable tape.
+const HIGH: Reg = Reg::R14;
+/// The maximum value of a cell, only set in `strict` mode.
+const CELL_MAX: Reg = Reg::R15;
+/// The register in which [`Compiler::cell_at`] computes the positions of other cells.
+const OTHER: Reg = Reg::Rsi;
+
+const SYS_READ: i64 = 0;
+const SYS_WRITE: i64 = 1;
+const SYS_MMAP: i64 = 9;
+const SYS_EXIT: i64 = 60;
+const EINTR: i32 = 4;
+const PROT_READ_WRITE: i64 = 0x3;
+const MAP_PRIVATE_ANONYMOUS_NORESERVE: i64 = 0x4022;
+
+/// Translates a [`Program`] into the instructions of a standalone Linux executable.
+///
+/// The tape is a single anonymous memory mapping. A growable tape reserves room for [`Settings::MAX_LENGTH`]
+/// cells on both sides of the initial first cell, so it never moves, and only its bounds are tracked.
+/// Those follow the interpreter exactly, including the errors when the tape can't grow any further.
+#[derive(Debug)]
+pub(super) struct Compiler<'s> {
+    settings: &'s Settings,
+    addressing: Addressing,
+    size: Size,
+    code: Vec<I>,
+    data: Vec<(Label, &'static str)>,
+    labels: usize,
+}
+
+impl<'s> Compiler<'s> {
+    pub(super) fn new(settings: &'s Settings) -> Self {
+        Self {
+            settings,
+            addressing: settings.into(),
+            size: settings.cell_width().into(),
+            code: Vec::new(),
+            data: Vec::new(),
+            labels: 0,
+        }
+    }
+
+    /// Returns the code, which starts at the entry point, and the messages it refers to.
+    pub(super) fn compile(mut self, program: &Program) -> (Vec<I>, Vec<(Label, &'static str)>) {
+        self.start();
+
+        let mut loops = Vec::new();
+        for instr in program.code.iter() {
+            match *instr {
+                Instruction::MutPointer(change) => {
+                    let other = self.cell_at(change);
+                    self.emit([I::Mov(POINTER, other)]);
+                },
+                Instruction::MutCell { offset, delta } => self.mut_cell(offset, delta),
+                Instruction::SetCell { offset, value } => {
+                    let other = self.cell_at(offset);
+                    self.emit([I::StoreImm(self.size, self.cell(other), value)]);
+                },
+                Instruction::MulAdd { offset, factor } => {
+                    // The position is computed first, since growing the tape overwrites `rax`.
+                    let other = self.cell_at(offset);
+                    self.emit([
+                        I::Load(self.size, Reg::Rax, self.cell(POINTER)),
+                        I::Imul(Reg::Rax, Reg::Rax, factor),
+                        I::AddMem(self.size, self.cell(other), Reg::Rax),
+                    ]);
+                },
+                Instruction::ScanRight(stride) => self.scan(stride as i32),
+                Instruction::ScanLeft(stride) => self.scan(-(stride as i32)),
+                Instruction::JumpRightZ(_) => {
+                    let (body, end) = (self.label(), self.label());
+                    self.emit([
+                        I::CmpMemImm(self.size, self.cell(POINTER), 0),
+                        I::Jcc(Cond::Equal, end),
+                        I::Bind(body),
+                    ]);
+                    loops.push((body, end));
+                },
+                Instruction::JumpLeftNz(_) => {
+                    let (body, end) = loops.pop().expect("unmatched loop end");
+                    self.emit([
+                        I::CmpMemImm(self.size, self.cell(POINTER), 0),
+                        I::Jcc(Cond::NotEqual, body),
+                        I::Bind(end),
+                    ]);
+                },
+                Instruction::Input => self.emit([I::Call(Label::Named("read"))]),
+                Instruction::Output => self.emit([I::Call(Label::Named("write"))]),
+                Instruction::Breakpoint(_) => (),
+            }
+        }
+        self.emit([
+            I::MovImm(Reg::Rax, SYS_EXIT),
+            I::MovImm(Reg::Rdi, 0),
+            I::Syscall,
+        ]);
+
+        let any = |predicate: fn(&Instruction) -> bool| program.code.iter().any(predicate);
+        if any(|instr| matches!(instr, Instruction::Input)) {
+            self.read();
+        }
+        if any(|instr| matches!(instr, Instruction::Output)) {
+            self.write();
+        }
+        if self.addressing == Addressing::Growable {
+            self.grow();
+        }
+        if self.addressing != Addressing::Wrapping {
+            self.tape_overflow();
+        }
+        if self.settings.strict() {
+            self.cell_overflow();
+        }
+        self.failures();
+        (self.code, self.data)
+    }
+
+    fn label(&mut self) -> Label {
+        self.labels += 1;
+        Label::Numbered(self.labels)
+    }
+
+    fn emit(&mut self, instrs: impl IntoIterator<Item = I>) {
+        self.code.extend(instrs);
+    }
+
+    /// The operand of the cell at the position stored in `position`.
+    fn cell(&self, position: Reg) -> Mem {
+        Mem::indexed(TAPE, position, self.settings.cell_width().bytes() as u8)
+    }
+
+    /// Maps the tape and initializes the registers.
+    fn start(&mut self) {
+        let bytes = u64::from(self.settings.cell_width().bytes());
+        let cells = match self.addressing {
+            Addressing::Wrapping | Addressing::Bounded => u64::from(self.settings.tape_length()),
+            Addressing::Growable => 2 * u64::from(Settings::MAX_LENGTH),
+        };
+        self.emit([
+            I::MovImm(Reg::Rax, SYS_MMAP),
+            I::MovImm(Reg::Rdi, 0),
+            I::MovImm(Reg::Rsi, (cells * bytes) as i64),
+            I::MovImm(Reg::Rdx, PROT_READ_WRITE),
+            I::MovImm(Reg::R10, MAP_PRIVATE_ANONYMOUS_NORESERVE),
+            I::MovImm(Reg::R8, -1),
+            I::MovImm(Reg::R9, 0),
+            I::Syscall,
+            // The errors are returned as negated error numbers, from -4095 to -1.
+            I::AluImm(AluOp::Cmp, Reg::Rax, -4096),
+            I::Jcc(Cond::Above, Label::Named("allocation_error")),
+            I::Mov(TAPE, Reg::Rax),
+            I::MovImm(POINTER, 0),
+        ]);
+        if self.addressing == Addressing::Growable {
+            self.emit([
+                I::MovImm(Reg::Rax, (u64::from(Settings::MAX_LENGTH) * bytes) as i64),
+                I::Alu(AluOp::Add, TAPE, Reg::Rax),
+                I::MovImm(LOW, 0),
+                I::MovImm(HIGH, self.settings.tape_length().into()),
+            ]);
+        }
+        if self.settings.strict() {
+            self.emit([I::MovImm(CELL_MAX, self.settings.cell_width().max().into())]);
+        }
+    }
+
+    /// Returns the register holding the position of the cell `offset` cells away from the pointer.
+    ///
+    /// The position is computed in [`OTHER`], unless the offset is zero.
+    fn cell_at(&mut self, offset: i32) -> Reg {
+        if offset == 0 {
+            return POINTER;
+        }
+        let done = self.label();
+        match self.addressing {
+            Addressing::Wrapping => {
+                let length = self.settings.tape_length() as i32;
+                self.emit([
+                    I::Lea(OTHER, Mem::at(POINTER, offset.rem_euclid(length))),
+                    I::AluImm(AluOp::Cmp, OTHER, length),
+                    I::Jcc(Cond::Below, done),
+                    I::AluImm(AluOp::Sub, OTHER, length),
+                ]);
+            },
+            Addressing::Bounded => self.emit([
+                I::Lea(OTHER, Mem::at(POINTER, offset)),
+                // Negative positions are above the length when compared as unsigned.
+                I::AluImm(AluOp::Cmp, OTHER, self.settings.tape_length() as i32),
+                I::Jcc(Cond::AboveOrEqual, Label::Named("tape_overflow")),
+            ]),
+            Addressing::Growable => {
+                let outside = self.label();
+                self.emit([
+                    I::Lea(OTHER, Mem::at(POINTER, offset)),
+                    I::Alu(AluOp::Cmp, OTHER, LOW),
+                    I::Jcc(Cond::Less, outside),
+                    I::Alu(AluOp::Cmp, OTHER, HIGH),
+                    I::Jcc(Cond::Less, done),
+                    I::Bind(outside),
+                    I::Call(Label::Named("grow")),
+                ]);
+            },
+        }
+        self.emit([I::Bind(done)]);
+        OTHER
+    }
+
+    fn mut_cell(&mut self, offset: i32, delta: i32) {
+        let other = self.cell_at(offset);
+        if !self.settings.strict() {
+            let delta = delta as u32 & self.settings.cell_width().max();
+            self.emit([I::AddMemImm(self.size, self.cell(other), delta)]);
+            return;
+        }
+        // The `cell_overflow` routine expects the old value in `rax`, the new one in `rcx`
+        // and the position in `rsi`. Both values are computed on 64 bits, so they can't overflow.
+        if other != OTHER {
+            self.emit([I::Mov(OTHER, other)]);
+        }
+        self.emit([
+            I::Load(self.size, Reg::Rax, self.cell(OTHER)),
+            I::Lea(Reg::Rcx, Mem::at(Reg::Rax, delta)),
+            I::Alu(AluOp::Cmp, Reg::Rcx, CELL_MAX),
+            I::Jcc(Cond::Above, Label::Named("cell_overflow")),
+            I::Store(self.size, self.cell(OTHER), Reg::Rcx),
+        ]);
+    }
+
+    fn scan(&mut self, stride: i32) {
+        let (start, end) = (self.label(), self.label());
+        self.emit([
+            I::Bind(start),
+            I::CmpMemImm(self.size, self.cell(POINTER), 0),
+            I::Jcc(Cond::Equal, end),
+        ]);
+        let other = self.cell_at(stride);
+        self.emit([I::Mov(POINTER, other), I::Jmp(start), I::Bind(end)]);
+    }
+
+    /// Emits the `read` routine, which reads a byte into the current cell or handles the end of input.
+    fn read(&mut self) {
+        let (retry, eof) = (self.label(), self.label());
+        self.emit([
+            I::Bind(Label::Named("read")),
+            I::AluImm(AluOp::Sub, Reg::Rsp, 8),
+            I::Bind(retry),
+            I::MovImm(Reg::Rax, SYS_READ),
+            I::MovImm(Reg::Rdi, 0),
+            I::Mov(Reg::Rsi, Reg::Rsp),
+            I::MovImm(Reg::Rdx, 1),
+            I::Syscall,
+            I::AluImm(AluOp::Cmp, Reg::Rax, -EINTR),
+            I::Jcc(Cond::Equal, retry),
+            I::AluImm(AluOp::Cmp, Reg::Rax, 0),
+            I::Jcc(Cond::Less, Label::Named("input_error")),
+            I::Jcc(Cond::Equal, eof),
+            I::Load(Size::Byte, Reg::Rax, Mem::at(Reg::Rsp, 0)),
+            I::Store(self.size, self.cell(POINTER), Reg::Rax),
+            I::AluImm(AluOp::Add, Reg::Rsp, 8),
+            I::Ret,
+            I::Bind(eof),
+        ]);
+        match self.settings.eof_behavior() {
+            EofBehavior::Zero => self.emit([I::StoreImm(self.size, self.cell(POINTER), 0)]),
+            EofBehavior::Unchanged => (),
+            EofBehavior::Max => self.emit([I::StoreImm(
+                self.size,
+                self.cell(POINTER),
+                self.settings.cell_width().max(),
+            )]),
+        }
+        self.emit([I::AluImm(AluOp::Add, Reg::Rsp, 8), I::Ret]);
+
+        self.emit([I::Bind(Label::Named("input_error"))]);
+        self.os_error(
+            "input_error_message",
+            "RuntimeError: Could not read from input.\n",
+        );
+    }
+
+    /// Emits the `write` routine, which writes the least significant byte of the current cell.
+    fn write(&mut self) {
+        let retry = self.label();
+        self.emit([
+            I::Bind(Label::Named("write")),
+            I::Bind(retry),
+            I::MovImm(Reg::Rax, SYS_WRITE),
+            I::MovImm(Reg::Rdi, 1),
+            I::Lea(Reg::Rsi, self.cell(POINTER)),
+            I::MovImm(Reg::Rdx, 1),
+            I::Syscall,
+            I::AluImm(AluOp::Cmp, Reg::Rax, -EINTR),
+            I::Jcc(Cond::Equal, retry),
+            I::AluImm(AluOp::Cmp, Reg::Rax, 1),
+            I::Jcc(Cond::NotEqual, Label::Named("output_error")),
+            I::Ret,
+        ]);
+
+        self.emit([I::Bind(Label::Named("output_error"))]);
+        self.os_error(
+            "output_error_message",
+            "RuntimeError: Could not write to output.\n",
+        );
+    }
+
+    /// Prints the message followed by the error number negated in `rax`, then exits.
+    fn os_error(&mut self, name: &'static str, text: &'static str) {
+        self.emit([I::Neg(Reg::Rax), I::Mov(POINTER, Reg::Rax)]);
+        self.message(name, text);
+        self.message("os_error_message", "The underlying error was: os error ");
+        self.emit([
+            I::Mov(Reg::Rax, POINTER),
+            I::Call(Label::Named("print_int")),
+        ]);
+        self.message("end_message", ".\n");
+        self.emit([I::Jmp(Label::Named("fail"))]);
+    }
+
+    /// Emits the `grow` routine, which extends the bounds of the tape to contain the position in [`OTHER`].
+    fn grow(&mut self) {
+        let (least_found, right) = (self.label(), self.label());
+        self.emit([
+            I::Bind(Label::Named("grow")),
+            // The length of the tape in `rax`, the room left in `rcx`, the least growth in `rdx`.
+            I::Mov(Reg::Rax, HIGH),
+            I::Alu(AluOp::Sub, Reg::Rax, LOW),
+            I::MovImm(Reg::Rcx, Settings::MAX_LENGTH.into()),
+            I::Alu(AluOp::Sub, Reg::Rcx, Reg::Rax),
+            I::Mov(Reg::Rdx, Reg::Rax),
+            I::Alu(AluOp::Cmp, Reg::Rdx, Reg::Rcx),
+            I::Jcc(Cond::LessOrEqual, least_found),
+            I::Mov(Reg::Rdx, Reg::Rcx),
+            I::Bind(least_found),
+            // The index of the cell in `rdi`, negative if left of the tape.
+            I::Mov(Reg::Rdi, OTHER),
+            I::Alu(AluOp::Sub, Reg::Rdi, LOW),
+            I::AluImm(AluOp::Cmp, Reg::Rdi, 0),
+            I::Jcc(Cond::GreaterOrEqual, right),
+        ]);
+        if self.settings.tape_mode() == TapeMode::GrowBoth {
+            let added_found = self.label();
+            self.emit([
+                I::Neg(Reg::Rdi),
+                I::Alu(AluOp::Cmp, Reg::Rdi, Reg::Rcx),
+                I::Jcc(Cond::Greater, Label::Named("tape_overflow")),
+                I::Alu(AluOp::Cmp, Reg::Rdi, Reg::Rdx),
+                I::Jcc(Cond::GreaterOrEqual, added_found),
+                I::Mov(Reg::Rdi, Reg::Rdx),
+                I::Bind(added_found),
+                I::Alu(AluOp::Sub, LOW, Reg::Rdi),
+                I::Ret,
+            ]);
+        } else {
+            self.emit([I::Jmp(Label::Named("tape_overflow"))]);
+        }
+        let grown_found = self.label();
+        self.emit([
+            I::Bind(right),
+            I::Alu(AluOp::Add, Reg::Rcx, Reg::Rax),
+            I::Alu(AluOp::Cmp, Reg::Rdi, Reg::Rcx),
+            I::Jcc(Cond::GreaterOrEqual, Label::Named("tape_overflow")),
+            I::Alu(AluOp::Add, Reg::Rdx, Reg::Rax),
+            I::AluImm(AluOp::Add, Reg::Rdi, 1),
+            I::Alu(AluOp::Cmp, Reg::Rdi, Reg::Rdx),
+            I::Jcc(Cond::GreaterOrEqual, grown_found),
+            I::Mov(Reg::Rdi, Reg::Rdx),
+            I::Bind(grown_found),
+            I::Alu(AluOp::Add, Reg::Rdi, LOW),
+            I::Mov(HIGH, Reg::Rdi),
+            I::Ret,
+        ]);
+    }
+
+    /// Emits the `tape_overflow` routine, which reports moving from the pointer to the position in [`OTHER`].
+    fn tape_overflow(&mut self) {
+        self.emit([
+            I::Bind(Label::Named("tape_overflow")),
+            I::Mov(LOW, OTHER),
+            I::Alu(AluOp::Sub, LOW, POINTER),
+        ]);
+        self.message(
+            "tape_overflow_message",
+            "RuntimeError: Tape address overflowed.\nAttempted to change pointer equal to ",
+        );
+        self.emit([
+            I::Mov(Reg::Rax, POINTER),
+            I::Call(Label::Named("print_int")),
+        ]);
+        self.message("by_message", " by ");
+        self.emit([I::Mov(Reg::Rax, LOW), I::Call(Label::Named("print_int"))]);
+        self.message("end_message", ".\n");
+        self.emit([I::Jmp(Label::Named("fail"))]);
+    }
+
+    /// Emits the `cell_overflow` routine, see [`Compiler::mut_cell`] for its inputs.
+    fn cell_overflow(&mut self) {
+        self.emit([
+            I::Bind(Label::Named("cell_overflow")),
+            I::Mov(POINTER, Reg::Rax),
+            I::Mov(LOW, Reg::Rcx),
+            I::Alu(AluOp::Sub, LOW, Reg::Rax),
+            I::Mov(HIGH, OTHER),
+        ]);
+        self.message(
+            "cell_overflow_message",
+            "RuntimeError: Cell overflowed.\nAttempted to change a cell equal to ",
+        );
+        self.emit([
+            I::Mov(Reg::Rax, POINTER),
+            I::Call(Label::Named("print_int")),
+        ]);
+        self.message("by_message", " by ");
+        self.emit([I::Mov(Reg::Rax, LOW), I::Call(Label::Named("print_int"))]);
+        self.message("at_message", " at address ");
+        self.emit([I::Mov(Reg::Rax, HIGH), I::Call(Label::Named("print_int"))]);
+        self.message("end_message", ".\n");
+        self.emit([I::Jmp(Label::Named("fail"))]);
+    }
+
+    /// Emits the routines shared by all of the errors, which print to `stderr` and exit with a failure.
+    fn failures(&mut self) {
+        self.emit([I::Bind(Label::Named("allocation_error"))]);
+        self.message(
+            "allocation_error_message",
+            "RuntimeError: Could not allocate the tape.\n",
+        );
+        self.emit([
+            I::Bind(Label::Named("fail")),
+            I::MovImm(Reg::Rax, SYS_EXIT),
+            I::MovImm(Reg::Rdi, 1),
+            I::Syscall,
+        ]);
+
+        // Prints `rdx` bytes starting at `rsi`.
+        self.emit([
+            I::Bind(Label::Named("print")),
+            I::MovImm(Reg::Rax, SYS_WRITE),
+            I::MovImm(Reg::Rdi, 2),
+            I::Syscall,
+            I::Ret,
+        ]);
+
+        // Prints the signed integer in `rax`, writing its digits from the end of a buffer on the stack.
+        let (positive, digit, printed) = (self.label(), self.label(), self.label());
+        self.emit([
+            I::Bind(Label::Named("print_int")),
+            I::AluImm(AluOp::Sub, Reg::Rsp, 32),
+            I::Lea(Reg::Rsi, Mem::at(Reg::Rsp, 32)),
+            I::Mov(Reg::R8, Reg::Rax),
+            I::AluImm(AluOp::Cmp, Reg::Rax, 0),
+            I::Jcc(Cond::GreaterOrEqual, positive),
+            I::Neg(Reg::Rax),
+            I::Bind(positive),
+            I::MovImm(Reg::Rcx, 10),
+            I::Bind(digit),
+            I::MovImm(Reg::Rdx, 0),
+            I::Div(Reg::Rcx),
+            I::AluImm(AluOp::Add, Reg::Rdx, i32::from(b'0')),
+            I::AluImm(AluOp::Sub, Reg::Rsi, 1),
+            I::Store(Size::Byte, Mem::at(Reg::Rsi, 0), Reg::Rdx),
+            I::AluImm(AluOp::Cmp, Reg::Rax, 0),
+            I::Jcc(Cond::NotEqual, digit),
+            I::AluImm(AluOp::Cmp, Reg::R8, 0),
+            I::Jcc(Cond::GreaterOrEqual, printed),
+            I::AluImm(AluOp::Sub, Reg::Rsi, 1),
+            I::StoreImm(Size::Byte, Mem::at(Reg::Rsi, 0), b'-'.into()),
+            I::Bind(printed),
+            I::Lea(Reg::Rdx, Mem::at(Reg::Rsp, 32)),
+            I::Alu(AluOp::Sub, Reg::Rdx, Reg::Rsi),
+            I::Call(Label::Named("print")),
+            I::AluImm(AluOp::Add, Reg::Rsp, 32),
+            I::Ret,
+        ]);
+    }
+
+    /// Prints a message to `stderr`, storing it under the `name` label if it wasn't stored yet.
+    fn message(&mut self, name: &'static str, text: &'static str) {
+        let label = Label::Named(name);
+        if !self.data.iter().any(|&(stored, _)| stored == label) {
+            self.data.push((label, text));
+        }
+        self.emit([
+            I::LeaLabel(Reg::Rsi, label),
+            I::MovImm(Reg::Rdx, text.len() as i64),
+            I::Call(Label::Named("print")),
+        ]);
+    }
+}
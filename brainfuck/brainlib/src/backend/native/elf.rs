@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use super::instruction::{Instr, Label};
+
+/// The virtual address at which the file is mapped, the usual one for non-relocatable executables.
+const BASE_ADDRESS: u64 = 0x40_0000;
+
+const ELF_HEADER_SIZE: u16 = 64;
+const PROGRAM_HEADER_SIZE: u16 = 56;
+const SECTION_HEADER_SIZE: u16 = 64;
+
+/// Assembles the code followed by the data into a static ELF executable for x86-64 Linux.
+///
+/// The whole file is mapped as a single readable and executable segment, the code starts right after the headers.
+pub(super) fn link(code: &[Instr], data: &[(Label, &str)]) -> Vec<u8> {
+    let headers = usize::from(ELF_HEADER_SIZE + PROGRAM_HEADER_SIZE);
+    let mut file = vec![0; headers];
+    let mut labels = HashMap::new();
+    let mut fixups = Vec::new();
+
+    for instr in code {
+        if let Instr::Bind(label) = *instr {
+            labels.insert(label, file.len());
+        }
+        if let Some(label) = instr.encode(&mut file) {
+            fixups.push((file.len() - 4, label));
+        }
+    }
+    for &(label, text) in data {
+        labels.insert(label, file.len());
+        file.extend_from_slice(text.as_bytes());
+    }
+    for (position, label) in fixups {
+        let target = labels[&label];
+        let displacement = target as i64 - (position as i64 + 4);
+        let displacement = i32::try_from(displacement).expect("code too large for 32-bit jumps");
+        file[position..position + 4].copy_from_slice(&displacement.to_le_bytes());
+    }
+
+    let mut header = Vec::with_capacity(headers);
+    // Identification: magic number, 64-bit, little endian, version 1, System V ABI.
+    header.extend_from_slice(&[0x7F, b'E', b'L', b'F', 2, 1, 1, 0]);
+    header.extend_from_slice(&[0; 8]);
+    header.extend_from_slice(&2u16.to_le_bytes()); // Executable file.
+    header.extend_from_slice(&62u16.to_le_bytes()); // x86-64.
+    header.extend_from_slice(&1u32.to_le_bytes()); // Version.
+    header.extend_from_slice(&(BASE_ADDRESS + headers as u64).to_le_bytes()); // Entry point.
+    header.extend_from_slice(&u64::from(ELF_HEADER_SIZE).to_le_bytes()); // Program headers offset.
+    header.extend_from_slice(&0u64.to_le_bytes()); // No section headers.
+    header.extend_from_slice(&0u32.to_le_bytes()); // Flags.
+    header.extend_from_slice(&ELF_HEADER_SIZE.to_le_bytes());
+    header.extend_from_slice(&PROGRAM_HEADER_SIZE.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes()); // Program header count.
+    header.extend_from_slice(&SECTION_HEADER_SIZE.to_le_bytes());
+    header.extend_from_slice(&0u16.to_le_bytes()); // Section header count.
+    header.extend_from_slice(&0u16.to_le_bytes()); // Section names index.
+
+    let size = file.len() as u64;
+    header.extend_from_slice(&1u32.to_le_bytes()); // Loadable segment.
+    header.extend_from_slice(&5u32.to_le_bytes()); // Readable and executable.
+    header.extend_from_slice(&0u64.to_le_bytes()); // File offset.
+    header.extend_from_slice(&BASE_ADDRESS.to_le_bytes()); // Virtual address.
+    header.extend_from_slice(&BASE_ADDRESS.to_le_bytes()); // Physical address.
+    header.extend_from_slice(&size.to_le_bytes()); // Size in the file.
+    header.extend_from_slice(&size.to_le_bytes()); // Size in memory.
+    header.extend_from_slice(&0x1000u64.to_le_bytes()); // Alignment.
+
+    debug_assert_eq!(header.len(), headers);
+    file[..headers].copy_from_slice(&header);
+    file
+}
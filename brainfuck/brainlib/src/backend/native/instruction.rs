@@ -0,0 +1,510 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::CellWidth;
+
+/// General purpose registers, numbered as in their encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Reg {
+    Rax = 0,
+    Rcx = 1,
+    Rdx = 2,
+    Rbx = 3,
+    Rsp = 4,
+    Rbp = 5,
+    Rsi = 6,
+    Rdi = 7,
+    R8 = 8,
+    R9 = 9,
+    R10 = 10,
+    R12 = 12,
+    R13 = 13,
+    R14 = 14,
+    R15 = 15,
+}
+
+impl Reg {
+    const NAMES: [[&'static str; 4]; 16] = [
+        ["al", "ax", "eax", "rax"],
+        ["cl", "cx", "ecx", "rcx"],
+        ["dl", "dx", "edx", "rdx"],
+        ["bl", "bx", "ebx", "rbx"],
+        ["spl", "sp", "esp", "rsp"],
+        ["bpl", "bp", "ebp", "rbp"],
+        ["sil", "si", "esi", "rsi"],
+        ["dil", "di", "edi", "rdi"],
+        ["r8b", "r8w", "r8d", "r8"],
+        ["r9b", "r9w", "r9d", "r9"],
+        ["r10b", "r10w", "r10d", "r10"],
+        ["r11b", "r11w", "r11d", "r11"],
+        ["r12b", "r12w", "r12d", "r12"],
+        ["r13b", "r13w", "r13d", "r13"],
+        ["r14b", "r14w", "r14d", "r14"],
+        ["r15b", "r15w", "r15d", "r15"],
+    ];
+
+    const fn name(self, size: Size) -> &'static str {
+        Self::NAMES[self as usize][size as usize]
+    }
+
+    /// The three bits of the register number placed in the ModRM or SIB byte.
+    const fn low(self) -> u8 {
+        self as u8 & 7
+    }
+
+    /// The bit of the register number placed in the REX prefix.
+    const fn high(self) -> u8 {
+        self as u8 >> 3
+    }
+}
+
+/// The size of an operand, in the order of the columns of [`Reg::NAMES`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Size {
+    Byte,
+    Word,
+    Dword,
+    Qword,
+}
+
+impl Size {
+    const fn keyword(self) -> &'static str {
+        match self {
+            Self::Byte => "byte",
+            Self::Word => "word",
+            Self::Dword => "dword",
+            Self::Qword => "qword",
+        }
+    }
+}
+
+impl From<CellWidth> for Size {
+    fn from(cell_width: CellWidth) -> Self {
+        match cell_width {
+            CellWidth::U8 => Self::Byte,
+            CellWidth::U16 => Self::Word,
+            CellWidth::U32 => Self::Dword,
+        }
+    }
+}
+
+/// A memory operand, `[base + index * scale + disp]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Mem {
+    pub(super) base: Reg,
+    /// The index register, which can't be `rsp`, with its scale of 1, 2, 4 or 8.
+    pub(super) index: Option<(Reg, u8)>,
+    pub(super) disp: i32,
+}
+
+impl Mem {
+    /// `[base + disp]`
+    pub(super) const fn at(base: Reg, disp: i32) -> Self {
+        Self {
+            base,
+            index: None,
+            disp,
+        }
+    }
+
+    /// `[base + index * scale]`
+    pub(super) const fn indexed(base: Reg, index: Reg, scale: u8) -> Self {
+        Self {
+            base,
+            index: Some((index, scale)),
+            disp: 0,
+        }
+    }
+}
+
+impl Display for Mem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}", self.base.name(Size::Qword))?;
+        match self.index {
+            Some((index, 1)) => write!(f, " + {}", index.name(Size::Qword))?,
+            Some((index, scale)) => write!(f, " + {}*{scale}", index.name(Size::Qword))?,
+            None => (),
+        }
+        match self.disp {
+            0 => write!(f, "]"),
+            disp if disp < 0 => write!(f, " - {}]", disp.unsigned_abs()),
+            disp => write!(f, " + {disp}]"),
+        }
+    }
+}
+
+/// Condition codes of the conditional jumps, the comparisons are unsigned (above, below) or signed (greater, less).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Cond {
+    Below = 0x2,
+    AboveOrEqual = 0x3,
+    Equal = 0x4,
+    NotEqual = 0x5,
+    Above = 0x7,
+    Less = 0xC,
+    GreaterOrEqual = 0xD,
+    LessOrEqual = 0xE,
+    Greater = 0xF,
+}
+
+impl Cond {
+    const fn mnemonic(self) -> &'static str {
+        match self {
+            Self::Below => "jb",
+            Self::AboveOrEqual => "jae",
+            Self::Equal => "je",
+            Self::NotEqual => "jne",
+            Self::Above => "ja",
+            Self::Less => "jl",
+            Self::GreaterOrEqual => "jge",
+            Self::LessOrEqual => "jle",
+            Self::Greater => "jg",
+        }
+    }
+}
+
+/// A position in the code or in the data, which may not be known yet when it is referenced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) enum Label {
+    /// A label of a routine or a message, named for the readability of the assembly.
+    Named(&'static str),
+    /// A label inside of the code, such as the start or the end of a loop.
+    Numbered(usize),
+}
+
+impl Display for Label {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Named(name) => write!(f, ".L{name}"),
+            Self::Numbered(number) => write!(f, ".L{number}"),
+        }
+    }
+}
+
+/// Arithmetic operations on two 64-bit registers, or a register and a sign extended 32-bit immediate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum AluOp {
+    Add,
+    Sub,
+    Cmp,
+}
+
+impl AluOp {
+    const fn mnemonic(self) -> &'static str {
+        match self {
+            Self::Add => "add",
+            Self::Sub => "sub",
+            Self::Cmp => "cmp",
+        }
+    }
+
+    /// The opcode of the `op r/m64, r64` form.
+    const fn opcode(self) -> u8 {
+        match self {
+            Self::Add => 0x01,
+            Self::Sub => 0x29,
+            Self::Cmp => 0x39,
+        }
+    }
+
+    /// The opcode extension of the `op r/m, imm` form.
+    const fn extension(self) -> u8 {
+        match self {
+            Self::Add => 0,
+            Self::Sub => 5,
+            Self::Cmp => 7,
+        }
+    }
+}
+
+/// The x86-64 instructions used by the native backend, along with label definitions.
+///
+/// Each instruction can be written as GNU assembler code in the Intel syntax with [`Display`],
+/// or encoded as machine code with [`Instr::encode`]. All of the jumps use 32-bit displacements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Instr {
+    /// Defines the label at the current position.
+    Bind(Label),
+    /// `mov dst, imm`
+    MovImm(Reg, i64),
+    /// `mov dst, src`
+    Mov(Reg, Reg),
+    /// `op dst, src`
+    Alu(AluOp, Reg, Reg),
+    /// `op dst, imm32`
+    AluImm(AluOp, Reg, i32),
+    /// `neg dst`
+    Neg(Reg),
+    /// `div src`, dividing `rdx:rax` and storing the quotient in `rax` and the remainder in `rdx`.
+    Div(Reg),
+    /// `imul dst32, src32, imm32`
+    Imul(Reg, Reg, i32),
+    /// `lea dst, [mem]`
+    Lea(Reg, Mem),
+    /// `lea dst, [rip + label]`
+    LeaLabel(Reg, Label),
+    /// `movzx dst32, size [mem]`, or a plain `mov` for a double word.
+    Load(Size, Reg, Mem),
+    /// `mov size [mem], src`
+    Store(Size, Mem, Reg),
+    /// `mov size [mem], imm`
+    StoreImm(Size, Mem, u32),
+    /// `add size [mem], src`
+    AddMem(Size, Mem, Reg),
+    /// `add size [mem], imm`
+    AddMemImm(Size, Mem, u32),
+    /// `cmp size [mem], imm`
+    CmpMemImm(Size, Mem, u32),
+    Jmp(Label),
+    Jcc(Cond, Label),
+    Call(Label),
+    Ret,
+    Syscall,
+}
+
+impl Display for Instr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        use Size::{Dword, Qword};
+
+        let ptr = |size: Size, mem: Mem| format!("{} ptr {mem}", size.keyword());
+        match *self {
+            Self::Bind(label) => write!(f, "{label}:"),
+            Self::MovImm(dst, imm) => write!(f, "mov {}, {imm}", dst.name(Qword)),
+            Self::Mov(dst, src) => write!(f, "mov {}, {}", dst.name(Qword), src.name(Qword)),
+            Self::Alu(op, dst, src) => write!(
+                f,
+                "{} {}, {}",
+                op.mnemonic(),
+                dst.name(Qword),
+                src.name(Qword)
+            ),
+            Self::AluImm(op, dst, imm) => write!(f, "{} {}, {imm}", op.mnemonic(), dst.name(Qword)),
+            Self::Neg(dst) => write!(f, "neg {}", dst.name(Qword)),
+            Self::Div(src) => write!(f, "div {}", src.name(Qword)),
+            Self::Imul(dst, src, imm) =>
+                write!(f, "imul {}, {}, {imm}", dst.name(Dword), src.name(Dword)),
+            Self::Lea(dst, mem) => write!(f, "lea {}, {mem}", dst.name(Qword)),
+            Self::LeaLabel(dst, label) => write!(f, "lea {}, [rip + {label}]", dst.name(Qword)),
+            Self::Load(Dword, dst, mem) =>
+                write!(f, "mov {}, {}", dst.name(Dword), ptr(Dword, mem)),
+            Self::Load(size, dst, mem) =>
+                write!(f, "movzx {}, {}", dst.name(Dword), ptr(size, mem)),
+            Self::Store(size, mem, src) => write!(f, "mov {}, {}", ptr(size, mem), src.name(size)),
+            Self::StoreImm(size, mem, imm) => write!(f, "mov {}, {imm}", ptr(size, mem)),
+            Self::AddMem(size, mem, src) => write!(f, "add {}, {}", ptr(size, mem), src.name(size)),
+            Self::AddMemImm(size, mem, imm) => write!(f, "add {}, {imm}", ptr(size, mem)),
+            Self::CmpMemImm(size, mem, imm) => write!(f, "cmp {}, {imm}", ptr(size, mem)),
+            Self::Jmp(label) => write!(f, "jmp {label}"),
+            Self::Jcc(cond, label) => write!(f, "{} {label}", cond.mnemonic()),
+            Self::Call(label) => write!(f, "call {label}"),
+            Self::Ret => write!(f, "ret"),
+            Self::Syscall => write!(f, "syscall"),
+        }
+    }
+}
+
+impl Instr {
+    /// Appends the machine code of the instruction to `code`.
+    ///
+    /// Returns the label referenced by the instruction, if any. Its 32-bit displacement, relative to the end
+    /// of the instruction, is then placed in the last four bytes of the code, and has to be filled in later.
+    pub(super) fn encode(self, code: &mut Vec<u8>) -> Option<Label> {
+        let mut out = Encoder(code);
+        match self {
+            Self::Bind(_) => (),
+            Self::MovImm(dst, imm) => {
+                if let Ok(imm) = u32::try_from(imm) {
+                    // Writing the lower half of a register clears its upper half.
+                    out.rex(false, 0, 0, dst.high(), false);
+                    out.bytes(&[0xB8 | dst.low()]);
+                    out.bytes(&imm.to_le_bytes());
+                } else if let Ok(imm) = i32::try_from(imm) {
+                    out.op_reg(true, &[0xC7], 0, dst);
+                    out.bytes(&imm.to_le_bytes());
+                } else {
+                    out.rex(true, 0, 0, dst.high(), false);
+                    out.bytes(&[0xB8 | dst.low()]);
+                    out.bytes(&imm.to_le_bytes());
+                }
+            },
+            Self::Mov(dst, src) => out.op_reg(true, &[0x89], src as u8, dst),
+            Self::Alu(op, dst, src) => out.op_reg(true, &[op.opcode()], src as u8, dst),
+            Self::AluImm(op, dst, imm) => {
+                out.op_reg(true, &[0x81], op.extension(), dst);
+                out.bytes(&imm.to_le_bytes());
+            },
+            Self::Neg(dst) => out.op_reg(true, &[0xF7], 3, dst),
+            Self::Div(src) => out.op_reg(true, &[0xF7], 6, src),
+            Self::Imul(dst, src, imm) => {
+                out.op_reg(false, &[0x69], dst as u8, src);
+                out.bytes(&imm.to_le_bytes());
+            },
+            Self::Lea(dst, mem) => out.op_mem(None, true, &[0x8D], dst as u8, mem),
+            Self::LeaLabel(dst, label) => {
+                out.rex(true, dst.high(), 0, 0, false);
+                out.bytes(&[0x8D, (dst.low() << 3) | 0b101]);
+                out.bytes(&[0; 4]);
+                return Some(label);
+            },
+            Self::Load(size, dst, mem) => {
+                let opcode: &[u8] = match size {
+                    Size::Byte => &[0x0F, 0xB6],
+                    Size::Word => &[0x0F, 0xB7],
+                    Size::Dword | Size::Qword => &[0x8B],
+                };
+                out.op_mem(None, size == Size::Qword, opcode, dst as u8, mem);
+            },
+            Self::Store(size, mem, src) => {
+                let opcode = if size == Size::Byte { 0x88 } else { 0x89 };
+                out.op_mem(Some(size), false, &[opcode], src as u8, mem);
+            },
+            Self::StoreImm(size, mem, imm) => {
+                let opcode = if size == Size::Byte { 0xC6 } else { 0xC7 };
+                out.op_mem(Some(size), false, &[opcode], 0, mem);
+                out.imm(size, imm);
+            },
+            Self::AddMem(size, mem, src) => {
+                let opcode = if size == Size::Byte { 0x00 } else { 0x01 };
+                out.op_mem(Some(size), false, &[opcode], src as u8, mem);
+            },
+            Self::AddMemImm(size, mem, imm) | Self::CmpMemImm(size, mem, imm) => {
+                let extension = if matches!(self, Self::AddMemImm(..)) {
+                    0
+                } else {
+                    7
+                };
+                let opcode = if size == Size::Byte { 0x80 } else { 0x81 };
+                out.op_mem(Some(size), false, &[opcode], extension, mem);
+                out.imm(size, imm);
+            },
+            Self::Jmp(label) => {
+                out.bytes(&[0xE9, 0, 0, 0, 0]);
+                return Some(label);
+            },
+            Self::Jcc(cond, label) => {
+                out.bytes(&[0x0F, 0x80 | cond as u8, 0, 0, 0, 0]);
+                return Some(label);
+            },
+            Self::Call(label) => {
+                out.bytes(&[0xE8, 0, 0, 0, 0]);
+                return Some(label);
+            },
+            Self::Ret => out.bytes(&[0xC3]),
+            Self::Syscall => out.bytes(&[0x0F, 0x05]),
+        }
+        None
+    }
+}
+
+/// Writes the parts of the encoded instructions.
+struct Encoder<'c>(&'c mut Vec<u8>);
+
+impl Encoder<'_> {
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    /// Writes an immediate of the given size.
+    fn imm(&mut self, size: Size, imm: u32) {
+        match size {
+            Size::Byte => self.bytes(&[imm as u8]),
+            Size::Word => self.bytes(&(imm as u16).to_le_bytes()),
+            Size::Dword | Size::Qword => self.bytes(&imm.to_le_bytes()),
+        }
+    }
+
+    /// Writes a REX prefix if any of its bits are set, or if `force`d to access the low byte of `rsp` to `rdi`.
+    fn rex(&mut self, wide: bool, reg: u8, index: u8, base: u8, force: bool) {
+        let rex = 0x40 | u8::from(wide) << 3 | reg << 2 | index << 1 | base;
+        if rex != 0x40 || force {
+            self.bytes(&[rex]);
+        }
+    }
+
+    /// Writes an instruction with a register operand and a register or opcode extension `reg`.
+    fn op_reg(&mut self, wide: bool, opcode: &[u8], reg: u8, rm: Reg) {
+        self.rex(wide, reg >> 3, 0, rm.high(), false);
+        self.bytes(opcode);
+        self.bytes(&[0xC0 | (reg & 7) << 3 | rm.low()]);
+    }
+
+    /// Writes an instruction with a memory operand and a register or opcode extension `reg`.
+    ///
+    /// The operand size prefix is added for words, and the REX prefix is forced for byte registers which need it.
+    fn op_mem(&mut self, size: Option<Size>, wide: bool, opcode: &[u8], reg: u8, mem: Mem) {
+        if size == Some(Size::Word) {
+            self.bytes(&[0x66]);
+        }
+        let index = mem.index.map_or(0, |(index, _)| index.high());
+        let force = size == Some(Size::Byte) && (4..8).contains(&reg);
+        self.rex(wide, reg >> 3, index, mem.base.high(), force);
+        self.bytes(opcode);
+
+        // `rbp` and `r13` can't be used as a base without a displacement.
+        let mode = match mem.disp {
+            0 if mem.base.low() != Reg::Rbp.low() => 0b00,
+            disp if i8::try_from(disp).is_ok() => 0b01,
+            _ => 0b10,
+        };
+        let reg = (reg & 7) << 3;
+        match mem.index {
+            // `rsp` and `r12` can only be used as a base with a SIB byte.
+            None if mem.base.low() != Reg::Rsp.low() =>
+                self.bytes(&[mode << 6 | reg | mem.base.low()]),
+            None => self.bytes(&[mode << 6 | reg | 0b100, 0x24]),
+            Some((index, scale)) => {
+                debug_assert_ne!(index, Reg::Rsp, "rsp can't be an index");
+                let scale = scale.trailing_zeros() as u8;
+                self.bytes(&[mode << 6 | reg | 0b100]);
+                self.bytes(&[scale << 6 | index.low() << 3 | mem.base.low()]);
+            },
+        }
+        match mode {
+            0b01 => self.bytes(&[mem.disp as u8]),
+            0b10 => self.bytes(&mem.disp.to_le_bytes()),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::{AluOp, Cond, Instr, Label, Mem, Reg, Size};
+
+    #[test_case(Instr::MovImm(Reg::Rax, 60), "mov rax, 60", &[0xB8, 60, 0, 0, 0]; "mov zero extended")]
+    #[test_case(Instr::MovImm(Reg::R8, -1), "mov r8, -1", &[0x49, 0xC7, 0xC0, 0xFF, 0xFF, 0xFF, 0xFF]; "mov sign extended")]
+    #[test_case(Instr::MovImm(Reg::Rsi, 1 << 33), "mov rsi, 8589934592", &[0x48, 0xBE, 0, 0, 0, 0, 2, 0, 0, 0]; "movabs")]
+    #[test_case(Instr::Mov(Reg::R12, Reg::Rsi), "mov r12, rsi", &[0x49, 0x89, 0xF4]; "mov")]
+    #[test_case(Instr::Alu(AluOp::Cmp, Reg::Rsi, Reg::R13), "cmp rsi, r13", &[0x4C, 0x39, 0xEE]; "cmp")]
+    #[test_case(Instr::AluImm(AluOp::Sub, Reg::Rsp, 8), "sub rsp, 8", &[0x48, 0x81, 0xEC, 8, 0, 0, 0]; "sub immediate")]
+    #[test_case(Instr::Div(Reg::Rcx), "div rcx", &[0x48, 0xF7, 0xF1]; "div")]
+    #[test_case(Instr::Imul(Reg::Rax, Reg::Rax, -3), "imul eax, eax, -3", &[0x69, 0xC0, 0xFD, 0xFF, 0xFF, 0xFF]; "imul")]
+    #[test_case(Instr::Lea(Reg::Rsi, Mem::at(Reg::R12, -2)), "lea rsi, [r12 - 2]", &[0x49, 0x8D, 0x74, 0x24, 0xFE]; "lea")]
+    #[test_case(Instr::Load(Size::Byte, Reg::Rax, Mem::at(Reg::Rsp, 0)), "movzx eax, byte ptr [rsp]", &[0x0F, 0xB6, 0x04, 0x24]; "load from stack")]
+    #[test_case(Instr::Load(Size::Word, Reg::Rax, Mem::indexed(Reg::Rbx, Reg::R12, 2)), "movzx eax, word ptr [rbx + r12*2]", &[0x42, 0x0F, 0xB7, 0x04, 0x63]; "load word")]
+    #[test_case(Instr::Store(Size::Byte, Mem::at(Reg::Rsi, 0), Reg::Rdx), "mov byte ptr [rsi], dl", &[0x88, 0x16]; "store byte")]
+    #[test_case(Instr::Store(Size::Byte, Mem::indexed(Reg::Rbx, Reg::Rsi, 1), Reg::Rsi), "mov byte ptr [rbx + rsi], sil", &[0x40, 0x88, 0x34, 0x33]; "store low byte of rsi")]
+    #[test_case(Instr::StoreImm(Size::Word, Mem::indexed(Reg::Rbx, Reg::Rsi, 2), 7), "mov word ptr [rbx + rsi*2], 7", &[0x66, 0xC7, 0x04, 0x73, 7, 0]; "store word immediate")]
+    #[test_case(Instr::AddMem(Size::Dword, Mem::indexed(Reg::Rbx, Reg::R12, 4), Reg::Rax), "add dword ptr [rbx + r12*4], eax", &[0x42, 0x01, 0x04, 0xA3]; "add to memory")]
+    #[test_case(Instr::AddMemImm(Size::Byte, Mem::indexed(Reg::Rbx, Reg::R12, 1), 255), "add byte ptr [rbx + r12], 255", &[0x42, 0x80, 0x04, 0x23, 0xFF]; "add immediate to memory")]
+    #[test_case(Instr::CmpMemImm(Size::Dword, Mem::at(Reg::Rbp, 0), 0), "cmp dword ptr [rbp], 0", &[0x81, 0x7D, 0x00, 0, 0, 0, 0]; "cmp memory at rbp")]
+    #[test_case(Instr::Syscall, "syscall", &[0x0F, 0x05]; "syscall")]
+    fn encodes_instructions(instr: Instr, text: &str, code: &[u8]) {
+        let mut encoded = Vec::new();
+        assert_eq!(instr.encode(&mut encoded), None);
+        assert_eq!(instr.to_string(), text);
+        assert_eq!(encoded, code);
+    }
+
+    #[test_case(Instr::Jcc(Cond::Less, Label::Numbered(3)), "jl .L3", &[0x0F, 0x8C]; "jcc")]
+    #[test_case(Instr::Call(Label::Named("grow")), "call .Lgrow", &[0xE8]; "call")]
+    #[test_case(Instr::LeaLabel(Reg::Rsi, Label::Named("message")), "lea rsi, [rip + .Lmessage]", &[0x48, 0x8D, 0x35]; "lea rip relative")]
+    fn leaves_room_for_displacement(instr: Instr, text: &str, opcode: &[u8]) {
+        let mut encoded = Vec::new();
+        assert!(instr.encode(&mut encoded).is_some());
+        assert_eq!(instr.to_string(), text);
+        assert_eq!(encoded[..encoded.len() - 4], *opcode);
+        assert_eq!(encoded[encoded.len() - 4..], [0; 4]);
+    }
+}
@@ -0,0 +1,32 @@
+//! Functionalities related to compilation from Brainfuck to native x86-64 Linux executables.
+//!
+//! The features are exposed through the [`NativeModule`] type, which can be emitted either as GNU assembler
+//! source code, or directly as a static ELF executable, without the need for an assembler or a linker.
+//!
+//! Both are generated from the same instructions, and only run on x86-64 Linux, but can be emitted on any platform.
+//!
+//! # Examples
+//! ```
+//! # use std::io::stdout;
+//! use brainlib::{native::NativeModule, Program, Settings};
+//!
+//! let settings = Settings::default();
+//! let source = ",[.,]";
+//! let program = Program::compile(source, &settings)?;
+//!
+//! let module = NativeModule::compile_from(&program, &settings);
+//! module.emit_asm(stdout())?;
+//!
+//! let mut elf = Vec::new();
+//! module.emit_elf(&mut elf)?;
+//! assert!(elf.starts_with(b"\x7FELF"));
+//!
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+mod compiler;
+mod elf;
+mod instruction;
+mod native_module;
+
+pub use self::native_module::NativeModule;
@@ -0,0 +1,68 @@
+use std::io::{self, Write};
+
+use super::{
+    compiler::Compiler,
+    elf,
+    instruction::{Instr, Label},
+};
+use crate::{Program, Settings};
+
+/// Represents a standalone x86-64 Linux executable compiled from a given [`Program`].
+///
+/// The executable can be created from a [`Program`] using [`NativeModule::compile_from`],
+/// and emitted using [`NativeModule::emit_asm`] or [`NativeModule::emit_elf`].
+///
+/// It doesn't depend on any library, the input, output and tape allocation use the `read`, `write`
+/// and `mmap` system calls directly.
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NativeModule {
+    code: Vec<Instr>,
+    data: Vec<(Label, &'static str)>,
+}
+
+impl NativeModule {
+    /// Creates a new [`NativeModule`] from a [`Program`].
+    ///
+    /// The code behaves like the interpreter for the given [`Settings`]. The runtime errors
+    /// (overflows in `strict` mode, failed I/O) print a diagnostic to `stderr` and exit with code 1.
+    ///
+    /// All programs produce a valid executable, panics can only occur due to internal errors.
+    pub fn compile_from(program: &Program, settings: &Settings) -> Self {
+        let (code, data) = Compiler::new(settings).compile(program);
+        Self { code, data }
+    }
+
+    /// Emits the GNU assembler source code to the given [`Write`].
+    ///
+    /// The source uses the Intel syntax and defines the `_start` symbol, so it can be built without
+    /// the C runtime, e.g. `as -o program.o program.s && ld -o program program.o`.
+    pub fn emit_asm(&self, mut write: impl Write) -> io::Result<()> {
+        writeln!(write, "    .intel_syntax noprefix")?;
+        writeln!(write, "    .globl _start")?;
+        writeln!(write)?;
+        writeln!(write, "    .text")?;
+        writeln!(write, "_start:")?;
+        for instr in &self.code {
+            match instr {
+                Instr::Bind(_) => writeln!(write, "{instr}")?,
+                _ => writeln!(write, "    {instr}")?,
+            }
+        }
+        writeln!(write)?;
+        writeln!(write, "    .section .rodata")?;
+        for (label, text) in &self.data {
+            writeln!(write, "{label}:")?;
+            writeln!(write, "    .ascii {text:?}")?;
+        }
+        Ok(())
+    }
+
+    /// Emits the static ELF executable to the given [`Write`].
+    ///
+    /// The machine code is the same as the one assembled from [`NativeModule::emit_asm`],
+    /// except for the choice between short and long jumps.
+    pub fn emit_elf(&self, mut write: impl Write) -> io::Result<()> {
+        write.write_all(&elf::link(&self.code, &self.data))
+    }
+}
@@ -0,0 +1,34 @@
+//! Functionalities related to transpilation from Brainfuck to Rust.
+//!
+//! The features are exposed through the [`RustSource`] and [`RustTarget`] types.
+//!
+//! The produced code is self-contained, it only depends on the standard library, so it can be vendored
+//! in other crates. It behaves exactly like [`Engine`](crate::interpreter::Engine) with the same [`Settings`](crate::Settings),
+//! reading from any [`Read`](std::io::Read) and writing to any [`Write`](std::io::Write).
+//!
+//! # Examples
+//! ```
+//! # use std::io::stdout;
+//! use brainlib::{
+//!     rust::{RustSource, RustTarget},
+//!     Program, Settings,
+//! };
+//!
+//! let settings = Settings::default();
+//! let source = ",[.,]";
+//! let program = Program::compile(source, &settings)?;
+//!
+//! let module = RustSource::compile_from(&program, RustTarget::Module, &settings);
+//! module.emit(stdout())?;
+//!
+//! let mut text = Vec::new();
+//! RustSource::compile_from(&program, RustTarget::Binary, &settings).emit(&mut text)?;
+//! assert!(String::from_utf8(text)?.contains("fn main() -> ExitCode {"));
+//!
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+mod rust_source;
+mod rust_target;
+
+pub use self::{rust_source::RustSource, rust_target::RustTarget};
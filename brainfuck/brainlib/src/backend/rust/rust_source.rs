@@ -0,0 +1,411 @@
+use std::io::{self, Write};
+
+use crate::{
+    backend::common::{
+        Addressing, CellWidth, EofBehavior, Instruction as RI, Program, Settings, SourceWriter,
+        TapeMode,
+    },
+    rust::RustTarget,
+};
+
+/// Represents the Rust source code of a given [`Program`].
+///
+/// The source can be created from a [`Program`] using [`RustSource::compile_from`],
+/// and emitted using [`RustSource::emit`].
+///
+/// The produced code only depends on the Rust standard library, and compiles without warnings
+/// on the 2021 edition, e.g. `rustc --edition 2021 -O main.rs`.
+#[must_use]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RustSource(String);
+
+impl RustSource {
+    /// Creates a new [`RustSource`] from a [`Program`].
+    ///
+    /// The entire program code is placed in the `execute` method of a `Machine`, which holds the tape
+    /// as a `Vec` of the unsigned integer type of the configured [`CellWidth`], and the index of the current cell.
+    /// A growable tape is grown with `cell_at`, like the tape of the interpreter.
+    ///
+    /// The code is run through `fn run(input: impl Read, output: impl Write) -> Result<(), RuntimeError>`,
+    /// which behaves like [`Engine::run`](crate::interpreter::Engine::run) for the given [`Settings`].
+    /// The [`RustTarget`] decides whether a `main` function running it on the standard I/O is added.
+    ///
+    /// All programs produce valid code, panics can only occur due to internal errors.
+    pub fn compile_from(program: &Program, target: RustTarget, settings: &Settings) -> Self {
+        let addressing = Addressing::from(settings);
+        let mut out = SourceWriter::new("    ");
+
+        prelude(&mut out, target, settings, addressing);
+
+        out.open("impl<R: Read, W: Write> Machine<R, W> {");
+        out.open("fn execute(&mut self) -> Result<(), RuntimeError> {");
+        for instr in program.code.iter() {
+            match *instr {
+                RI::MutPointer(change) => {
+                    out.line(format!(
+                        "self.pointer = {};",
+                        index(settings, addressing, change)
+                    ));
+                },
+                RI::MutCell { offset, delta } => {
+                    let cell = cell(&mut out, settings, addressing, offset);
+                    let max = settings.cell_width().max();
+                    match (settings.strict(), delta < 0) {
+                        (true, _) => out.line(format!("self.add({cell}, {delta})?;")),
+                        (false, false) => out.line(format!(
+                            "self.memory[{cell}] = self.memory[{cell}].wrapping_add({});",
+                            delta as u32 & max
+                        )),
+                        (false, true) => out.line(format!(
+                            "self.memory[{cell}] = self.memory[{cell}].wrapping_sub({});",
+                            delta.unsigned_abs() & max
+                        )),
+                    }
+                },
+                RI::SetCell { offset, value } => {
+                    let cell = cell(&mut out, settings, addressing, offset);
+                    out.line(format!("self.memory[{cell}] = {value};"));
+                },
+                RI::MulAdd { offset, factor } => {
+                    // The value is read first, since growing the tape to the left moves the current cell.
+                    out.line("let value = self.memory[self.pointer];");
+                    let cell = cell(&mut out, settings, addressing, offset);
+                    let (method, factor) = match factor < 0 {
+                        false => ("wrapping_add", factor.unsigned_abs()),
+                        true => ("wrapping_sub", factor.unsigned_abs()),
+                    };
+                    out.line(format!(
+                        "self.memory[{cell}] = self.memory[{cell}].{method}(value.wrapping_mul({}));",
+                        factor & settings.cell_width().max()
+                    ));
+                },
+                RI::ScanRight(stride) => scan(&mut out, settings, addressing, stride as i32),
+                RI::ScanLeft(stride) => scan(&mut out, settings, addressing, -(stride as i32)),
+                RI::JumpRightZ(_) => out.open("while self.memory[self.pointer] != 0 {"),
+                RI::JumpLeftNz(_) => out.close("}"),
+                RI::Input => out.line("self.read()?;"),
+                RI::Output => out.line("self.write()?;"),
+                RI::Breakpoint(_) => (),
+            }
+        }
+        out.line("Ok(())");
+        out.close("}");
+        out.close("}");
+
+        out.line("");
+        out.line("// Not every program uses all of the helpers.");
+        out.line("#[allow(dead_code)]");
+        out.open("impl<R: Read, W: Write> Machine<R, W> {");
+        match addressing {
+            Addressing::Wrapping => (),
+            Addressing::Bounded => out.lines(BOUNDED_CELL_AT),
+            Addressing::Growable => out.lines(&growable_cell_at(settings.tape_mode())),
+        }
+        if addressing != Addressing::Wrapping {
+            out.line("");
+            out.lines(TAPE_OVERFLOW);
+            out.line("");
+        }
+        if settings.strict() {
+            out.lines(&add(settings.cell_width()));
+            out.line("");
+        }
+        out.lines(&read(settings.cell_width(), settings.eof_behavior()));
+        out.line("");
+        out.lines(WRITE);
+        out.close("}");
+
+        Self(out.finish())
+    }
+
+    /// Emits the Rust source code to the given [`Write`].
+    pub fn emit(&self, mut write: impl Write) -> io::Result<()> {
+        write.write_all(self.0.as_bytes())
+    }
+}
+
+/// Writes the module documentation, the imports, the error type, the entry points and the `Machine` type.
+fn prelude(
+    out: &mut SourceWriter,
+    target: RustTarget,
+    settings: &Settings,
+    addressing: Addressing,
+) {
+    match target {
+        RustTarget::Module => out.lines(MODULE_DOCS),
+        RustTarget::Binary => out.lines(BINARY_DOCS),
+    }
+    out.line("");
+    out.open("use std::{");
+    out.line("error::Error,");
+    out.line("fmt::{self, Display, Formatter},");
+    out.line("io::{self, ErrorKind, Read, Write},");
+    if target == RustTarget::Binary {
+        out.line("process::ExitCode,");
+    }
+    out.close("};");
+    out.line("");
+    out.line(match settings.cell_width() {
+        CellWidth::U8 => "type Cell = u8;",
+        CellWidth::U16 => "type Cell = u16;",
+        CellWidth::U32 => "type Cell = u32;",
+    });
+    out.line("");
+    out.line(format!(
+        "const TAPE_LENGTH: usize = {};",
+        settings.tape_length()
+    ));
+    if addressing == Addressing::Growable {
+        out.line(format!(
+            "const MAX_LENGTH: usize = {};",
+            Settings::MAX_LENGTH
+        ));
+    }
+    out.line("");
+    out.lines(RUNTIME_ERROR);
+    out.line("");
+    out.lines(RUN);
+    if target == RustTarget::Binary {
+        out.line("");
+        out.lines(MAIN);
+    }
+    out.line("");
+    out.lines(MACHINE);
+    out.line("");
+}
+
+/// Returns the expression of the index of the cell `offset` cells away from the pointer.
+fn index(settings: &Settings, addressing: Addressing, offset: i32) -> String {
+    match addressing {
+        Addressing::Wrapping => format!(
+            "(self.pointer + {}) % TAPE_LENGTH",
+            offset.rem_euclid(settings.tape_length() as i32)
+        ),
+        Addressing::Bounded | Addressing::Growable => format!("self.cell_at({offset})?"),
+    }
+}
+
+/// Returns the expression of the index of the cell `offset` cells away from the pointer,
+/// storing it in `at` first if it has to be computed.
+fn cell(
+    out: &mut SourceWriter,
+    settings: &Settings,
+    addressing: Addressing,
+    offset: i32,
+) -> &'static str {
+    if offset == 0 {
+        return "self.pointer";
+    }
+    out.line(format!("let at = {};", index(settings, addressing, offset)));
+    "at"
+}
+
+fn scan(out: &mut SourceWriter, settings: &Settings, addressing: Addressing, stride: i32) {
+    out.open("while self.memory[self.pointer] != 0 {");
+    out.line(format!(
+        "self.pointer = {};",
+        index(settings, addressing, stride)
+    ));
+    out.close("}");
+}
+
+/// Returns the expression converting a byte to a cell, which would be a useless conversion for 8-bit cells.
+const fn widen_byte(cell_width: CellWidth) -> &'static str {
+    match cell_width {
+        CellWidth::U8 => "buffer[0]",
+        CellWidth::U16 | CellWidth::U32 => "Cell::from(buffer[0])",
+    }
+}
+
+const MODULE_DOCS: &str = "\
+//! A Brainfuck program compiled to Rust.
+//!
+//! The program is executed with the [`run`] function.";
+
+const BINARY_DOCS: &str = "\
+//! A Brainfuck program compiled to Rust.
+//!
+//! The program reads from the standard input and writes to the standard output.";
+
+const RUNTIME_ERROR: &str = r#"/// An error which stopped the execution of the program.
+#[derive(Debug)]
+pub enum RuntimeError {
+    /// The input could not be read from.
+    InputError(io::Error),
+    /// The output could not be written to.
+    OutputError(io::Error),
+    /// The pointer moved outside of the tape.
+    TapeOverflow { from: i32, by: i32 },
+    /// A cell overflowed in strict mode.
+    CellOverflow { at: i32, from: u32, by: i32 },
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InputError(_) => write!(f, "input error"),
+            Self::OutputError(_) => write!(f, "output error"),
+            Self::TapeOverflow { from, by } => {
+                write!(f, "tape overflow when changing {from} by {by}")
+            }
+            Self::CellOverflow { at, from, by } => {
+                write!(f, "cell overflow when changing {from} by {by} at {at}")
+            }
+        }
+    }
+}
+
+impl Error for RuntimeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::InputError(err) | Self::OutputError(err) => Some(err),
+            Self::TapeOverflow { .. } | Self::CellOverflow { .. } => None,
+        }
+    }
+}"#;
+
+const RUN: &str = "\
+/// Runs the program, reading from `input` and writing to `output`.
+///
+/// The output is flushed once the program halts, even if it failed.
+pub fn run(input: impl Read, output: impl Write) -> Result<(), RuntimeError> {
+    let mut machine = Machine {
+        memory: vec![0; TAPE_LENGTH],
+        pointer: 0,
+        origin: 0,
+        input,
+        output,
+    };
+    let result = machine.execute();
+    result.and(machine.output.flush().map_err(RuntimeError::OutputError))
+}";
+
+const MAIN: &str = r#"fn main() -> ExitCode {
+    match run(io::stdin().lock(), io::stdout().lock()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            match err.source() {
+                Some(source) => eprintln!("RuntimeError: {err}: {source}."),
+                None => eprintln!("RuntimeError: {err}."),
+            }
+            ExitCode::FAILURE
+        }
+    }
+}"#;
+
+const MACHINE: &str = "\
+// Not every program uses all of the fields.
+#[allow(dead_code)]
+struct Machine<R, W> {
+    memory: Vec<Cell>,
+    pointer: usize,
+    /// The index of the initial first cell, it only moves when the tape grows to the left.
+    origin: usize,
+    input: R,
+    output: W,
+}";
+
+const BOUNDED_CELL_AT: &str = "\
+/// Returns the index of the cell `offset` cells away from the pointer.
+fn cell_at(&mut self, offset: i32) -> Result<usize, RuntimeError> {
+    let index = self.pointer as i64 + i64::from(offset);
+    if !(0..TAPE_LENGTH as i64).contains(&index) {
+        return Err(self.tape_overflow(offset));
+    }
+    Ok(index as usize)
+}";
+
+/// Returns the `cell_at` method of a growable tape, which grows it so that it contains the cell
+/// `offset` cells away from the pointer, and returns the index of that cell.
+fn growable_cell_at(tape_mode: TapeMode) -> String {
+    let grow_left = match tape_mode {
+        TapeMode::GrowBoth => GROW_LEFT,
+        TapeMode::Fixed | TapeMode::GrowRight => "        return Err(self.tape_overflow(offset));",
+    };
+    format!(
+        "\
+/// Returns the index of the cell `offset` cells away from the pointer, growing the tape if needed.
+fn cell_at(&mut self, offset: i32) -> Result<usize, RuntimeError> {{
+    let index = self.pointer as i64 + i64::from(offset);
+    let length = self.memory.len() as i64;
+    let room = MAX_LENGTH as i64 - length;
+    if index < 0 {{
+{grow_left}
+    }}
+    if index >= length {{
+        if index >= length + room {{
+            return Err(self.tape_overflow(offset));
+        }}
+        let grown = (index + 1).max(length + length.min(room));
+        self.memory.resize(grown as usize, 0);
+    }}
+    Ok(index as usize)
+}}"
+    )
+}
+
+/// The part of `cell_at` which splices zeroed cells in front of the memory.
+const GROW_LEFT: &str = "        if -index > room {
+            return Err(self.tape_overflow(offset));
+        }
+        let added = (-index).max(length.min(room)) as usize;
+        self.memory.splice(0..0, vec![0; added]);
+        self.origin += added;
+        self.pointer += added;
+        return Ok((index + added as i64) as usize);";
+
+const TAPE_OVERFLOW: &str = "\
+fn tape_overflow(&self, by: i32) -> RuntimeError {
+    let from = self.pointer as i32 - self.origin as i32;
+    RuntimeError::TapeOverflow { from, by }
+}";
+
+/// Returns the `add` method, which changes a cell in strict mode.
+fn add(cell_width: CellWidth) -> String {
+    let from = match cell_width {
+        CellWidth::U8 | CellWidth::U16 => "from: u32::from(from)",
+        CellWidth::U32 => "from",
+    };
+    format!(
+        "\
+fn add(&mut self, at: usize, by: i32) -> Result<(), RuntimeError> {{
+    let from = self.memory[at];
+    let value = i64::from(from) + i64::from(by);
+    if !(0..=i64::from(Cell::MAX)).contains(&value) {{
+        let at = at as i32 - self.origin as i32;
+        return Err(RuntimeError::CellOverflow {{ at, {from}, by }});
+    }}
+    self.memory[at] = value as Cell;
+    Ok(())
+}}"
+    )
+}
+
+/// Returns the `read` method, which stores the read byte or handles the end of input.
+fn read(cell_width: CellWidth, eof_behavior: EofBehavior) -> String {
+    let at_eof = match eof_behavior {
+        EofBehavior::Zero => "self.memory[self.pointer] = 0",
+        EofBehavior::Unchanged => "{}",
+        EofBehavior::Max => "self.memory[self.pointer] = Cell::MAX",
+    };
+    let byte = widen_byte(cell_width);
+    format!(
+        "\
+fn read(&mut self) -> Result<(), RuntimeError> {{
+    let mut buffer = [0];
+    match self.input.read_exact(&mut buffer) {{
+        Ok(()) => self.memory[self.pointer] = {byte},
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => {at_eof},
+        Err(err) => return Err(RuntimeError::InputError(err)),
+    }}
+    Ok(())
+}}"
+    )
+}
+
+const WRITE: &str = "\
+/// Writes the least significant byte of the current cell.
+fn write(&mut self) -> Result<(), RuntimeError> {
+    let byte = self.memory[self.pointer].to_le_bytes()[0];
+    self.output.write_all(&[byte]).map_err(RuntimeError::OutputError)
+}";
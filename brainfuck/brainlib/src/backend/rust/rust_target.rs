@@ -0,0 +1,21 @@
+/// The kind of Rust code produced by [`RustSource`](crate::rust::RustSource).
+///
+/// Both targets define the same `run` function and `RuntimeError` type, they only differ in the entry point.
+#[must_use]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RustTarget {
+    /// A module which can be vendored in another crate, e.g. as `src/program.rs`.
+    ///
+    /// The following items are public:
+    /// - `fn run(input: impl Read, output: impl Write) -> Result<(), RuntimeError>`
+    /// - `enum RuntimeError`, mirroring [`RuntimeError`](crate::interpreter::RuntimeError)
+    ///
+    /// This is the [`RustTarget::default`] target.
+    #[default]
+    Module,
+
+    /// A full `main.rs` of a binary crate, which runs the program on the standard input and output.
+    ///
+    /// If the program fails, the error is printed to the standard error and the process exits with a failure code.
+    Binary,
+}
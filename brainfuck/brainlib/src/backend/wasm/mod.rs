@@ -6,7 +6,8 @@
 //!
 //! The features are exposed through the [`WasmModule`] and [`WasmTarget`] types.
 //!
-//! **NOTE:** Currently only WAT[^2] (WebAssembly Text) format is supported.
+//! The module can be emitted either as WAT[^2] (WebAssembly Text) or in the binary format.
+//! A JavaScript loader, which instantiates the binary module with byte buffers for I/O, can be emitted alongside it.
 //!
 //! # Examples
 //! ```
@@ -23,6 +24,14 @@
 //! let module = WasmModule::compile_from(&program, WasmTarget::Normal, &settings);
 //! module.emit_wat(stdout())?;
 //!
+//! let mut binary = Vec::new();
+//! module.emit_wasm(&mut binary)?;
+//! assert!(binary.starts_with(b"\0asm"));
+//!
+//! let mut loader = Vec::new();
+//! module.emit_js_loader(&mut loader)?;
+//! assert!(String::from_utf8(loader)?.contains("export async function run(wasm, input) {"));
+//!
 //! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
 //!
@@ -33,4 +42,5 @@
 mod wasm_module;
 mod wasm_target;
 
+pub(crate) use self::wasm_target::tape_bytes;
 pub use self::{wasm_module::WasmModule, wasm_target::WasmTarget};
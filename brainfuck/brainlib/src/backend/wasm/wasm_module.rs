@@ -5,28 +5,33 @@ use wasmitter::{
     instruction::{BlockType, ConstInstr as CWI, Instr as WI, MemArg, Nn, Sx},
     module::Module,
     text::Id,
-    types::Mut,
+    types::{Mut, I32},
 };
 
 use crate::backend::{
-    common::{Instruction as CI, Program, Settings},
-    wasm::WasmTarget,
+    common::{CellWidth, EofBehavior, Instruction as CI, Program, Settings, TapeMode},
+    wasm::{tape_bytes, WasmTarget},
 };
 
 /// Represents a WebAssembly module for a given [`Program`].
 ///
 /// A module can be created from a [`Program`] using [`WasmModule::compile_from`].
 ///
-/// The underlying WASM module can be emitted as WAT using [`WasmModule::emit_wat`].
+/// The underlying WASM module can be emitted as WAT using [`WasmModule::emit_wat`],
+/// or in the binary format using [`WasmModule::emit_wasm`].
 ///
-/// You should select an appropriate [`WasmTarget`] for your use case.
+/// You should select an appropriate [`WasmTarget`] for your use case. A JavaScript loader, which runs
+/// the module with byte buffers as its input and output, can be emitted using [`WasmModule::emit_js_loader`].
 ///
 /// # Specification
 /// - [Modules - Structure](https://webassembly.github.io/spec/core/syntax/modules.html)
 /// - [Modules - Text Format](https://webassembly.github.io/spec/core/text/modules.html)
 #[must_use]
 #[derive(Debug)]
-pub struct WasmModule(Module);
+pub struct WasmModule {
+    module: Module,
+    target: WasmTarget,
+}
 
 impl WasmModule {
     /// Creates a new [`WasmModule`] from a [`Program`].
@@ -34,29 +39,53 @@ impl WasmModule {
     /// The I/O functions provided by the selected [`WasmTarget`].
     ///
     /// The entire program code is placed in the `$main` function.
+    /// Input is read through the `$read_cell` function, which handles the configured [`EofBehavior`].
     /// This function is exported as `_start`, alongside the memory named `memory`.
-    /// The tape pointer is stored in the global `$ptr`, which is not exported.
+    /// The tape pointer is stored in the global `$ptr` as the byte address of the current cell, it is not exported.
+    /// Cells are stored in the memory starting at the address `0`, using the configured [`CellWidth`].
     /// The behaviour above applies to all targets.
     ///
+    /// With a growable [`TapeMode`], every cell address is passed through the `$reserve` function,
+    /// which grows the memory with `memory.grow` when needed and returns the address to use.
+    /// The WASI target then stores the tape after its buffers, starting at the address `32`.
+    ///
     /// All programs produce a valid module, panics can only occur due to internal errors.
     pub fn compile_from(program: &Program, target: WasmTarget, settings: &Settings) -> Self {
         let mut module = Module::new();
 
         let pages = target.required_pages(settings);
         let (read_byte, write_byte) = target.inject_io_funcs(&mut module, settings);
+        let read_cell = inject_read_cell(&mut module, settings, read_byte);
 
-        let ptr = module.global("$ptr", Mut::Var, CWI::I32Const(0));
-        let memory = module.memory(Id::none(), (pages, pages));
+        let start = target.tape_address(settings);
+        let ptr = module.global("$ptr", Mut::Var, CWI::I32Const(start));
+        let (memory, reserve) = match settings.tape_mode() {
+            TapeMode::Fixed => (module.memory(Id::none(), (pages, pages)), None),
+            TapeMode::GrowRight | TapeMode::GrowBoth => {
+                let memory = module.memory(Id::none(), pages);
+                (
+                    memory,
+                    Some(inject_reserve(&mut module, settings, ptr, start)),
+                )
+            },
+        };
+        let tape = Tape { ptr, reserve };
 
         let mut stack = vec![Vec::new()];
 
-        for instr in program.0.iter() {
+        for instr in program.code.iter() {
             let current = stack.last_mut().expect("unexpected stack underflow");
 
             match instr {
-                CI::MutPointer(change) => current.append(&mut mut_pointer(settings, ptr, *change)),
-                CI::MutCell(change) => current.append(&mut mut_cell(settings, ptr, *change)),
-                CI::SetCell(value) => current.append(&mut set_cell(ptr, *value)),
+                CI::MutPointer(change) => current.append(&mut mut_pointer(settings, tape, *change)),
+                CI::MutCell { offset, delta } =>
+                    current.append(&mut mut_cell(settings, tape, *offset, *delta)),
+                CI::SetCell { offset, value } =>
+                    current.append(&mut set_cell(settings, tape, *offset, *value)),
+                CI::MulAdd { offset, factor } =>
+                    current.append(&mut mul_add(settings, tape, *offset, *factor)),
+                CI::ScanRight(stride) => current.push(scan(settings, tape, *stride as i32)),
+                CI::ScanLeft(stride) => current.push(scan(settings, tape, -(*stride as i32))),
                 CI::JumpRightZ(_) => stack.push(Vec::new()),
                 CI::JumpLeftNz(_) => {
                     let body = stack.pop().expect("unexpected stack underflow");
@@ -65,17 +94,17 @@ impl WasmModule {
                     current.push(WI::Block(
                         BlockType::default(),
                         [
-                            loop_header(ptr),
+                            loop_header(settings, tape.ptr),
                             vec![WI::Loop(
                                 BlockType::default(),
-                                [body, loop_trailer(ptr)].concat(),
+                                [body, loop_trailer(settings, tape.ptr)].concat(),
                             )],
                         ]
                         .concat(),
                     ))
                 },
-                CI::Input => current.append(&mut input(ptr, read_byte)),
-                CI::Output => current.append(&mut output(ptr, write_byte)),
+                CI::Input => current.append(&mut input(settings, tape.ptr, read_cell)),
+                CI::Output => current.append(&mut output(settings, tape.ptr, write_byte)),
                 CI::Breakpoint(_) => current.push(WI::Nop),
             }
         }
@@ -89,21 +118,211 @@ impl WasmModule {
         module.export("memory", memory);
         module.export("_start", main);
 
-        Self(module)
+        Self { module, target }
     }
 
     /// Emits the underlying WASM module as WAT to the given [`Write`].
     pub fn emit_wat(&self, mut write: impl Write) -> io::Result<()> {
-        write.write_all(self.0.to_wat().expect("internal error").as_bytes())
+        write.write_all(self.module.to_wat().expect("internal error").as_bytes())
+    }
+
+    /// Emits the underlying WASM module in the binary format to the given [`Write`].
+    ///
+    /// The output can be loaded directly by a WebAssembly runtime.
+    pub fn emit_wasm(&self, mut write: impl Write) -> io::Result<()> {
+        write.write_all(&self.module.to_wasm().expect("internal error"))
+    }
+
+    /// Emits a JavaScript loader for the module to the given [`Write`].
+    ///
+    /// The loader is an ES module exporting `run(wasm: BufferSource | WebAssembly.Module, input: Uint8Array)`,
+    /// which instantiates the module emitted by [`WasmModule::emit_wasm`], runs it, and resolves to the output
+    /// as a `Uint8Array`. It provides the I/O imports of the [`WasmTarget`] the module was compiled for,
+    /// reading from and writing to byte buffers. A trap rejects with a `WebAssembly.RuntimeError`,
+    /// whose `output` property holds the output written before it.
+    pub fn emit_js_loader(&self, mut write: impl Write) -> io::Result<()> {
+        let imports = self.target.js_imports();
+        write!(
+            write,
+            r#"/**
+ * Instantiates the WebAssembly module, runs it with the given input, and returns its output.
+ * @param {{BufferSource | WebAssembly.Module}} wasm The module in the binary format, or a compiled module.
+ * @param {{Uint8Array}} input
+ * @returns {{Promise<Uint8Array>}}
+ */
+export async function run(wasm, input) {{
+    let position = 0;
+    const output = [];
+    let instance;
+{imports}
+    const instantiated = await WebAssembly.instantiate(wasm, imports);
+    instance = instantiated.instance ?? instantiated;
+    try {{
+        instance.exports._start();
+    }} catch (error) {{
+        error.output = Uint8Array.from(output);
+        throw error;
+    }}
+    return Uint8Array.from(output);
+}}
+"#
+        )
     }
 }
 
+/// The global and the function used to access the tape.
+#[derive(Debug, Clone, Copy)]
+struct Tape {
+    ptr: GlobalIdx,
+    /// The `$reserve` function, present only if the tape is growable.
+    reserve: Option<FuncIdx>,
+}
+
+/// Creates the `$reserve` function, which takes a cell address, grows the memory to contain it,
+/// and returns the address of the same cell after growing.
+///
+/// Growing to the left moves the whole tape (and `$ptr`) to the right, since memory can only grow at the end.
+/// The memory at least doubles on each growth. Moving left of a [`TapeMode::GrowRight`] tape traps.
+fn inject_reserve(module: &mut Module, settings: &Settings, ptr: GlobalIdx, start: u32) -> FuncIdx {
+    module.func("$reserve", |scope| {
+        let address = scope.add_param(I32);
+        let pages = scope.add_local(I32);
+        scope.add_result(I32);
+
+        let page_bits = Module::PAGE_SIZE.trailing_zeros();
+        let grow_by_pages = vec![
+            // Grows by at least the current size, so that growing is amortized.
+            WI::LocalGet(pages),
+            WI::MemorySize,
+            WI::LocalGet(pages),
+            WI::MemorySize,
+            WI::IGt(Nn::N32, Sx::U),
+            WI::Select,
+            WI::LocalTee(pages),
+            WI::MemoryGrow,
+            WI::I32Const(-1i32 as u32),
+            WI::INe(Nn::N32),
+            WI::BrIf(0.into()),
+            WI::Unreachable,
+        ];
+        let shifted = |value: Vec<WI>| {
+            [
+                value,
+                vec![
+                    WI::LocalGet(pages),
+                    WI::I32Const(page_bits),
+                    WI::IShl(Nn::N32),
+                    WI::IAdd(Nn::N32),
+                ],
+            ]
+            .concat()
+        };
+
+        let grow_left = match settings.tape_mode() {
+            TapeMode::GrowBoth => [
+                vec![
+                    WI::I32Const(start),
+                    WI::LocalGet(address),
+                    WI::ISub(Nn::N32),
+                    WI::I32Const(Module::PAGE_SIZE - 1),
+                    WI::IAdd(Nn::N32),
+                    WI::I32Const(page_bits),
+                    WI::IShr(Nn::N32, Sx::U),
+                    WI::LocalSet(pages),
+                    WI::Block(BlockType::default(), grow_by_pages.clone()),
+                ],
+                // Moves the old memory after the new pages and clears the space before it.
+                shifted(vec![WI::I32Const(start)]),
+                vec![
+                    WI::I32Const(start),
+                    WI::MemorySize,
+                    WI::LocalGet(pages),
+                    WI::ISub(Nn::N32),
+                    WI::I32Const(page_bits),
+                    WI::IShl(Nn::N32),
+                    WI::I32Const(start),
+                    WI::ISub(Nn::N32),
+                    WI::MemoryCopy,
+                    WI::I32Const(start),
+                    WI::I32Const(0),
+                    WI::LocalGet(pages),
+                    WI::I32Const(page_bits),
+                    WI::IShl(Nn::N32),
+                    WI::MemoryFill,
+                ],
+                shifted(vec![WI::GlobalGet(ptr)]),
+                vec![WI::GlobalSet(ptr)],
+                shifted(vec![WI::LocalGet(address)]),
+                vec![WI::LocalSet(address)],
+            ]
+            .concat(),
+            _ => vec![WI::Unreachable],
+        };
+
+        vec![
+            WI::Block(
+                BlockType::default(),
+                [
+                    vec![
+                        WI::LocalGet(address),
+                        WI::I32Const(start),
+                        WI::IGe(Nn::N32, Sx::S),
+                        WI::BrIf(0.into()),
+                    ],
+                    grow_left,
+                ]
+                .concat(),
+            ),
+            WI::Block(
+                BlockType::default(),
+                [
+                    vec![
+                        WI::LocalGet(address),
+                        WI::I32Const(page_bits),
+                        WI::IShr(Nn::N32, Sx::U),
+                        WI::LocalTee(pages),
+                        WI::MemorySize,
+                        WI::ILt(Nn::N32, Sx::U),
+                        WI::BrIf(0.into()),
+                        WI::LocalGet(pages),
+                        WI::I32Const(1),
+                        WI::IAdd(Nn::N32),
+                        WI::MemorySize,
+                        WI::ISub(Nn::N32),
+                        WI::LocalSet(pages),
+                    ],
+                    grow_by_pages,
+                ]
+                .concat(),
+            ),
+            WI::LocalGet(address),
+        ]
+    })
+}
+
 #[must_use]
-fn mut_pointer(settings: &Settings, ptr: GlobalIdx, change: i32) -> Vec<WI> {
+fn mut_pointer(settings: &Settings, tape: Tape, change: i32) -> Vec<WI> {
+    let (ptr, bytes) = (tape.ptr, settings.cell_width().bytes());
+
+    if let Some(reserve) = tape.reserve {
+        return vec![
+            WI::GlobalGet(ptr),
+            WI::I32Const(change.wrapping_mul(bytes as i32) as u32),
+            WI::IAdd(Nn::N32),
+            WI::Call(reserve),
+            WI::GlobalSet(ptr),
+        ];
+    }
+
+    let change = match settings.strict() {
+        true => change.wrapping_mul(bytes as i32) as u32,
+        false => change.rem_euclid(settings.tape_length() as i32) as u32 * bytes,
+    };
+
     [
         vec![
             WI::GlobalGet(ptr),
-            WI::I32Const(change as u32),
+            WI::I32Const(change),
             WI::IAdd(Nn::N32),
             WI::GlobalSet(ptr),
         ],
@@ -123,7 +342,7 @@ fn mut_pointer(settings: &Settings, ptr: GlobalIdx, change: i32) -> Vec<WI> {
                     BlockType::default(),
                     vec![
                         WI::GlobalGet(ptr),
-                        WI::I32Const(settings.tape_length()),
+                        WI::I32Const(tape_bytes(settings)),
                         WI::ILt(Nn::N32, Sx::S),
                         WI::BrIf(0.into()),
                         WI::Unreachable,
@@ -133,7 +352,7 @@ fn mut_pointer(settings: &Settings, ptr: GlobalIdx, change: i32) -> Vec<WI> {
         } else {
             vec![
                 WI::GlobalGet(ptr),
-                WI::I32Const(settings.tape_length()),
+                WI::I32Const(tape_bytes(settings)),
                 WI::IRem(Nn::N32, Sx::U),
                 WI::GlobalSet(ptr),
             ]
@@ -142,88 +361,246 @@ fn mut_pointer(settings: &Settings, ptr: GlobalIdx, change: i32) -> Vec<WI> {
     .concat()
 }
 
+/// Returns the instruction which loads a cell from the address on the stack, with the given static offset.
+fn load_cell(settings: &Settings, offset: u32) -> WI {
+    match settings.cell_width() {
+        CellWidth::U8 => WI::ILoad8(Nn::N32, Sx::U, MemArg::with_offset(offset)),
+        CellWidth::U16 => WI::ILoad16(Nn::N32, Sx::U, MemArg::with_offset(offset)),
+        CellWidth::U32 => WI::I32Load(MemArg::with_offset(offset)),
+    }
+}
+
+/// Returns the instruction which stores a value on the stack to the cell at the address below it,
+/// with the given static offset.
+fn store_cell(settings: &Settings, offset: u32) -> WI {
+    match settings.cell_width() {
+        CellWidth::U8 => WI::IStore8(Nn::N32, MemArg::with_offset(offset)),
+        CellWidth::U16 => WI::IStore16(Nn::N32, MemArg::with_offset(offset)),
+        CellWidth::U32 => WI::I32Store(MemArg::with_offset(offset)),
+    }
+}
+
+/// Returns the instructions which push the base address of the cell at `offset` from the pointer,
+/// alongside the static offset (in bytes) used to access it.
 #[must_use]
-fn mut_cell(settings: &Settings, ptr: GlobalIdx, change: i8) -> Vec<WI> {
-    [
+fn cell_address(settings: &Settings, tape: Tape, offset: i32) -> (Vec<WI>, u32) {
+    let (ptr, bytes) = (tape.ptr, settings.cell_width().bytes());
+
+    if offset == 0 {
+        return (vec![WI::GlobalGet(ptr)], 0);
+    }
+
+    if let Some(reserve) = tape.reserve {
+        return (
+            vec![
+                WI::GlobalGet(ptr),
+                WI::I32Const(offset.wrapping_mul(bytes as i32) as u32),
+                WI::IAdd(Nn::N32),
+                WI::Call(reserve),
+            ],
+            0,
+        );
+    }
+
+    if !settings.strict() {
+        // The tape wraps around, so the address can't be expressed with a static offset.
+        return (
+            vec![
+                WI::GlobalGet(ptr),
+                WI::I32Const(offset.rem_euclid(settings.tape_length() as i32) as u32 * bytes),
+                WI::IAdd(Nn::N32),
+                WI::I32Const(tape_bytes(settings)),
+                WI::IRem(Nn::N32, Sx::U),
+            ],
+            0,
+        );
+    }
+
+    let change = offset.wrapping_mul(bytes as i32) as u32;
+    let bounds_check = WI::Block(
+        BlockType::default(),
         vec![
             WI::GlobalGet(ptr),
-            WI::GlobalGet(ptr),
-            WI::ILoad8(Nn::N32, Sx::U, MemArg::default()),
-            WI::I32Const(change as u32),
+            WI::I32Const(change),
             WI::IAdd(Nn::N32),
-            WI::IStore8(Nn::N32, MemArg::default()),
+            WI::I32Const(tape_bytes(settings)),
+            WI::ILt(Nn::N32, Sx::U),
+            WI::BrIf(0.into()),
+            WI::Unreachable,
         ],
-        if settings.strict() {
-            vec![WI::Block(
-                BlockType::default(),
-                vec![
-                    WI::GlobalGet(ptr),
-                    WI::ILoad8(Nn::N32, Sx::U, MemArg::default()),
-                    WI::I32Const(!0xFF),
-                    WI::IAnd(Nn::N32),
-                    WI::IEqz(Nn::N32),
-                    WI::BrIf(0.into()),
-                    WI::Unreachable,
-                ],
-            )]
-        } else {
+    );
+
+    match offset > 0 {
+        true => (vec![bounds_check, WI::GlobalGet(ptr)], change),
+        false => (
             vec![
+                bounds_check,
                 WI::GlobalGet(ptr),
-                WI::GlobalGet(ptr),
-                WI::ILoad8(Nn::N32, Sx::U, MemArg::default()),
-                WI::I32Const(0xFF),
-                WI::IAnd(Nn::N32),
-                WI::IStore8(Nn::N32, MemArg::default()),
+                WI::I32Const(change),
+                WI::IAdd(Nn::N32),
+            ],
+            0,
+        ),
+    }
+}
+
+#[must_use]
+fn mut_cell(settings: &Settings, tape: Tape, offset: i32, change: i32) -> Vec<WI> {
+    let (address, static_offset) = cell_address(settings, tape, offset);
+
+    let overflow_check = match settings.strict() {
+        true => vec![WI::Block(
+            BlockType::default(),
+            [
+                address.clone(),
+                vec![load_cell(settings, static_offset)],
+                match change > 0 {
+                    true => vec![
+                        WI::I32Const(settings.cell_width().max() - change as u32),
+                        WI::ILe(Nn::N32, Sx::U),
+                    ],
+                    false => vec![WI::I32Const(change.unsigned_abs()), WI::IGe(Nn::N32, Sx::U)],
+                },
+                vec![WI::BrIf(0.into()), WI::Unreachable],
             ]
-        },
+            .concat(),
+        )],
+        false => Vec::new(),
+    };
+
+    [
+        overflow_check,
+        address.clone(),
+        address,
+        vec![
+            load_cell(settings, static_offset),
+            WI::I32Const(change as u32),
+            WI::IAdd(Nn::N32),
+            store_cell(settings, static_offset),
+        ],
     ]
     .concat()
 }
 
 #[must_use]
-fn set_cell(ptr: GlobalIdx, value: u8) -> Vec<WI> {
-    vec![
-        WI::GlobalGet(ptr),
-        WI::I32Const(value as u32),
-        WI::IStore8(Nn::N32, MemArg::default()),
+fn set_cell(settings: &Settings, tape: Tape, offset: i32, value: u32) -> Vec<WI> {
+    let (address, static_offset) = cell_address(settings, tape, offset);
+
+    [
+        address,
+        vec![WI::I32Const(value), store_cell(settings, static_offset)],
     ]
+    .concat()
 }
 
 #[must_use]
-fn input(ptr: GlobalIdx, read_byte: FuncIdx) -> Vec<WI> {
-    vec![
-        WI::GlobalGet(ptr),
-        WI::Call(read_byte),
-        WI::I32Const(0xFF),
-        WI::IAnd(Nn::N32),
-        WI::IStore8(Nn::N32, MemArg::default()),
+fn mul_add(settings: &Settings, tape: Tape, offset: i32, factor: i32) -> Vec<WI> {
+    let (target, static_offset) = cell_address(settings, tape, offset);
+
+    [
+        target.clone(),
+        target,
+        vec![
+            load_cell(settings, static_offset),
+            WI::GlobalGet(tape.ptr),
+            load_cell(settings, 0),
+            WI::I32Const(factor as u32),
+            WI::IMul(Nn::N32),
+            WI::IAdd(Nn::N32),
+            store_cell(settings, static_offset),
+        ],
     ]
+    .concat()
+}
+
+fn scan(settings: &Settings, tape: Tape, change: i32) -> WI {
+    WI::Block(
+        BlockType::default(),
+        vec![WI::Loop(
+            BlockType::default(),
+            [
+                vec![
+                    WI::GlobalGet(tape.ptr),
+                    load_cell(settings, 0),
+                    WI::IEqz(Nn::N32),
+                    WI::BrIf(1.into()),
+                ],
+                mut_pointer(settings, tape, change),
+                vec![WI::Br(0.into())],
+            ]
+            .concat(),
+        )],
+    )
+}
+
+/// Creates the `$read_cell` function, which takes the value of the current cell and returns its new value.
+///
+/// A byte is read using `read_byte`, which returns a negative value at the end of input.
+/// In that case, the returned value depends on the configured [`EofBehavior`].
+fn inject_read_cell(module: &mut Module, settings: &Settings, read_byte: FuncIdx) -> FuncIdx {
+    module.func("$read_cell", |scope| {
+        let cell = scope.add_param(I32);
+        let byte = scope.add_local(I32);
+        scope.add_result(I32);
+
+        vec![
+            WI::Call(read_byte),
+            WI::LocalTee(byte),
+            WI::I32Const(0xFF),
+            WI::IAnd(Nn::N32),
+            match settings.eof_behavior() {
+                EofBehavior::Zero => WI::I32Const(0),
+                EofBehavior::Unchanged => WI::LocalGet(cell),
+                EofBehavior::Max => WI::I32Const(settings.cell_width().max()),
+            },
+            WI::LocalGet(byte),
+            WI::I32Const(0),
+            WI::IGe(Nn::N32, Sx::S),
+            WI::Select,
+        ]
+    })
 }
 
 #[must_use]
-fn output(ptr: GlobalIdx, write_byte: FuncIdx) -> Vec<WI> {
+fn input(settings: &Settings, ptr: GlobalIdx, read_cell: FuncIdx) -> Vec<WI> {
     vec![
         WI::GlobalGet(ptr),
-        WI::ILoad8(Nn::N32, Sx::U, MemArg::default()),
-        WI::Call(write_byte),
+        WI::GlobalGet(ptr),
+        load_cell(settings, 0),
+        WI::Call(read_cell),
+        store_cell(settings, 0),
     ]
 }
 
 #[must_use]
-fn loop_header(ptr: GlobalIdx) -> Vec<WI> {
+fn output(settings: &Settings, ptr: GlobalIdx, write_byte: FuncIdx) -> Vec<WI> {
+    [
+        vec![WI::GlobalGet(ptr), load_cell(settings, 0)],
+        match settings.cell_width() {
+            CellWidth::U8 => Vec::new(),
+            // Only the least significant byte of wider cells is written.
+            _ => vec![WI::I32Const(0xFF), WI::IAnd(Nn::N32)],
+        },
+        vec![WI::Call(write_byte)],
+    ]
+    .concat()
+}
+
+#[must_use]
+fn loop_header(settings: &Settings, ptr: GlobalIdx) -> Vec<WI> {
     vec![
         WI::GlobalGet(ptr),
-        WI::ILoad8(Nn::N32, Sx::U, MemArg::default()),
+        load_cell(settings, 0),
         WI::IEqz(Nn::N32),
         WI::BrIf(0.into()),
     ]
 }
 
 #[must_use]
-fn loop_trailer(ptr: GlobalIdx) -> Vec<WI> {
+fn loop_trailer(settings: &Settings, ptr: GlobalIdx) -> Vec<WI> {
     vec![
         WI::GlobalGet(ptr),
-        WI::ILoad8(Nn::N32, Sx::U, MemArg::default()),
+        load_cell(settings, 0),
         WI::BrIf(0.into()),
     ]
 }
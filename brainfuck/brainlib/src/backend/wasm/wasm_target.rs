@@ -1,11 +1,11 @@
 use wasmitter::{
     indices::FuncIdx,
-    instruction::{Instr as WI, MemArg},
+    instruction::{Instr as WI, MemArg, Nn, Sx},
     module::Module,
     types::I32,
 };
 
-use crate::Settings;
+use crate::{Settings, TapeMode};
 
 /// The target WASM runtime, determining the module structure.
 ///
@@ -18,7 +18,8 @@ pub enum WasmTarget {
     ///
     /// This target requires you to define the I/O functions yourself, since they
     /// are imported from the produced module. You can, for example, use JavaScript
-    /// functions to provide the input and output.
+    /// functions to provide the input and output, or the loader emitted by
+    /// [`WasmModule::emit_js_loader`](crate::wasm::WasmModule::emit_js_loader).
     ///
     /// The following functions are imported:
     /// - `(import "bf" "input" (func (result i32)))`
@@ -28,6 +29,9 @@ pub enum WasmTarget {
     /// used to represent the bytes, since WebAssembly doesn't support `u8` values.
     /// The module uses only the least significant byte of the `i32` values.
     ///
+    /// The input function should return a negative value (e.g. `-1`) once the end of input is reached,
+    /// the module then stores a value depending on the configured [`EofBehavior`](crate::EofBehavior).
+    ///
     /// This is the [`WasmTarget::default`] target.
     ///
     /// # Examples
@@ -38,6 +42,10 @@ pub enum WasmTarget {
     ///   (import "bf" "output" (func $write_byte (param i32)))
     ///   (memory (;0;) 1 1)
     ///   (global $ptr (mut i32) (i32.const 0))
+    ///   (func $read_cell (param i32) (result i32)
+    ///     (local i32)
+    ///     ;; EOF handling
+    ///   )
     ///   (func $main
     ///     ;; example program
     ///   )
@@ -77,7 +85,11 @@ pub enum WasmTarget {
     ///     (call $fd_read)
     ///     (drop)
     ///     (i32.const 30012)
+    ///     (i32.load8_u)
+    ///     (i32.const -1)
+    ///     (i32.const 30000)
     ///     (i32.load)
+    ///     (select)
     ///   )
     ///   (func $write_byte (param i32)
     ///     (i32.const 30024)
@@ -90,6 +102,10 @@ pub enum WasmTarget {
     ///     (call $fd_write)
     ///     (drop)
     ///   )
+    ///   (func $read_cell (param i32) (result i32)
+    ///     (local i32)
+    ///     ;; EOF handling
+    ///   )
     ///   (func $main
     ///     (i32.const 30004)
     ///     (i32.const 30012)
@@ -120,6 +136,23 @@ pub enum WasmTarget {
     Wasi,
 }
 
+/// Returns the number of bytes (initially) occupied by the tape.
+#[must_use]
+pub(crate) const fn tape_bytes(settings: &Settings) -> u32 {
+    settings.tape_length() * settings.cell_width().bytes()
+}
+
+/// Returns the address of the memory used by the WASI I/O functions.
+///
+/// It is placed after a fixed tape, but before a growable one, since the end of such a tape moves.
+#[must_use]
+const fn scratch_address(settings: &Settings) -> u32 {
+    match settings.tape_mode() {
+        TapeMode::Fixed => tape_bytes(settings),
+        TapeMode::GrowRight | TapeMode::GrowBoth => 0,
+    }
+}
+
 impl Default for WasmTarget {
     /// Returns the default target, [`WasmTarget::Normal`].
     fn default() -> Self {
@@ -128,15 +161,23 @@ impl Default for WasmTarget {
 }
 
 impl WasmTarget {
+    /// Returns the address of the first cell of the tape.
+    #[must_use]
+    pub(crate) const fn tape_address(&self, settings: &Settings) -> u32 {
+        match (self, settings.tape_mode()) {
+            (WasmTarget::Wasi, TapeMode::GrowRight | TapeMode::GrowBoth) => 32,
+            _ => 0,
+        }
+    }
+
     #[must_use]
     pub(crate) fn required_pages(&self, settings: &Settings) -> u32 {
-        let required_bytes = settings.tape_length()
-            + match self {
-                WasmTarget::Normal => 0,
-                WasmTarget::Wasi => 28,
-            };
+        let required_bytes = match (self, settings.tape_mode()) {
+            (WasmTarget::Wasi, TapeMode::Fixed) => tape_bytes(settings) + 28,
+            _ => self.tape_address(settings) + tape_bytes(settings),
+        };
 
-        (required_bytes + Module::PAGE_SIZE - 1) / Module::PAGE_SIZE
+        required_bytes.div_ceil(Module::PAGE_SIZE)
     }
 
     pub(crate) fn inject_io_funcs(
@@ -171,26 +212,31 @@ impl WasmTarget {
                     scope.add_result(I32);
                     vec![
                         WI::I32Const(0),
-                        WI::I32Const(settings.tape_length() + 4),
+                        WI::I32Const(scratch_address(settings) + 4),
                         WI::I32Const(1),
-                        WI::I32Const(settings.tape_length()),
+                        WI::I32Const(scratch_address(settings)),
                         WI::Call(fd_read),
                         WI::Drop,
-                        WI::I32Const(settings.tape_length() + 12),
+                        // The number of bytes read is zero only at the end of input.
+                        WI::I32Const(scratch_address(settings) + 12),
+                        WI::ILoad8(Nn::N32, Sx::U, MemArg::default()),
+                        WI::I32Const(-1i32 as u32),
+                        WI::I32Const(scratch_address(settings)),
                         WI::I32Load(MemArg::default()),
+                        WI::Select,
                     ]
                 });
 
                 let write_byte = module.func("$write_byte", |scope| {
                     let value = scope.add_param(I32);
                     vec![
-                        WI::I32Const(settings.tape_length() + 24),
+                        WI::I32Const(scratch_address(settings) + 24),
                         WI::LocalGet(value),
                         WI::I32Store(MemArg::default()),
                         WI::I32Const(1),
-                        WI::I32Const(settings.tape_length() + 16),
+                        WI::I32Const(scratch_address(settings) + 16),
                         WI::I32Const(1),
-                        WI::I32Const(settings.tape_length()),
+                        WI::I32Const(scratch_address(settings)),
                         WI::Call(fd_write),
                         WI::Drop,
                     ]
@@ -201,24 +247,75 @@ impl WasmTarget {
         }
     }
 
+    /// Returns the JavaScript statement defining the `imports` object of the loader,
+    /// which reads from `input` at `position` and pushes to `output`.
+    #[must_use]
+    pub(crate) const fn js_imports(&self) -> &'static str {
+        match self {
+            WasmTarget::Normal => NORMAL_JS_IMPORTS,
+            WasmTarget::Wasi => WASI_JS_IMPORTS,
+        }
+    }
+
     #[must_use]
     pub(crate) fn main_header(&self, settings: &Settings) -> Vec<WI> {
         match self {
             WasmTarget::Normal => Vec::new(),
             WasmTarget::Wasi => vec![
-                WI::I32Const(settings.tape_length() + 4),
-                WI::I32Const(settings.tape_length() + 12),
+                WI::I32Const(scratch_address(settings) + 4),
+                WI::I32Const(scratch_address(settings) + 12),
                 WI::I32Store(MemArg::default()),
-                WI::I32Const(settings.tape_length() + 8),
+                WI::I32Const(scratch_address(settings) + 8),
                 WI::I32Const(1),
                 WI::I32Store(MemArg::default()),
-                WI::I32Const(settings.tape_length() + 16),
-                WI::I32Const(settings.tape_length() + 24),
+                WI::I32Const(scratch_address(settings) + 16),
+                WI::I32Const(scratch_address(settings) + 24),
                 WI::I32Store(MemArg::default()),
-                WI::I32Const(settings.tape_length() + 20),
+                WI::I32Const(scratch_address(settings) + 20),
                 WI::I32Const(1),
                 WI::I32Store(MemArg::default()),
             ],
         }
     }
 }
+
+const NORMAL_JS_IMPORTS: &str = "    const imports = {
+        bf: {
+            input: () => (position < input.length ? input[position++] : -1),
+            output: (byte) => {
+                output.push(byte & 0xFF);
+            },
+        },
+    };";
+
+/// The subset of WASI used by the module, which only reads from the standard input and writes to the standard output.
+const WASI_JS_IMPORTS: &str = "    const imports = {
+        wasi_unstable: {
+            fd_read: (_fd, iovs, count, read) => {
+                const view = new DataView(instance.exports.memory.buffer);
+                let total = 0;
+                for (let n = 0; n < count; n++) {
+                    const address = view.getUint32(iovs + 8 * n, true);
+                    const length = view.getUint32(iovs + 8 * n + 4, true);
+                    const chunk = input.subarray(position, position + length);
+                    new Uint8Array(view.buffer, address, chunk.length).set(chunk);
+                    position += chunk.length;
+                    total += chunk.length;
+                }
+                view.setUint32(read, total, true);
+                return 0;
+            },
+            fd_write: (_fd, iovs, count, written) => {
+                const view = new DataView(instance.exports.memory.buffer);
+                let total = 0;
+                for (let n = 0; n < count; n++) {
+                    const address = view.getUint32(iovs + 8 * n, true);
+                    const length = view.getUint32(iovs + 8 * n + 4, true);
+                    output.push(...new Uint8Array(view.buffer, address, length));
+                    total += length;
+                }
+                view.setUint32(written, total, true);
+                return 0;
+            },
+        },
+    };";
@@ -16,10 +16,13 @@ mod backend;
 mod frontend;
 pub mod util;
 
+#[cfg(feature = "jit")]
+pub use crate::backend::jit;
 pub use crate::{
     backend::{
-        common::{Instruction, Program, Settings},
-        interpreter, wasm,
+        c,
+        common::{CellWidth, EofBehavior, Instruction, Program, Settings, TapeMode},
+        interpreter, js, llvm, native, rust, wasm,
     },
     frontend::ParseError,
 };
@@ -32,8 +35,12 @@ mod tests {
     };
 
     use crate::{
-        interpreter::{ByteEngine, RuntimeError, StdEngine},
-        Instruction, ParseError, Program, Settings,
+        interpreter::{
+            AsyncEngine, ByteEngine, ByteIo, ChannelIo, Coverage, EngineState, FastEngine, IoError,
+            LoopCoverage, LoopProfile, Profile, RuntimeError, StateError, StdEngine, StdIo, SyncIo,
+            TraceFormat, TraceRecord, Tracer,
+        },
+        CellWidth, Instruction, ParseError, Program, Settings, TapeMode,
     };
 
     #[test]
@@ -42,11 +49,29 @@ mod tests {
 
         assert_debug::<Program>();
         assert_debug::<Settings>();
+        assert_debug::<CellWidth>();
+        assert_debug::<TapeMode>();
         assert_debug::<ByteEngine>();
         assert_debug::<StdEngine>();
+        assert_debug::<FastEngine<StdIo>>();
+        assert_debug::<AsyncEngine<SyncIo<ByteIo>>>();
+        #[cfg(feature = "jit")]
+        assert_debug::<crate::jit::JitEngine<StdIo>>();
+        assert_debug::<StdIo>();
+        assert_debug::<ByteIo>();
+        assert_debug::<ChannelIo>();
+        assert_debug::<EngineState>();
+        assert_debug::<Profile>();
+        assert_debug::<LoopProfile>();
+        assert_debug::<Coverage>();
+        assert_debug::<LoopCoverage>();
+        assert_debug::<TraceRecord>();
+        assert_debug::<TraceFormat>();
+        assert_debug::<Tracer<Vec<u8>>>();
         assert_debug::<Instruction>();
         assert_debug::<ParseError>();
         assert_debug::<RuntimeError>();
+        assert_debug::<StateError>();
     }
 
     #[test]
@@ -55,9 +80,25 @@ mod tests {
 
         assert_send_sync::<Program>();
         assert_send_sync::<Settings>();
+        assert_send_sync::<CellWidth>();
+        assert_send_sync::<TapeMode>();
         assert_send_sync::<Instruction>();
         assert_send_sync::<ParseError>();
         assert_send_sync::<RuntimeError>();
+        assert_send_sync::<IoError>();
+        assert_send_sync::<EngineState>();
+        assert_send_sync::<StateError>();
+        assert_send_sync::<Profile>();
+        assert_send_sync::<LoopProfile>();
+        assert_send_sync::<Coverage>();
+        assert_send_sync::<LoopCoverage>();
+        assert_send_sync::<TraceRecord>();
+        assert_send_sync::<TraceFormat>();
+        assert_send_sync::<Tracer<Vec<u8>>>();
+        assert_send_sync::<FastEngine<ByteIo>>();
+        assert_send_sync::<AsyncEngine<SyncIo<ByteIo>>>();
+        #[cfg(feature = "jit")]
+        assert_send_sync::<crate::jit::JitEngine<ByteIo>>();
     }
 
     #[test]
@@ -66,5 +107,7 @@ mod tests {
 
         assert_error::<ParseError>();
         assert_error::<RuntimeError>();
+        assert_error::<IoError>();
+        assert_error::<StateError>();
     }
 }
@@ -15,11 +15,26 @@ use std::io::{ErrorKind, Read, Write};
 /// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
 #[must_use]
-pub fn read_byte(mut input: impl Read) -> Option<u8> {
+pub fn read_byte(input: impl Read) -> Option<u8> {
+    read_byte_or_eof(input).map(|byte| byte.unwrap_or(0))
+}
+
+/// Read a single byte from a given [`Read`], distinguishing EOF from a byte with a value of `0`.
+///
+/// This returns `Some(Some(byte))` if a byte was read, `Some(None)` on EOF and [`None`] on any other error.
+///
+/// # Examples
+/// ```
+/// # use brainlib::util::read_byte_or_eof;
+/// assert_eq!(read_byte_or_eof("A".as_bytes()), Some(Some(b'A')));
+/// assert_eq!(read_byte_or_eof("".as_bytes()), Some(None));
+/// ```
+#[must_use]
+pub fn read_byte_or_eof(mut input: impl Read) -> Option<Option<u8>> {
     let mut buffer = [0];
     match input.read_exact(&mut buffer).map_err(|e| e.kind()) {
-        Ok(_) => Some(buffer[0]),
-        Err(ErrorKind::UnexpectedEof) => Some(0),
+        Ok(_) => Some(Some(buffer[0])),
+        Err(ErrorKind::UnexpectedEof) => Some(None),
         _ => None,
     }
 }
@@ -18,4 +18,4 @@
 
 mod io;
 
-pub use io::{read_byte, write_byte};
+pub use io::{read_byte, read_byte_or_eof, write_byte};
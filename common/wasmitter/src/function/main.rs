@@ -1,7 +1,7 @@
 use crate::{
     indices::{FuncIdx, TypeIdx},
     instruction::Expr,
-    internal::{FuncUid, WasmIndex},
+    internal::{encode_u32, encode_vec, FuncUid, WasmIndex},
     module::Module,
     types::ValType,
     WasmError,
@@ -77,4 +77,26 @@ impl Func {
 
         result
     }
+
+    #[must_use]
+    pub(crate) fn emit_wasm(&self, module: &Module) -> Vec<u8> {
+        let mut locals: Vec<(u32, &ValType)> = Vec::new();
+        for local in &self.locals {
+            match locals.last_mut() {
+                Some((count, val_type)) if *val_type == local => *count += 1,
+                _ => locals.push((1, local)),
+            }
+        }
+
+        let func =
+            [
+                encode_vec(locals.into_iter().map(|(count, val_type)| {
+                    [encode_u32(count), vec![val_type.emit_wasm()]].concat()
+                })),
+                self.body.emit_wasm(module, self),
+            ]
+            .concat();
+
+        [encode_u32(func.len() as u32), func].concat()
+    }
 }
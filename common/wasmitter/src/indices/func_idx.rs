@@ -69,7 +69,6 @@ impl FuncIdx {
 impl<'a> WasmIndex<'a> for FuncIdx {
     type Ctx = &'a Module;
 
-    #[must_use]
     fn resolve(&self, module: &'a Module) -> u32 {
         self.kind.resolve(module.func_import_count())
     }
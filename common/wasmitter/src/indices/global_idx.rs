@@ -63,7 +63,6 @@ impl GlobalIdx {
 impl<'a> WasmIndex<'a> for GlobalIdx {
     type Ctx = &'a Module;
 
-    #[must_use]
     fn resolve(&self, module: &'a Module) -> u32 {
         self.kind.resolve(module.global_import_count())
     }
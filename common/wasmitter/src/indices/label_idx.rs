@@ -31,7 +31,6 @@ impl LabelIdx {
 impl<'a> WasmIndex<'a> for LabelIdx {
     type Ctx = ();
 
-    #[must_use]
     fn resolve(&self, _: ()) -> u32 {
         self.0
     }
@@ -53,7 +53,6 @@ impl LocalIdx {
 impl<'a> WasmIndex<'a> for LocalIdx {
     type Ctx = (&'a Module, &'a Func);
 
-    #[must_use]
     fn resolve(&self, (module, func): (&'a Module, &'a Func)) -> u32 {
         let func_type = module.get_signature(func.type_idx());
         match self.kind {
@@ -63,7 +63,6 @@ impl MemIdx {
 impl<'a> WasmIndex<'a> for MemIdx {
     type Ctx = &'a Module;
 
-    #[must_use]
     fn resolve(&self, module: &'a Module) -> u32 {
         self.kind.resolve(module.mem_import_count())
     }
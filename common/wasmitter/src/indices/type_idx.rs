@@ -30,7 +30,6 @@ impl TypeIdx {
 impl<'a> WasmIndex<'a> for TypeIdx {
     type Ctx = &'a Module;
 
-    #[must_use]
     fn resolve(&self, _: &'a Module) -> u32 {
         self.index
     }
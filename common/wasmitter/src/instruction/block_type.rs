@@ -1,5 +1,6 @@
 use crate::{
     indices::TypeIdx,
+    internal::{encode_i64, WasmIndex},
     module::Module,
     types::{FuncType, ResultType, ValType},
 };
@@ -64,4 +65,13 @@ impl BlockType {
 
         func_type.emit_wat_inline()
     }
+
+    #[must_use]
+    pub(crate) fn emit_wasm(&self, module: &Module) -> Vec<u8> {
+        match &self.0 {
+            BlockTypeKind::Type(type_idx) => encode_i64(type_idx.resolve(module).into()),
+            BlockTypeKind::Val(Some(val_type)) => vec![val_type.emit_wasm()],
+            BlockTypeKind::Val(None) => vec![0x40],
+        }
+    }
 }
@@ -1,5 +1,6 @@
 use crate::{
     instruction::Instr,
+    internal::{encode_i32, encode_i64},
     types::{ValType, F32, F64, I32, I64},
 };
 
@@ -51,6 +52,16 @@ impl ConstInstr {
             Self::F64Const(val) => format!("(f64.const {val})"),
         }
     }
+
+    #[must_use]
+    pub(crate) fn emit_wasm(&self) -> Vec<u8> {
+        match self {
+            Self::I32Const(val) => [vec![0x41], encode_i32(*val as i32)].concat(),
+            Self::I64Const(val) => [vec![0x42], encode_i64(*val as i64)].concat(),
+            Self::F32Const(val) => [vec![0x43], val.to_le_bytes().to_vec()].concat(),
+            Self::F64Const(val) => [vec![0x44], val.to_le_bytes().to_vec()].concat(),
+        }
+    }
 }
 
 impl From<ConstInstr> for Instr {
@@ -54,4 +54,16 @@ impl Expr {
             .map(|instr| instr.emit_wat_block(module, func, indent))
             .collect()
     }
+
+    #[must_use]
+    pub(crate) fn emit_wasm(&self, module: &Module, func: &Func) -> Vec<u8> {
+        [
+            self.0
+                .iter()
+                .flat_map(|instr| instr.emit_wasm(module, func))
+                .collect(),
+            vec![0x0B],
+        ]
+        .concat()
+    }
 }
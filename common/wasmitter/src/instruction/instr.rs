@@ -3,10 +3,28 @@ use crate::{
     function::Func,
     indices::{FuncIdx, GlobalIdx, LabelIdx, LocalIdx},
     instruction::{BlockType, Expr, MemArg, Nn, Sx},
-    internal::WasmIndex,
+    internal::{encode_i32, encode_i64, encode_u32, WasmIndex},
     module::Module,
 };
 
+// Picks the opcode variant for the given width.
+#[must_use]
+const fn by_nn(nn: Nn, op32: u8, op64: u8) -> u8 {
+    match nn {
+        Nn::N32 => op32,
+        Nn::N64 => op64,
+    }
+}
+
+// Picks the opcode variant for the given signedness, the unsigned one always follows the signed one.
+#[must_use]
+const fn by_sx(sx: Sx, op_s: u8) -> u8 {
+    match sx {
+        Sx::S => op_s,
+        Sx::U => op_s + 1,
+    }
+}
+
 /// Supported WebAssembly instructions.
 ///
 /// Non-exhaustive, since new instructions may get supported in the future.
@@ -332,4 +350,123 @@ impl Instr {
             }
         )
     }
+
+    #[must_use]
+    pub(crate) fn emit_wasm(&self, module: &Module, func: &Func) -> Vec<u8> {
+        match self {
+            Instr::I32Const(val) => [vec![0x41], encode_i32(*val as i32)].concat(),
+            Instr::I64Const(val) => [vec![0x42], encode_i64(*val as i64)].concat(),
+            Instr::F32Const(val) => [vec![0x43], val.to_le_bytes().to_vec()].concat(),
+            Instr::F64Const(val) => [vec![0x44], val.to_le_bytes().to_vec()].concat(),
+            Instr::IClz(nn) => vec![by_nn(*nn, 0x67, 0x79)],
+            Instr::ICtz(nn) => vec![by_nn(*nn, 0x68, 0x7A)],
+            Instr::IPopcnt(nn) => vec![by_nn(*nn, 0x69, 0x7B)],
+            Instr::FAbs(nn) => vec![by_nn(*nn, 0x8B, 0x99)],
+            Instr::FNeg(nn) => vec![by_nn(*nn, 0x8C, 0x9A)],
+            Instr::FSqrt(nn) => vec![by_nn(*nn, 0x91, 0x9F)],
+            Instr::FCeil(nn) => vec![by_nn(*nn, 0x8D, 0x9B)],
+            Instr::FFloor(nn) => vec![by_nn(*nn, 0x8E, 0x9C)],
+            Instr::FTrunc(nn) => vec![by_nn(*nn, 0x8F, 0x9D)],
+            Instr::FNearest(nn) => vec![by_nn(*nn, 0x90, 0x9E)],
+            Instr::IAdd(nn) => vec![by_nn(*nn, 0x6A, 0x7C)],
+            Instr::ISub(nn) => vec![by_nn(*nn, 0x6B, 0x7D)],
+            Instr::IMul(nn) => vec![by_nn(*nn, 0x6C, 0x7E)],
+            Instr::IDiv(nn, sx) => vec![by_sx(*sx, by_nn(*nn, 0x6D, 0x7F))],
+            Instr::IRem(nn, sx) => vec![by_sx(*sx, by_nn(*nn, 0x6F, 0x81))],
+            Instr::IAnd(nn) => vec![by_nn(*nn, 0x71, 0x83)],
+            Instr::IOr(nn) => vec![by_nn(*nn, 0x72, 0x84)],
+            Instr::IXor(nn) => vec![by_nn(*nn, 0x73, 0x85)],
+            Instr::IShl(nn) => vec![by_nn(*nn, 0x74, 0x86)],
+            Instr::IShr(nn, sx) => vec![by_sx(*sx, by_nn(*nn, 0x75, 0x87))],
+            Instr::IRotl(nn) => vec![by_nn(*nn, 0x77, 0x89)],
+            Instr::IRotr(nn) => vec![by_nn(*nn, 0x78, 0x8A)],
+            Instr::FAdd(nn) => vec![by_nn(*nn, 0x92, 0xA0)],
+            Instr::FSub(nn) => vec![by_nn(*nn, 0x93, 0xA1)],
+            Instr::FMul(nn) => vec![by_nn(*nn, 0x94, 0xA2)],
+            Instr::FDiv(nn) => vec![by_nn(*nn, 0x95, 0xA3)],
+            Instr::FMin(nn) => vec![by_nn(*nn, 0x96, 0xA4)],
+            Instr::FMax(nn) => vec![by_nn(*nn, 0x97, 0xA5)],
+            Instr::FCopysign(nn) => vec![by_nn(*nn, 0x98, 0xA6)],
+            Instr::IEqz(nn) => vec![by_nn(*nn, 0x45, 0x50)],
+            Instr::IEq(nn) => vec![by_nn(*nn, 0x46, 0x51)],
+            Instr::INe(nn) => vec![by_nn(*nn, 0x47, 0x52)],
+            Instr::ILt(nn, sx) => vec![by_sx(*sx, by_nn(*nn, 0x48, 0x53))],
+            Instr::IGt(nn, sx) => vec![by_sx(*sx, by_nn(*nn, 0x4A, 0x55))],
+            Instr::ILe(nn, sx) => vec![by_sx(*sx, by_nn(*nn, 0x4C, 0x57))],
+            Instr::IGe(nn, sx) => vec![by_sx(*sx, by_nn(*nn, 0x4E, 0x59))],
+            Instr::FEq(nn) => vec![by_nn(*nn, 0x5B, 0x61)],
+            Instr::FNe(nn) => vec![by_nn(*nn, 0x5C, 0x62)],
+            Instr::FLt(nn) => vec![by_nn(*nn, 0x5D, 0x63)],
+            Instr::FGt(nn) => vec![by_nn(*nn, 0x5E, 0x64)],
+            Instr::FLe(nn) => vec![by_nn(*nn, 0x5F, 0x65)],
+            Instr::FGe(nn) => vec![by_nn(*nn, 0x60, 0x66)],
+            Instr::IExtend8S(nn) => vec![by_nn(*nn, 0xC0, 0xC2)],
+            Instr::IExtend16S(nn) => vec![by_nn(*nn, 0xC1, 0xC3)],
+            Instr::I64Extend32S => vec![0xC4],
+            Instr::I32WrapI64 => vec![0xA7],
+            Instr::I64ExtendI32(sx) => vec![by_sx(*sx, 0xAC)],
+            Instr::ITruncF(nn, mm, sx) => {
+                vec![by_sx(*sx, by_nn(*nn, 0xA8, 0xAE) + by_nn(*mm, 0, 2))]
+            },
+            Instr::ITruncSatF(nn, mm, sx) => [
+                vec![0xFC],
+                encode_u32(by_sx(*sx, by_nn(*nn, 0, 4) + by_nn(*mm, 0, 2)).into()),
+            ]
+            .concat(),
+            Instr::F32DemoteF64 => vec![0xB6],
+            Instr::F64PromoteF32 => vec![0xBB],
+            Instr::FConvertI(nn, mm, sx) => {
+                vec![by_sx(*sx, by_nn(*nn, 0xB2, 0xB7) + by_nn(*mm, 0, 2))]
+            },
+            Instr::IReinterpretF(nn) => vec![by_nn(*nn, 0xBC, 0xBD)],
+            Instr::FReinterpretI(nn) => vec![by_nn(*nn, 0xBE, 0xBF)],
+            Instr::Drop => vec![0x1A],
+            Instr::Select => vec![0x1B],
+            Instr::LocalGet(idx) => [vec![0x20], encode_u32(idx.resolve((module, func)))].concat(),
+            Instr::LocalSet(idx) => [vec![0x21], encode_u32(idx.resolve((module, func)))].concat(),
+            Instr::LocalTee(idx) => [vec![0x22], encode_u32(idx.resolve((module, func)))].concat(),
+            Instr::GlobalGet(idx) => [vec![0x23], encode_u32(idx.resolve(module))].concat(),
+            Instr::GlobalSet(idx) => [vec![0x24], encode_u32(idx.resolve(module))].concat(),
+            Instr::I32Load(memarg) => [vec![0x28], memarg.emit_wasm()].concat(),
+            Instr::I64Load(memarg) => [vec![0x29], memarg.emit_wasm()].concat(),
+            Instr::F32Load(memarg) => [vec![0x2A], memarg.emit_wasm()].concat(),
+            Instr::F64Load(memarg) => [vec![0x2B], memarg.emit_wasm()].concat(),
+            Instr::I32Store(memarg) => [vec![0x36], memarg.emit_wasm()].concat(),
+            Instr::I64Store(memarg) => [vec![0x37], memarg.emit_wasm()].concat(),
+            Instr::F32Store(memarg) => [vec![0x38], memarg.emit_wasm()].concat(),
+            Instr::F64Store(memarg) => [vec![0x39], memarg.emit_wasm()].concat(),
+            Instr::ILoad8(nn, sx, memarg) =>
+                [vec![by_sx(*sx, by_nn(*nn, 0x2C, 0x30))], memarg.emit_wasm()].concat(),
+            Instr::ILoad16(nn, sx, memarg) =>
+                [vec![by_sx(*sx, by_nn(*nn, 0x2E, 0x32))], memarg.emit_wasm()].concat(),
+            Instr::I64Load32(sx, memarg) => [vec![by_sx(*sx, 0x34)], memarg.emit_wasm()].concat(),
+            Instr::IStore8(nn, memarg) =>
+                [vec![by_nn(*nn, 0x3A, 0x3C)], memarg.emit_wasm()].concat(),
+            Instr::IStore16(nn, memarg) =>
+                [vec![by_nn(*nn, 0x3B, 0x3D)], memarg.emit_wasm()].concat(),
+            Instr::I64Store32(memarg) => [vec![0x3E], memarg.emit_wasm()].concat(),
+            Instr::MemorySize => vec![0x3F, 0x00],
+            Instr::MemoryGrow => vec![0x40, 0x00],
+            Instr::MemoryFill => vec![0xFC, 0x0B, 0x00],
+            Instr::MemoryCopy => vec![0xFC, 0x0A, 0x00, 0x00],
+            Instr::Nop => vec![0x01],
+            Instr::Unreachable => vec![0x00],
+            Instr::Block(block_type, instrs) => [
+                vec![0x02],
+                block_type.emit_wasm(module),
+                Expr(instrs.clone()).emit_wasm(module, func),
+            ]
+            .concat(),
+            Instr::Loop(block_type, instrs) => [
+                vec![0x03],
+                block_type.emit_wasm(module),
+                Expr(instrs.clone()).emit_wasm(module, func),
+            ]
+            .concat(),
+            Instr::Br(idx) => [vec![0x0C], encode_u32(idx.resolve(()))].concat(),
+            Instr::BrIf(idx) => [vec![0x0D], encode_u32(idx.resolve(()))].concat(),
+            Instr::Return => vec![0x0F],
+            Instr::Call(idx) => [vec![0x10], encode_u32(idx.resolve(module))].concat(),
+        }
+    }
 }
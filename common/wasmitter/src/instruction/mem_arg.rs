@@ -1,8 +1,16 @@
 use std::fmt::{self, Display, Formatter};
 
+use crate::internal::encode_u32;
+
 /// Defines the offset and alignment of a memory operation.
 ///
-/// **NOTE:** Currently, only the default alignment (`offset=0 align=0`) is supported.
+/// The offset is added to the address operand to obtain the effective address.
+///
+/// **NOTE:** Currently, only the default (natural) alignment is supported.
+///
+/// # Specification
+/// - [Memory Instructions - Structure](https://webassembly.github.io/spec/core/syntax/instructions.html#memory-instructions)
+/// - [Memory Instructions - Text Format](https://webassembly.github.io/spec/core/text/instructions.html#memory-instructions)
 #[must_use]
 #[derive(Debug, Default, Clone, Copy)]
 pub struct MemArg<const N: usize> {
@@ -17,6 +25,29 @@ impl<const N: usize> MemArg<N> {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Creates a new `MemArg` with the given static offset and the default alignment.
+    ///
+    /// # Examples
+    /// ```
+    /// # use wasmitter::instruction::MemArg;
+    /// assert_eq!(MemArg::<1>::with_offset(4).to_string(), " offset=4");
+    /// ```
+    pub fn with_offset(offset: u32) -> Self {
+        Self {
+            offset,
+            ..Self::default()
+        }
+    }
+
+    #[must_use]
+    pub(crate) fn emit_wasm(&self) -> Vec<u8> {
+        let align = match self.align {
+            0 => N.trailing_zeros(),
+            align => align.trailing_zeros(),
+        };
+        [encode_u32(align), encode_u32(self.offset)].concat()
+    }
 }
 
 impl<const N: usize> Display for MemArg<N> {
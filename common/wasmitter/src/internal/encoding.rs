@@ -0,0 +1,83 @@
+#[must_use]
+pub(crate) fn encode_u32(mut value: u32) -> Vec<u8> {
+    let mut result = Vec::new();
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            result.push(byte);
+            return result;
+        }
+        result.push(byte | 0x80);
+    }
+}
+
+#[must_use]
+pub(crate) fn encode_i32(value: i32) -> Vec<u8> {
+    encode_i64(value.into())
+}
+
+#[must_use]
+pub(crate) fn encode_i64(mut value: i64) -> Vec<u8> {
+    let mut result = Vec::new();
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        let sign_bit_clear = byte & 0x40 == 0;
+        if (value == 0 && sign_bit_clear) || (value == -1 && !sign_bit_clear) {
+            result.push(byte);
+            return result;
+        }
+        result.push(byte | 0x80);
+    }
+}
+
+#[must_use]
+pub(crate) fn encode_name(name: &str) -> Vec<u8> {
+    [encode_u32(name.len() as u32), name.as_bytes().to_vec()].concat()
+}
+
+#[must_use]
+pub(crate) fn encode_vec(items: impl IntoIterator<Item = Vec<u8>>) -> Vec<u8> {
+    let items = items.into_iter().collect::<Vec<_>>();
+    [encode_u32(items.len() as u32), items.concat()].concat()
+}
+
+#[must_use]
+pub(crate) fn encode_section(id: u8, contents: Vec<u8>) -> Vec<u8> {
+    [vec![id], encode_u32(contents.len() as u32), contents].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::{encode_i32, encode_i64, encode_u32};
+
+    #[test_case(0, &[0x00]; "zero")]
+    #[test_case(127, &[0x7F]; "largest single byte")]
+    #[test_case(128, &[0x80, 0x01]; "smallest two bytes")]
+    #[test_case(624_485, &[0xE5, 0x8E, 0x26]; "three bytes")]
+    #[test_case(u32::MAX, &[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]; "max")]
+    fn encodes_unsigned(value: u32, bytes: &[u8]) {
+        assert_eq!(encode_u32(value), bytes);
+    }
+
+    #[test_case(0, &[0x00]; "zero")]
+    #[test_case(-1, &[0x7F]; "minus one")]
+    #[test_case(63, &[0x3F]; "largest single byte")]
+    #[test_case(64, &[0xC0, 0x00]; "positive with sign bit set")]
+    #[test_case(-64, &[0x40]; "smallest single byte")]
+    #[test_case(-65, &[0xBF, 0x7F]; "largest negative two bytes")]
+    #[test_case(-123_456, &[0xC0, 0xBB, 0x78]; "three bytes")]
+    #[test_case(i32::MIN.into(), &[0x80, 0x80, 0x80, 0x80, 0x78]; "i32 min")]
+    #[test_case(i32::MAX.into(), &[0xFF, 0xFF, 0xFF, 0xFF, 0x07]; "i32 max")]
+    #[test_case(i64::MIN, &[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x7F]; "i64 min")]
+    #[test_case(i64::MAX, &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00]; "i64 max")]
+    fn encodes_signed(value: i64, bytes: &[u8]) {
+        assert_eq!(encode_i64(value), bytes);
+        if let Ok(value) = i32::try_from(value) {
+            assert_eq!(encode_i32(value), bytes);
+        }
+    }
+}
@@ -1,8 +1,13 @@
+mod encoding;
 mod func_uid;
 mod index_kind;
 mod module_uid;
 mod wasm_index;
 
 pub(crate) use self::{
-    func_uid::FuncUid, index_kind::IndexKind, module_uid::ModuleUid, wasm_index::WasmIndex,
+    encoding::{encode_i32, encode_i64, encode_name, encode_section, encode_u32, encode_vec},
+    func_uid::FuncUid,
+    index_kind::IndexKind,
+    module_uid::ModuleUid,
+    wasm_index::WasmIndex,
 };
@@ -1,6 +1,6 @@
 use crate::{
     indices::{FuncIdx, GlobalIdx, MemIdx},
-    internal::WasmIndex,
+    internal::{encode_name, encode_u32, WasmIndex},
     module::Module,
 };
 
@@ -69,6 +69,15 @@ impl ExportDesc {
             ExportDescKind::Global(idx) => format!("(global {})", idx.id_or_index(module)),
         }
     }
+
+    #[must_use]
+    fn emit_wasm(&self, module: &Module) -> Vec<u8> {
+        match self.0 {
+            ExportDescKind::Func(idx) => [vec![0x00], encode_u32(idx.resolve(module))].concat(),
+            ExportDescKind::Mem(idx) => [vec![0x02], encode_u32(idx.resolve(module))].concat(),
+            ExportDescKind::Global(idx) => [vec![0x03], encode_u32(idx.resolve(module))].concat(),
+        }
+    }
 }
 
 #[must_use]
@@ -88,4 +97,9 @@ impl Export {
             self.desc.emit_wat_inline(module)
         )
     }
+
+    #[must_use]
+    pub(crate) fn emit_wasm(&self, module: &Module) -> Vec<u8> {
+        [encode_name(&self.name), self.desc.emit_wasm(module)].concat()
+    }
 }
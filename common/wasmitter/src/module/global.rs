@@ -43,4 +43,14 @@ impl Global {
             self.init.emit_wat_inline(),
         )
     }
+
+    #[must_use]
+    pub(crate) fn emit_wasm(&self) -> Vec<u8> {
+        [
+            self.global_type.emit_wasm(),
+            self.init.emit_wasm(),
+            vec![0x0B],
+        ]
+        .concat()
+    }
 }
@@ -1,6 +1,6 @@
 use crate::{
     indices::{FuncIdx, GlobalIdx, MemIdx, TypeIdx},
-    internal::WasmIndex,
+    internal::{encode_name, encode_u32, WasmIndex},
     module::Module,
     types::{GlobalType, MemType, Mut, ValType},
     WasmError,
@@ -55,6 +55,17 @@ impl ImportDesc {
             },
         }
     }
+
+    #[must_use]
+    fn emit_wasm(&self, module: &Module) -> Vec<u8> {
+        match self {
+            ImportDesc::Func { type_idx, .. } =>
+                [vec![0x00], encode_u32(type_idx.resolve(module))].concat(),
+            ImportDesc::Mem { mem_type, .. } => [vec![0x02], mem_type.emit_wasm()].concat(),
+            ImportDesc::Global { global_type, .. } =>
+                [vec![0x03], global_type.emit_wasm()].concat(),
+        }
+    }
 }
 
 #[must_use]
@@ -133,4 +144,14 @@ impl Import {
             self.desc.emit_wat_inline(module)
         )
     }
+
+    #[must_use]
+    pub(crate) fn emit_wasm(&self, module: &Module) -> Vec<u8> {
+        [
+            encode_name(&self.module),
+            encode_name(&self.name),
+            self.desc.emit_wasm(module),
+        ]
+        .concat()
+    }
 }
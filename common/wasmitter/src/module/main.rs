@@ -3,7 +3,7 @@ use crate::{
     function::{Func, FuncScope},
     indices::{FuncIdx, GlobalIdx, MemIdx, TypeIdx},
     instruction::{ConstInstr, Expr},
-    internal::{ModuleUid, WasmIndex},
+    internal::{encode_section, encode_u32, encode_vec, ModuleUid, WasmIndex},
     module::{Export, ExportDesc, Global, Import, Mem},
     text::Id,
     types::{FuncType, Limits, Mut, ResultType, ValType},
@@ -102,7 +102,7 @@ impl Module {
     ///
     /// Returns the first error found, or `None` if the module is valid.
     ///
-    /// Validation is ran automatically when calling [`Module::to_wat`].
+    /// Validation is ran automatically when calling [`Module::to_wat`] or [`Module::to_wasm`].
     #[must_use]
     pub fn validate(&self) -> Option<WasmError> {
         for func in &self.funcs {
@@ -174,6 +174,65 @@ impl Module {
         result.push_str(")\n");
         Ok(result)
     }
+
+    /// Emits the module in the WebAssembly binary format.
+    ///
+    /// The sections are emitted in the order required by the specification:
+    /// 1. Types
+    /// 2. Imports
+    /// 3. Functions
+    /// 4. Memories
+    /// 5. Globals
+    /// 6. Exports
+    /// 7. Code
+    ///
+    /// Empty sections are omitted.
+    ///
+    /// # Errors
+    /// Returns the first [`WasmError`] found, if any.
+    ///
+    /// # Examples
+    /// ```
+    /// # use wasmitter::Module;
+    /// let module = Module::new();
+    /// assert_eq!(module.to_wasm()?, b"\0asm\x01\0\0\0");
+    /// # Ok::<(), wasmitter::WasmError>(())
+    /// ```
+    ///
+    /// # Specification
+    /// - [Modules - Binary Format](https://webassembly.github.io/spec/core/binary/modules.html)
+    pub fn to_wasm(&self) -> Result<Vec<u8>, WasmError> {
+        if let Some(error) = self.validate() {
+            return Err(error);
+        }
+
+        let section = |id: u8, items: Vec<Vec<u8>>| {
+            if items.is_empty() {
+                Vec::new()
+            } else {
+                encode_section(id, encode_vec(items))
+            }
+        };
+
+        Ok([
+            b"\0asm".to_vec(),
+            vec![0x01, 0x00, 0x00, 0x00],
+            section(1, self.types.iter().map(FuncType::emit_wasm).collect()),
+            section(2, self.imports.iter().map(|i| i.emit_wasm(self)).collect()),
+            section(
+                3,
+                self.funcs
+                    .iter()
+                    .map(|f| encode_u32(f.type_idx().resolve(self)))
+                    .collect(),
+            ),
+            section(5, self.mems.iter().map(Mem::emit_wasm).collect()),
+            section(6, self.globals.iter().map(Global::emit_wasm).collect()),
+            section(7, self.exports.iter().map(|e| e.emit_wasm(self)).collect()),
+            section(10, self.funcs.iter().map(|f| f.emit_wasm(self)).collect()),
+        ]
+        .concat())
+    }
 }
 
 /// # Functions section
@@ -26,4 +26,9 @@ impl Mem {
             self.mem_type.emit_wat_inline()
         )
     }
+
+    #[must_use]
+    pub(crate) fn emit_wasm(&self) -> Vec<u8> {
+        self.mem_type.emit_wasm()
+    }
 }
@@ -23,10 +23,19 @@ impl FuncType {
             construct_part(&self.params, "param"),
             construct_part(&self.results, "result"),
         ]
-        .iter()
-        .cloned()
+        .into_iter()
         .filter(|s| !s.is_empty())
         .collect::<Vec<String>>()
         .join(" ")
     }
+
+    #[must_use]
+    pub(crate) fn emit_wasm(&self) -> Vec<u8> {
+        [
+            vec![0x60],
+            self.params.emit_wasm(),
+            self.results.emit_wasm(),
+        ]
+        .concat()
+    }
 }
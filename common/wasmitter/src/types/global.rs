@@ -30,4 +30,13 @@ impl GlobalType {
             Mut::Var => format!("(mut {})", self.val_type.emit_wat_inline()),
         }
     }
+
+    #[must_use]
+    pub(crate) fn emit_wasm(&self) -> Vec<u8> {
+        let mutability = match self.mutability {
+            Mut::Const => 0x00,
+            Mut::Var => 0x01,
+        };
+        vec![self.val_type.emit_wasm(), mutability]
+    }
 }
@@ -1,4 +1,4 @@
-use crate::WasmError;
+use crate::{internal::encode_u32, WasmError};
 
 /// A pair of minimum and an optional maximum value.
 ///
@@ -37,6 +37,14 @@ impl Limits {
             Some(WasmError::InvalidLimits { min, max })
         }
     }
+
+    #[must_use]
+    pub(crate) fn emit_wasm(&self) -> Vec<u8> {
+        match self.max {
+            Some(max) => [vec![0x01], encode_u32(self.min), encode_u32(max)].concat(),
+            None => [vec![0x00], encode_u32(self.min)].concat(),
+        }
+    }
 }
 
 impl From<u32> for Limits {
@@ -19,6 +19,11 @@ impl MemType {
             None => self.limits.min.to_string(),
         }
     }
+
+    #[must_use]
+    pub(crate) fn emit_wasm(&self) -> Vec<u8> {
+        self.limits.emit_wasm()
+    }
 }
 
 impl From<Limits> for MemType {
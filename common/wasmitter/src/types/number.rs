@@ -32,6 +32,16 @@ impl NumType {
         }
         .into()
     }
+
+    #[must_use]
+    pub(crate) fn emit_wasm(&self) -> u8 {
+        match self {
+            NumType::I32 => 0x7F,
+            NumType::I64 => 0x7E,
+            NumType::F32 => 0x7D,
+            NumType::F64 => 0x7C,
+        }
+    }
 }
 
 impl Display for NumType {
@@ -1,4 +1,4 @@
-use crate::types::ValType;
+use crate::{internal::encode_vec, types::ValType};
 
 /// A type classifying the results (or parameters) of a function.
 ///
@@ -40,6 +40,11 @@ impl ResultType {
             .collect::<Vec<String>>()
             .join(" ")
     }
+
+    #[must_use]
+    pub(crate) fn emit_wasm(&self) -> Vec<u8> {
+        encode_vec(self.0.iter().map(|t| vec![t.emit_wasm()]))
+    }
 }
 
 impl From<ValType> for ResultType {
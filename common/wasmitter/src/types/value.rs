@@ -22,6 +22,13 @@ impl ValType {
             ValType::Num(num) => num.emit_wat_inline(),
         }
     }
+
+    #[must_use]
+    pub(crate) fn emit_wasm(&self) -> u8 {
+        match self {
+            ValType::Num(num) => num.emit_wasm(),
+        }
+    }
 }
 
 /// A shorthand for `ValType::Num(NumType::I32)`.